@@ -13,97 +13,144 @@ include!(concat!(env!("OUT_DIR"), "/version_data.rs"));
 
 extern crate alias_lib;
 
-pub struct HybridLibraryInterface;
-
-impl AliasProvider for HybridLibraryInterface {
-    // --- 1. THE ATOMIC "HANDS" ---
+pub struct HybridLibraryInterface {
+    // Tried in order: by default native hands (kernel32), then process hands (doskey.exe)
+    providers: Vec<Box<dyn AliasProvider>>,
+}
 
-    fn raw_set_macro(name: &str, value: Option<&str>) -> io::Result<bool> {
-        match Win32LibraryInterface::raw_set_macro(name, value) {
-            // 1. Success! Return immediately.
-            Ok(true) => Ok(true),
+impl HybridLibraryInterface {
+    /// Composes any providers: the first is tried first, each later one picks up the slack.
+    /// Panics on an empty list: a hybrid needs at least one provider.
+    pub fn new(providers: Vec<Box<dyn AliasProvider>>) -> Self {
+        assert!(!providers.is_empty(), "A hybrid needs at least one provider");
+        Self { providers }
+    }
 
-            // 2. Win32 says "False" (it didn't work, but no crash). Try Wrapper.
-            Ok(false) => {
-                WrapperLibraryInterface::raw_set_macro(name, value)
-            },
+    // Writes to the registry, diagnostics and the like have one owner: the first in line
+    fn lead(&self) -> &dyn AliasProvider {
+        self.providers[0].as_ref()
+    }
 
-            // 3. HARD ERROR: The Win32 API actually failed/errored out.
+    // The table a given kind of provider sees, for the audit columns (empty if none is composed)
+    fn aliases_of(&self, kind: ProviderType, verbosity: &Verbosity) -> Vec<(String, String)> {
+        let Some(provider) = self.providers.iter().find(|p| p.provider_type() == kind) else { return Vec::new(); };
+        match provider.get_all_aliases(verbosity) {
+            Ok(list) => list,
             Err(e) => {
-                // Check if it's a "safe" error to ignore (like the API not being available)
-                // Otherwise, PERCOLATE the error up.
-                if e.kind() == io::ErrorKind::Unsupported {
-                    WrapperLibraryInterface::raw_set_macro(name, value)
-                } else {
-                    // Return the actual error so the user knows WHY it failed.
-                    Err(e)
-                }
+                let err_struct = failure!(verbosity, e);
+                shout!(verbosity, AliasIcon::Fail, "{}", err_struct.message);
+                Vec::new()
             }
         }
     }
+}
 
-    fn raw_reload_from_file(verbosity: &Verbosity, path: &Path) -> io::Result<()> {
-        // Try native reload, fallback to process spawn if it fails
-        if Win32LibraryInterface::raw_reload_from_file(verbosity, path).is_err() {
-            return WrapperLibraryInterface::raw_reload_from_file(verbosity, path);
+impl Default for HybridLibraryInterface {
+    fn default() -> Self {
+        let win32: Box<dyn AliasProvider> = Box::new(Win32LibraryInterface::default());
+        let wrapper: Box<dyn AliasProvider> = Box::new(WrapperLibraryInterface::default());
+        // `provider = "wrapper"` in alias.toml puts doskey.exe first
+        if config::config_value("provider").as_deref() == Some("wrapper") {
+            return Self::new(vec![wrapper, win32]);
         }
-        Ok(())
+        Self::new(vec![win32, wrapper])
+    }
+}
+
+impl AliasProvider for HybridLibraryInterface {
+    // --- 1. THE ATOMIC "HANDS" ---
+
+    fn raw_set_macro(&self, name: &str, value: Option<&str>) -> io::Result<bool> {
+        let mut outcome = Ok(false);
+        for provider in &self.providers {
+            outcome = provider.raw_set_macro(name, value);
+            match &outcome {
+                // 1. Success! Return immediately.
+                Ok(true) => break,
+                // 2. "False" (it didn't work, but no crash). Try the next one.
+                Ok(false) => continue,
+                // 3. Only a "safe" error (the API not being available) moves on;
+                // anything else PERCOLATES up so the user knows WHY it failed.
+                Err(e) if e.kind() == io::ErrorKind::Unsupported => continue,
+                Err(_) => break,
+            }
+        }
+        outcome
     }
 
-    fn get_all_aliases(verbosity: &Verbosity) -> io::Result<Vec<(String, String)>> {
-        // If Win32 returns our "Alert String" (Error 203), try the wrapper.
-        let w32_res = Win32LibraryInterface::get_all_aliases(verbosity)?;
+    fn raw_reload_from_file(&self, verbosity: &Verbosity, path: &Path) -> io::Result<()> {
+        // Try native reload, fall through to process spawn if it fails
+        let mut outcome = Ok(());
+        for provider in &self.providers {
+            outcome = provider.raw_reload_from_file(verbosity, path);
+            if outcome.is_ok() { break; }
+        }
+        outcome
+    }
 
-        // If the Win32 list only contains our Alert Message, it's "effectively" empty.
-        if w32_res.is_empty() || (w32_res.len() == 1 && w32_res[0].0.contains("found in Win32 RAM")) {
-            return WrapperLibraryInterface::get_all_aliases(verbosity);
+    fn get_all_aliases(&self, verbosity: &Verbosity) -> io::Result<Vec<(String, String)>> {
+        let mut list = Vec::new();
+        for provider in &self.providers {
+            list = provider.get_all_aliases(verbosity)?;
+            // A list holding only Win32's "Alert String" (Error 203) is "effectively" empty
+            let effectively_empty = list.is_empty() || (list.len() == 1 && list[0].0.contains("found in Win32 RAM"));
+            if !effectively_empty { break; }
         }
-        Ok(w32_res)
+        Ok(list)
     }
 
-    fn write_autorun_registry(cmd: &str, v: &Verbosity) -> io::Result<()> {
-        Win32LibraryInterface::write_autorun_registry(cmd, v)
+    fn write_autorun_registry(&self, cmd: &str, v: &Verbosity) -> io::Result<()> {
+        self.lead().write_autorun_registry(cmd, v)
     }
 
-    fn read_autorun_registry() -> String {
-        Win32LibraryInterface::read_autorun_registry()
+    fn read_autorun_registry(&self) -> String {
+        self.lead().read_autorun_registry()
     }
 
     // --- 2. THE CENTRALIZED LOGIC ---
 
-    fn purge_ram_macros(verbosity: &Verbosity) -> io::Result<PurgeReport> {
-        let report = Win32LibraryInterface::purge_ram_macros(verbosity)?;
-        // If report has failures, let the Wrapper try to finish the job
-        if !report.failed.is_empty() {
-            return WrapperLibraryInterface::purge_ram_macros(verbosity);
+    fn purge_ram_macros(&self, verbosity: &Verbosity) -> io::Result<PurgeReport> {
+        let mut report = PurgeReport::default();
+        for provider in &self.providers {
+            report = provider.purge_ram_macros(verbosity)?;
+            // If report has failures, let the next one try to finish the job
+            if report.failed.is_empty() { break; }
         }
         Ok(report)
     }
 
-    fn query_alias(name: &str, verbosity: &Verbosity) -> Vec<String> {
-        let output = Win32LibraryInterface::query_alias(name, verbosity);
+    fn query_alias(&self, name: &str, verbosity: &Verbosity) -> Vec<String> {
+        let not_found = |output: &[String]| output.first().is_some_and(|s| s.contains("not found") || s.contains("not a known alias"));
+        let output = self.lead().query_alias(name, verbosity);
 
-        // If Win32 returns the "not found" alert, ask the wrapper
-        if output.get(0).map_or(false, |s| s.contains("not found") || s.contains("not a known alias")) {
-            let wrap_output = WrapperLibraryInterface::query_alias(name, verbosity);
-            if !wrap_output.is_empty() {
-                return wrap_output;
+        // If the first returns the "not found" alert, ask the others
+        if not_found(&output) {
+            for provider in &self.providers[1..] {
+                let other = provider.query_alias(name, verbosity);
+                if !other.is_empty() && !not_found(&other) {
+                    return other;
+                }
             }
         }
         output
     }
 
-    fn set_alias(opts: SetOptions, path: &Path, verbosity: &Verbosity) -> io::Result<()> {
+    fn set_alias(&self, opts: SetOptions, path: &Path, verbosity: &Verbosity) -> io::Result<()> {
         let name = if opts.force_case { opts.name.clone() } else { opts.name.to_lowercase() };
         let val_opt = if opts.value.is_empty() { None } else { Some(opts.value.as_str()) };
 
-        // Attempt API strike first
-        if Win32LibraryInterface::raw_set_macro(&name, val_opt).is_err() {
-            WrapperLibraryInterface::set_alias(opts.clone(), path, verbosity)?;
-        } else if !opts.volatile {
-            // Update disk only if RAM strike was accepted and not volatile
-            update_disk_file(verbosity, &name, &opts.value, path)
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        // Attempt a raw strike with every provider but the last, which gets the whole job if none lands
+        let (last, strikers) = self.providers.split_last().expect("new() refuses an empty provider list");
+        if !strikers.iter().any(|p| p.raw_set_macro(&name, val_opt).is_ok()) {
+            last.set_alias(opts.clone(), path, verbosity)?;
+        } else {
+            // The last provider warns on its own path, so only the raw strike reports here
+            warn_macro_issues(verbosity, &name, &opts.value);
+            if !opts.volatile {
+                // Update disk only if RAM strike was accepted and not volatile
+                update_disk_file(verbosity, &name, &opts.value, path)
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+            }
         }
 
         if opts.volatile {
@@ -112,42 +159,26 @@ impl AliasProvider for HybridLibraryInterface {
         Ok(())
     }
 
-    fn run_diagnostics(path: &Path, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
-        Win32LibraryInterface::run_diagnostics(path, verbosity)
+    fn run_diagnostics(&self, path: &Path, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
+        self.lead().run_diagnostics(path, verbosity)
     }
 
-    fn alias_show_all(verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
-        if verbosity.level == VerbosityLevel::Mute { return Ok(()); }
-
-        // 1. Try Win32
-        let w32 = match Win32LibraryInterface::get_all_aliases(verbosity) {
-            Ok(list) => list,
-            Err(e) => {
-                // MAP: Convert Box<dyn Error> into a concrete io::Error
-                let io_err = std::io::Error::new(std::io::ErrorKind::Other, e.to_string());
-                let err_struct = failure!(verbosity, io_err);
-                shout!(verbosity, AliasIcon::Fail, "{}", err_struct.message);
-                Vec::new()
-            }
-        };
+    fn alias_show_all(&self, verbosity: &Verbosity) -> Result<AuditReport, Box<dyn std::error::Error>> {
+        if verbosity.level == VerbosityLevel::Mute { return Ok(AuditReport::default()); }
 
-        // 2. Try Wrapper
-        let wrap = match WrapperLibraryInterface::get_all_aliases(verbosity) {
-            Ok(list) => list,
-            Err(e) => {
-                let io_err = std::io::Error::new(std::io::ErrorKind::Other, e.to_string());
-                let err_struct = failure!(verbosity, io_err);
-                shout!(verbosity, AliasIcon::Fail, "{}", err_struct.message);
-                Vec::new()
-            }
-        };
+        // 1. & 2. Win32 and Wrapper columns, whatever order they are tried in
+        let w32 = self.aliases_of(ProviderType::Win32, verbosity);
+        let wrap = self.aliases_of(ProviderType::Wrapper, verbosity);
 
-        // 3. Try File
+        // 3. Try File (every layer, merged; the winners are what --reload would load)
         let file = match get_alias_path("") {
-            Some(p) => match parse_macro_file(&p, verbosity) {
-                Ok(list) => list,
+            Some(p) => match load_layers(verbosity, &p) {
+                Ok(layered) => layered.into_iter()
+                    .filter(|e| is_valid_name(&e.name))
+                    .map(|e| (e.name, e.value))
+                    .collect(),
                 Err(e) => {
-                    let io_err = std::io::Error::new(std::io::ErrorKind::Other, e.to_string());
+                    let io_err = io::Error::other(e.to_string());
                     let err_struct = failure!(verbosity, io_err);
                     shout!(verbosity, AliasIcon::Fail, "File Error: {}", err_struct.message);
                     Vec::new()
@@ -157,22 +188,18 @@ impl AliasProvider for HybridLibraryInterface {
         };
 
         // 4. Final Audit
-        perform_triple_audit(verbosity, w32, wrap, file, &Self::provider_type());
-
-        Ok(())
+        Ok(perform_triple_audit(verbosity, w32, wrap, file, &self.provider_type()))
     }
-    fn provider_type() -> ProviderType {
+    fn provider_type(&self) -> ProviderType {
         ProviderType::Hybrid
     }
-    fn get_version() -> &'static Versioning {
+    fn get_version(&self) -> &'static Versioning {
         &VERSION
     }
-    fn get_versions() -> Vec<&'static Versioning> {
-        vec![
-            alias_lib::Versioning::current(),
-            WrapperLibraryInterface::get_version(),
-            Win32LibraryInterface::get_version(),
-            Self::get_version(),
-        ]
+    fn get_versions(&self) -> Vec<&'static Versioning> {
+        std::iter::once(alias_lib::Versioning::current())
+            .chain(self.providers.iter().rev().map(|p| p.get_version()))
+            .chain(std::iter::once(self.get_version()))
+            .collect()
     }
 }
@@ -10,6 +10,6 @@ fn main() {
         // The Final Scream: main() is the only one allowed to
         // print a Percolated Error to stderr.
         eprintln!("{}", e);
-        std::process::exit(1);
+        std::process::exit(e.downcast_ref::<AliasError>().map_or(1, |a| a.code as i32));
     }
 }
@@ -78,15 +78,29 @@ fn test_hybrid_fallback_logic_robust() {
     };
 
     // FIX: Replaced 'true' (bool) with 'voice!(Silent, Off, Off)'
-    HybridLibraryInterface::set_alias(opts, dummy_path, &voice!(Silent, Off, Off))
+    HybridLibraryInterface::default().set_alias(opts, dummy_path, &voice!(Silent, Off, Off))
         .expect("Internal set failed");
 
     // FIX: Replaced 'OutputMode::Silent' with 'voice!(Silent, Off, Off)'
-    let results = HybridLibraryInterface::query_alias(name, &voice!(Silent, Off, Off));
+    let results = HybridLibraryInterface::default().query_alias(name, &voice!(Silent, Off, Off));
 
     assert!(results.iter().any(|s| s.contains(val)), "Hybrid fallback logic failed internally");
 }
 
+#[test]
+fn test_hybrid_composes_an_ordered_list() {
+    let hybrid = HybridLibraryInterface::new(vec![
+        Box::new(MemoryProvider::new()),
+        Box::new(MemoryProvider::seeded(&[("gs", "git status")])),
+    ]);
+    let v = voice!(Silent, Off, Off);
+    assert_eq!(hybrid.get_all_aliases(&v).unwrap(), vec![("gs".to_string(), "git status".to_string())],
+        "An empty first table falls through to the next");
+    assert!(hybrid.raw_set_macro("ll", Some("dir")).unwrap());
+    assert_eq!(hybrid.get_all_aliases(&v).unwrap(), vec![("ll".to_string(), "dir".to_string())],
+        "A write lands on the first provider that takes it");
+}
+
 #[test]
 #[serial(console)]
 fn test_ui_audit_logic() {
@@ -102,11 +116,11 @@ fn test_ui_audit_logic() {
     };
 
     // FIX: Replaced 'true' with 'voice!(Silent, Off, Off)'
-    HybridLibraryInterface::set_alias(opts, dummy_path, &voice!(Silent, Off, Off))
+    HybridLibraryInterface::default().set_alias(opts, dummy_path, &voice!(Silent, Off, Off))
         .expect("Internal set failed");
 
     // FIX: Added required Verbosity argument
-    HybridLibraryInterface::alias_show_all(&voice!(Normal, Off, Off))
+    HybridLibraryInterface::default().alias_show_all(&voice!(Normal, Off, Off))
         .expect("UI Audit logic failed");
 
     if dummy_path.exists() {
@@ -0,0 +1,151 @@
+// alias_lib/src/apply.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Batch mode: `alias --apply changes.txt` (or `--apply -` / bare `--apply` for stdin).
+// One operation per line:
+//   gs=git status       set (or change)
+//   gl=                 remove, exactly like `alias gl=`
+//   --remove ll         remove
+//   # or ; ...          comment; blank lines are skipped
+// Every line is validated before anything is touched (a value doskey would mangle is an
+// error here, not a warning), then the file is rewritten once and RAM is updated in one pass.
+// An alias counts as unchanged only when the file and RAM both already agree with the batch.
+// If a RAM injection fails, the file and the macros already injected are put back, so a batch
+// lands completely or not at all.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use crate::*;
+
+/// One line of a batch. `value` is None for a removal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchOp {
+    pub line: usize,
+    pub name: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub added: usize,
+    pub changed: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} added, {} changed, {} removed, {} unchanged", self.added, self.changed, self.removed, self.unchanged)
+    }
+}
+
+/// Empty or `-` reads stdin.
+pub fn read_batch_source(source: &str) -> io::Result<String> {
+    if source.is_empty() || source == "-" {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        return Ok(content);
+    }
+    read_alias_text(Path::new(source))
+}
+
+/// All operations, or every problem found (`line N: ...`). Naming one alias twice is an
+/// error: a batch should say one thing per alias.
+pub fn parse_batch(content: &str) -> Result<Vec<BatchOp>, Vec<String>> {
+    let mut ops: Vec<BatchOp> = Vec::new();
+    let mut errors = Vec::new();
+    for (i, raw) in content.lines().enumerate() {
+        let line = i + 1;
+        let text = raw.trim();
+        if text.is_empty() || text.starts_with('#') || text.starts_with(';') { continue; }
+
+        let (name, value) = if let Some(rest) = text.strip_prefix("--remove") {
+            match rest.strip_prefix(char::is_whitespace).map(str::trim) {
+                Some(name) if !name.is_empty() => (name, None),
+                _ => { errors.push(format!("line {}: --remove needs a name", line)); continue; },
+            }
+        } else if let Some((name, value)) = raw.trim_start().split_once('=') {
+            // The value is kept as written: trailing blanks can matter to a macro
+            let value = value.trim_end_matches(['\r', '\n']);
+            (name.trim(), (!value.is_empty()).then(|| value.to_string()))
+        } else {
+            errors.push(format!("line {}: expected name=value or --remove name, got '{}'", line, text));
+            continue;
+        };
+
+        if !is_valid_name(name) {
+            errors.push(format!("line {}: invalid alias name '{}'", line, name));
+            continue;
+        }
+        let name = name.to_lowercase();
+        if let Some(first) = ops.iter().find(|op| op.name == name) {
+            errors.push(format!("line {}: '{}' already appears on line {}", line, name, first.line));
+            continue;
+        }
+        let issues = value.as_deref().map(validate_macro).unwrap_or_default();
+        if !issues.is_empty() {
+            errors.extend(issues.iter().map(|issue| format!("line {}: '{}': {}", line, name, issue)));
+            continue;
+        }
+        ops.push(BatchOp { line, name, value });
+    }
+    if errors.is_empty() { Ok(ops) } else { Err(errors) }
+}
+
+/// Applies a validated batch: one file rewrite, one RAM pass, full rollback on a RAM failure.
+pub fn apply_batch(provider: &dyn AliasProvider, verbosity: &Verbosity, ops: &[BatchOp], path: &Path) -> Result<BatchSummary, Box<dyn std::error::Error>> {
+    // parse_batch already refused these; a hand-built batch gets the same check before the lock
+    if let Some((op, issue)) = ops.iter().find_map(|op| op.value.as_deref().and_then(|v| validate_macro(v).into_iter().next()).map(|i| (op, i))) {
+        return Err(failure!(verbosity, ErrorCode::Syntax, "line {}: '{}': {}. Nothing applied.", op.line, op.name, issue));
+    }
+    let _lock = lock_alias_file(verbosity, path)?;
+    apply_batch_locked(provider, verbosity, ops, path)
+}
+
+/// apply_batch for a caller that already holds the alias file lock and validated the values.
+pub(crate) fn apply_batch_locked(provider: &dyn AliasProvider, verbosity: &Verbosity, ops: &[BatchOp], path: &Path) -> Result<BatchSummary, Box<dyn std::error::Error>> {
+    let existed = path.exists();
+    let original = if existed { AliasDocument::load(path, verbosity)? } else { AliasDocument::default() };
+    let ram_before = provider.get_all_aliases(verbosity)?;
+    let in_ram = |name: &str| ram_before.iter().rev().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str());
+    let mut doc = original.clone();
+    let mut summary = BatchSummary::default();
+    let mut touched: Vec<&BatchOp> = Vec::new();
+
+    for op in ops {
+        let (file, ram, wanted) = (doc.get(&op.name), in_ram(&op.name), op.value.as_deref());
+        match wanted {
+            _ if file == wanted && ram == wanted => { summary.unchanged += 1; continue; },
+            None => summary.removed += 1,
+            Some(_) if file.is_none() && ram.is_none() => summary.added += 1,
+            Some(_) => summary.changed += 1,
+        }
+        if file != wanted { doc.set(&op.name, wanted.unwrap_or_default()); }
+        touched.push(op);
+    }
+    if touched.is_empty() { return Ok(summary); }
+
+    // Only RAM may be behind; the file is then left alone
+    let wrote = doc != original;
+    if wrote { write_alias_document(verbosity, &doc, path)?; }
+    for (done, op) in touched.iter().enumerate() {
+        let Err(e) = inject_macro(provider, &op.name, op.value.as_deref()) else { continue; };
+
+        // Put RAM back first (it is what the shell sees), then the file
+        for undo in &touched[..done] {
+            if let Err(e) = inject_macro(provider, &undo.name, in_ram(&undo.name)) {
+                shout!(verbosity, AliasIcon::Alert, "Rollback could not restore '{}' in RAM: {}", undo.name, e);
+            }
+        }
+        let restored = if !wrote { Ok(()) }
+            else if existed { write_alias_document(verbosity, &original, path) }
+            else { fs::remove_file(path).map_err(|e| failure!(verbosity, e).into()) };
+        if let Err(e) = restored {
+            shout!(verbosity, AliasIcon::Alert, "Rollback could not restore {}: {} (see --backups)", path.display(), e);
+        }
+        return Err(failure!(verbosity, ErrorCode::Generic, "RAM injection of '{}' (line {}) failed: {}. Nothing applied.", op.name, op.line, e));
+    }
+    Ok(summary)
+}
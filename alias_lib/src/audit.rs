@@ -0,0 +1,221 @@
+// alias_lib/src/audit.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// The audit as data. triple_audit() only compares lists and never prints, so callers
+// (and run()'s exit code) can see what was found. Rendering lives in lib.rs/output.rs.
+// AuditFilter is the --list/--grep/--<status>-only/--sort view over either audit.
+
+use std::fmt;
+use std::str::FromStr;
+use regex::Regex;
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditStatus {
+    Synced,  // Every layer that has it agrees
+    Phantom, // Loaded in RAM, missing from the file (or from the kernel, in the triple audit)
+    Pending, // In the file, not loaded yet
+    Desync,  // Layers disagree on the value
+    Corrupt, // Name cmd.exe can never trigger
+}
+
+impl AuditStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Synced => "synced",
+            Self::Phantom => "phantom",
+            Self::Pending => "pending",
+            Self::Desync => "desync",
+            Self::Corrupt => "corrupt",
+        }
+    }
+}
+
+impl FromStr for AuditStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "synced" => Ok(Self::Synced),
+            "phantom" => Ok(Self::Phantom),
+            "pending" => Ok(Self::Pending),
+            "desync" => Ok(Self::Desync),
+            "corrupt" => Ok(Self::Corrupt),
+            other => Err(format!("Unknown audit status '{}' (synced, phantom, pending, desync, corrupt)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuditSort {
+    #[default]
+    File,   // File order, RAM-only entries last
+    Name,
+    Length, // Longest value first
+}
+
+impl AuditSort {
+    pub const ALL: [Self; 3] = [Self::File, Self::Name, Self::Length];
+}
+
+impl FromStr for AuditSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "file" => Ok(Self::File),
+            "name" => Ok(Self::Name),
+            "length" | "len" => Ok(Self::Length),
+            other => Err(format!("Unknown sort order '{}' (name, file, length)", other)),
+        }
+    }
+}
+
+impl fmt::Display for AuditSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File => write!(f, "file"),
+            Self::Name => write!(f, "name"),
+            Self::Length => write!(f, "length"),
+        }
+    }
+}
+
+/// What the listing shows. Every test must pass; an empty filter keeps everything in file order.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub names: Option<String>,       // --list: glob over the name
+    pub values: Option<Regex>,       // --grep: searched in every layer's value
+    pub statuses: Vec<AuditStatus>,  // --pending-only etc.; several flags widen the set
+    pub sort: AuditSort,
+}
+
+impl AuditFilter {
+    pub fn keeps(&self, entry: &AuditEntry) -> bool {
+        self.names.as_ref().is_none_or(|glob| glob_match(glob, &entry.name))
+            && self.values.as_ref().is_none_or(|re| entry.values().any(|v| re.is_match(v)))
+            && (self.statuses.is_empty() || self.statuses.contains(&entry.status))
+    }
+
+    /// Positions of the kept entries, in display order. Sorts are stable: ties keep file order.
+    pub fn select(&self, entries: &[AuditEntry]) -> Vec<usize> {
+        let mut kept: Vec<usize> = (0..entries.len()).filter(|&i| self.keeps(&entries[i])).collect();
+        match self.sort {
+            AuditSort::File => {}
+            AuditSort::Name => kept.sort_by_key(|&i| entries[i].name.to_lowercase()),
+            AuditSort::Length => kept.sort_by_key(|&i| std::cmp::Reverse(entries[i].values().map(str::len).max().unwrap_or(0))),
+        }
+        kept
+    }
+
+    pub fn apply(&self, entries: Vec<AuditEntry>) -> Vec<AuditEntry> {
+        let order = self.select(&entries);
+        let mut slots: Vec<Option<AuditEntry>> = entries.into_iter().map(Some).collect();
+        order.into_iter().filter_map(|i| slots[i].take()).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub name: String,
+    pub status: AuditStatus,
+    pub win32: Option<String>,
+    pub doskey: Option<String>,
+    pub file: Option<String>,
+    pub meta: Option<AliasMeta>,
+    pub origin: Option<LayerOrigin>,
+}
+
+impl AuditEntry {
+    /// Every value some layer holds for this name.
+    pub fn values(&self) -> impl Iterator<Item = &str> {
+        [&self.win32, &self.doskey, &self.file].into_iter().flatten().map(String::as_str)
+    }
+
+    /// The mesh only knows "RAM": which column that is depends on who read it.
+    pub fn from_mesh(entry: &AliasEntryMesh, provider: &ProviderType) -> Self {
+        let ram = entry.os_value.clone();
+        let (win32, doskey) = match provider {
+            ProviderType::Wrapper => (None, ram),
+            _ => (ram, None),
+        };
+        Self {
+            name: entry.name.clone(),
+            status: entry.status(),
+            win32,
+            doskey,
+            file: entry.file_value.clone(),
+            meta: entry.meta.clone(),
+            origin: entry.origin.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditReport {
+    pub entries: Vec<AuditEntry>,
+    pub synced: usize,
+    pub phantom: usize,
+    pub pending: usize,
+    pub desync: usize,
+    pub corrupt: usize,
+}
+
+impl AuditReport {
+    pub fn from_entries(entries: Vec<AuditEntry>) -> Self {
+        let mut report = Self::default();
+        for entry in &entries {
+            match entry.status {
+                AuditStatus::Synced => report.synced += 1,
+                AuditStatus::Phantom => report.phantom += 1,
+                AuditStatus::Pending => report.pending += 1,
+                AuditStatus::Desync => report.desync += 1,
+                AuditStatus::Corrupt => report.corrupt += 1,
+            }
+        }
+        report.entries = entries;
+        report
+    }
+
+    /// Anything a `--reload` would change (or could not fix).
+    pub fn desync_detected(&self) -> bool {
+        self.phantom + self.pending + self.desync + self.corrupt > 0
+    }
+
+    /// What fails the exit code: layers that disagree, or names cmd.exe can never run.
+    /// Phantoms (every `--temp` alias) and pending entries are normal states, not errors.
+    pub fn is_broken(&self) -> bool {
+        self.desync + self.corrupt > 0
+    }
+}
+
+/// Win32 is the live truth. Matches are plucked from the other lists, so whatever is left
+/// afterwards is a Doskey phantom or a pending file entry.
+pub fn triple_audit(
+    win32_pairs: Vec<(String, String)>,
+    mut wrap_pairs: Vec<(String, String)>,
+    mut file_pairs: Vec<(String, String)>,
+) -> AuditReport {
+    let pluck = |list: &mut Vec<(String, String)>, name: &str| list.iter().position(|(n, _)| n == name).map(|i| list.remove(i).1);
+    let mut entries = Vec::new();
+
+    for (name, w_val) in win32_pairs {
+        let doskey = pluck(&mut wrap_pairs, &name);
+        let file = pluck(&mut file_pairs, &name);
+        let drift = [&doskey, &file].iter().any(|v| v.as_ref().is_some_and(|v| !macro_equivalent(&w_val, v)));
+        let status = if !is_valid_name(&name) { AuditStatus::Corrupt }
+            else if drift { AuditStatus::Desync }
+            else { AuditStatus::Synced };
+        entries.push(AuditEntry { name, status, win32: Some(w_val), doskey, file, meta: None, origin: None });
+    }
+    for (name, d_val) in wrap_pairs {
+        let file = pluck(&mut file_pairs, &name);
+        let status = if is_valid_name(&name) { AuditStatus::Phantom } else { AuditStatus::Corrupt };
+        entries.push(AuditEntry { name, status, win32: None, doskey: Some(d_val), file, meta: None, origin: None });
+    }
+    for (name, f_val) in file_pairs {
+        let status = if is_valid_name(&name) { AuditStatus::Pending } else { AuditStatus::Corrupt };
+        entries.push(AuditEntry { name, status, win32: None, doskey: None, file: Some(f_val), meta: None, origin: None });
+    }
+    AuditReport::from_entries(entries)
+}
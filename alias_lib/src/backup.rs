@@ -0,0 +1,174 @@
+// alias_lib/src/backup.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Rotating snapshots of the alias file, kept next to it in `.alias-backups/`.
+// A snapshot is taken right before every transactional swap and before the editor
+// opens, so a bad --edalias session or import is always one --restore away.
+//   <dir>/.alias-backups/<file_name>@<utc stamp>.<seq>
+// The stamp uses '-' instead of ':' (Windows file names) and sorts lexically;
+// <seq> breaks ties between snapshots taken within the same second.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::*;
+
+pub const BACKUP_DIR_NAME: &str = ".alias-backups";
+pub const DEFAULT_BACKUP_RETENTION: usize = 10;
+
+/// What restoring a snapshot would do to the current file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackupDiff {
+    pub added: usize,   // in the snapshot, not in the file
+    pub removed: usize, // in the file, not in the snapshot
+    pub changed: usize, // in both, different value
+}
+
+impl BackupDiff {
+    pub fn between(current: &[(String, String)], snapshot: &[(String, String)]) -> Self {
+        let lookup = |list: &[(String, String)], name: &str| list.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.clone());
+        let mut diff = Self::default();
+        for (name, value) in snapshot {
+            match lookup(current, name) {
+                None => diff.added += 1,
+                Some(v) if !macro_equivalent(&v, value) => diff.changed += 1,
+                Some(_) => {},
+            }
+        }
+        diff.removed = current.iter().filter(|(name, _)| lookup(snapshot, name).is_none()).count();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added + self.removed + self.changed == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupInfo {
+    pub index: usize, // 1 = newest
+    pub path: PathBuf,
+    pub stamp: String,
+    pub entries: usize,
+    pub diff: BackupDiff,
+}
+
+pub fn backup_dir(path: &Path) -> PathBuf {
+    path.parent().unwrap_or(Path::new(".")).join(BACKUP_DIR_NAME)
+}
+
+/// ALIAS_BACKUPS=<n> (or `backups = n` in alias.toml) keeps the newest n snapshots, 0 turns snapshots off.
+pub fn backup_retention() -> usize {
+    env::var(ENV_ALIAS_BACKUPS).ok()
+        .and_then(|v| v.trim().parse().ok())
+        .or_else(|| config::config_value("backups").and_then(|v| v.parse().ok()))
+        .unwrap_or(DEFAULT_BACKUP_RETENTION)
+}
+
+fn snapshot_prefix(path: &Path) -> String {
+    format!("{}@", path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default())
+}
+
+/// Snapshot files for `path`, newest first.
+fn snapshot_files(path: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let dir = backup_dir(path);
+    if !dir.is_dir() { return Ok(Vec::new()); }
+    let prefix = snapshot_prefix(path);
+    let mut files: Vec<(String, PathBuf)> = fs::read_dir(&dir)?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.strip_prefix(&prefix).map(|stamp| (stamp.to_string(), e.path()))
+        })
+        .collect();
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(files)
+}
+
+/// Copies the current file into the backup directory and prunes past `retention`.
+/// Nothing is written for a missing or empty file, or when the newest snapshot already matches.
+pub fn snapshot_alias_file(path: &Path, retention: usize) -> io::Result<Option<PathBuf>> {
+    if retention == 0 { return Ok(None); }
+    let content = match fs::read(path) {
+        Ok(c) if !c.is_empty() => c,
+        Ok(_) => return Ok(None),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let existing = snapshot_files(path)?;
+    if let Some((_, newest)) = existing.first()
+        && fs::read(newest).is_ok_and(|prev| prev == content) {
+        return Ok(None);
+    }
+
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir)?;
+    let stamp = utc_timestamp().replace(':', "-");
+    // Always above the newest slot of this second, even if older ones were pruned
+    let first_seq = existing.iter()
+        .filter_map(|(s, _)| s.strip_prefix(&stamp)?.strip_prefix('.')?.parse::<usize>().ok())
+        .max().map_or(0, |n| n + 1);
+    let mut target = None;
+    for seq in first_seq..1000 {
+        let candidate = dir.join(format!("{}{}.{:03}", snapshot_prefix(path), stamp, seq));
+        // create_new: two writers in the same second must not share a slot
+        match fs::OpenOptions::new().write(true).create_new(true).open(&candidate) {
+            Ok(mut file) => {
+                io::Write::write_all(&mut file, &content)?;
+                target = Some(candidate);
+                break;
+            },
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    let target = target.ok_or_else(|| io::Error::new(io::ErrorKind::AlreadyExists, "No free backup slot for this second"))?;
+
+    for (_, stale) in snapshot_files(path)?.into_iter().skip(retention) {
+        let _ = fs::remove_file(stale);
+    }
+    Ok(Some(target))
+}
+
+/// The hook used before writes: a failing backup is reported, never fatal.
+pub fn backup_before_write(verbosity: &Verbosity, path: &Path) {
+    if let Err(e) = snapshot_alias_file(path, backup_retention()) {
+        shout!(verbosity, AliasIcon::Alert, "Backup skipped for {}: {}", path.display(), e);
+    }
+}
+
+pub fn list_backups(path: &Path) -> io::Result<Vec<BackupInfo>> {
+    let current = match read_alias_text(path) {
+        Ok(c) => AliasDocument::parse(&c).entries(),
+        Err(_) => Vec::new(),
+    };
+    snapshot_files(path)?.into_iter().enumerate().map(|(i, (stamp, file))| {
+        let entries = AliasDocument::parse(&read_alias_text(&file)?).entries();
+        Ok(BackupInfo {
+            index: i + 1,
+            diff: BackupDiff::between(&current, &entries),
+            entries: entries.len(),
+            path: file,
+            stamp,
+        })
+    }).collect()
+}
+
+/// `3` is the third newest. Anything else is a stamp prefix (`2025-01-31T09:00` works too);
+/// the newest match wins.
+pub fn find_backup<'a>(backups: &'a [BackupInfo], selector: &str) -> Option<&'a BackupInfo> {
+    if let Ok(n) = selector.parse::<usize>() {
+        return backups.iter().find(|b| b.index == n);
+    }
+    let wanted = selector.trim().replace(':', "-");
+    if wanted.is_empty() { return None; }
+    backups.iter().find(|b| b.stamp.starts_with(&wanted))
+}
+
+/// Puts a snapshot back through the normal locked, transactional write,
+/// which snapshots the current file first, so a restore can itself be restored.
+pub fn restore_backup(verbosity: &Verbosity, backup: &BackupInfo, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let (content, encoding) = read_alias_file(&backup.path).map_err(|e| failure!(verbosity, e))?;
+    let _lock = lock_alias_file(verbosity, path)?;
+    write_alias_document(verbosity, &AliasDocument { encoding, ..AliasDocument::parse(&content) }, path)
+}
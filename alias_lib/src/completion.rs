@@ -0,0 +1,239 @@
+// alias_lib/src/completion.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// `alias --completions clink|powershell|bash|zsh|fish` prints a completion script.
+// The switches are not listed here: they come from AliasAction::catalog() through
+// to_cli_args, so a renamed or new flag shows up in every script without touching this file.
+// Alias names are not baked in either; each script runs `alias --completions names`
+// (the alias names in the active file, one per line) when it needs them.
+
+use std::collections::BTreeMap;
+use crate::*;
+
+/// The --completions target the scripts call back into for alias names.
+pub const NAMES_TARGET: &str = "names";
+
+/// What may follow a switch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompletionArg {
+    None,
+    AliasName,
+    Path,
+    Choice(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionSpec {
+    pub flag: String,
+    pub arg: CompletionArg,
+}
+
+fn setting_choices(key: &str) -> CompletionArg {
+    match config::find_setting(key).map(|s| s.kind) {
+        Some(config::SettingKind::Choice(values)) => CompletionArg::Choice(values.iter().map(|v| v.to_string()).collect()),
+        _ => CompletionArg::None,
+    }
+}
+
+fn argument_of(action: &AliasAction) -> CompletionArg {
+    let choice = |values: Vec<String>| CompletionArg::Choice(values);
+    match action {
+        AliasAction::Remove(_) | AliasAction::Unalias(_) | AliasAction::Describe(_)
+        | AliasAction::Expand(..) | AliasAction::History(_) => CompletionArg::AliasName,
+        AliasAction::File | AliasAction::Import(_) | AliasAction::Allow(_)
+        | AliasAction::ExpandFile(_) | AliasAction::Apply(_) => CompletionArg::Path,
+        AliasAction::Format(_) => setting_choices("format"),
+        AliasAction::Sort(_) => choice(AuditSort::ALL.iter().map(|s| s.to_string()).collect()),
+        AliasAction::Scope(_) => setting_choices("scope"),
+        AliasAction::Encoding(_) => choice(AliasEncoding::ALL.iter().map(|e| e.to_string()).collect()),
+        AliasAction::Export(_) | AliasAction::Completions(_) => choice(ExportFormat::ALL.iter().map(|f| f.to_string()).collect()),
+        AliasAction::ConfigShow | AliasAction::ConfigSet(..) => choice(vec!["show".to_string(), "set".to_string()]),
+        _ => CompletionArg::None,
+    }
+}
+
+/// Every switch with what may follow it, in catalog order and without duplicates.
+pub fn completion_specs() -> Vec<CompletionSpec> {
+    let mut specs: Vec<CompletionSpec> = Vec::new();
+    for action in AliasAction::catalog() {
+        let cli = action.to_cli_args();
+        let Some(flag) = cli.split_whitespace().next() else { continue; };
+        if specs.iter().any(|s| s.flag == flag) { continue; }
+        specs.push(CompletionSpec { flag: flag.to_string(), arg: argument_of(&action) });
+    }
+    specs
+}
+
+// The command names a script registers for: `alias` and, if different, the file name itself
+fn command_names(exe: &str) -> Vec<String> {
+    let stem = Path::new(exe).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "alias".to_string());
+    let mut names = vec![stem];
+    if names[0] != exe { names.push(exe.to_string()); }
+    names
+}
+
+// Switches grouped by the kind of argument they take, for shells that dispatch on `prev`
+fn flags_taking<'a>(specs: &'a [CompletionSpec], wanted: &CompletionArg) -> Vec<&'a str> {
+    specs.iter().filter(|s| &s.arg == wanted).map(|s| s.flag.as_str()).collect()
+}
+
+fn choices(specs: &[CompletionSpec]) -> BTreeMap<&str, &[String]> {
+    specs.iter().filter_map(|s| match &s.arg {
+        CompletionArg::Choice(values) => Some((s.flag.as_str(), values.as_slice())),
+        _ => None,
+    }).collect()
+}
+
+/// A completion script for `shell`; `exe` is the binary's file name (e.g. alias.exe).
+pub fn completion_script(shell: ExportFormat, exe: &str) -> String {
+    let specs = completion_specs();
+    match shell {
+        ExportFormat::Bash => bash_script(&specs, exe),
+        ExportFormat::Zsh => zsh_script(&specs, exe),
+        ExportFormat::Fish => fish_script(&specs, exe),
+        ExportFormat::PowerShell => powershell_script(&specs, exe),
+        ExportFormat::Clink => clink_script(&specs, exe),
+    }
+}
+
+fn names_command(exe: &str) -> String {
+    format!("{} --quiet --no-tips --completions {}", exe, NAMES_TARGET)
+}
+
+// `alias` is a builtin in bash and zsh, so the callback always names the binary
+fn bash_script(specs: &[CompletionSpec], exe: &str) -> String {
+    let all: Vec<&str> = specs.iter().map(|s| s.flag.as_str()).collect();
+    let mut out = format!("# {} completions for bash: eval \"$({} --completions bash)\"\n", exe, exe);
+    out.push_str("_alias_complete() {\n");
+    out.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n");
+    out.push_str("    case \"$prev\" in\n");
+    out.push_str(&format!("        {}) COMPREPLY=($(compgen -W \"$({} 2>/dev/null)\" -- \"$cur\")); return;;\n",
+        flags_taking(specs, &CompletionArg::AliasName).join("|"), names_command(exe)));
+    out.push_str(&format!("        {}) COMPREPLY=($(compgen -f -- \"$cur\")); return;;\n", flags_taking(specs, &CompletionArg::Path).join("|")));
+    for (flag, values) in choices(specs) {
+        out.push_str(&format!("        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return;;\n", flag, values.join(" ")));
+    }
+    out.push_str("    esac\n");
+    out.push_str("    if [[ \"$cur\" == -* ]]; then\n");
+    out.push_str(&format!("        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n", all.join(" ")));
+    out.push_str("    else\n");
+    out.push_str(&format!("        COMPREPLY=($(compgen -W \"$({} 2>/dev/null)\" -- \"$cur\"))\n", names_command(exe)));
+    out.push_str("    fi\n}\n");
+    out.push_str(&format!("complete -F _alias_complete {}\n", command_names(exe).join(" ")));
+    out
+}
+
+fn zsh_script(specs: &[CompletionSpec], exe: &str) -> String {
+    let all: Vec<&str> = specs.iter().map(|s| s.flag.as_str()).collect();
+    let names = command_names(exe).join(" ");
+    let mut out = format!("#compdef {}\n# {} completions for zsh: source <({} --completions zsh)\n", names, exe, exe);
+    out.push_str("_alias_complete() {\n");
+    out.push_str("    local -a names\n");
+    out.push_str("    case \"${words[CURRENT-1]}\" in\n");
+    out.push_str(&format!("        {}) names=(${{(f)\"$({} 2>/dev/null)\"}}); compadd -a names; return;;\n",
+        flags_taking(specs, &CompletionArg::AliasName).join("|"), names_command(exe)));
+    out.push_str(&format!("        {}) _files; return;;\n", flags_taking(specs, &CompletionArg::Path).join("|")));
+    for (flag, values) in choices(specs) {
+        out.push_str(&format!("        {}) compadd {}; return;;\n", flag, values.join(" ")));
+    }
+    out.push_str("    esac\n");
+    out.push_str("    if [[ \"$PREFIX\" == -* ]]; then\n");
+    out.push_str(&format!("        compadd -- {}\n", all.join(" ")));
+    out.push_str("    else\n");
+    out.push_str(&format!("        names=(${{(f)\"$({} 2>/dev/null)\"}}); compadd -a names\n", names_command(exe)));
+    out.push_str("    fi\n}\n");
+    out.push_str(&format!("compdef _alias_complete {}\n", names));
+    out
+}
+
+fn fish_script(specs: &[CompletionSpec], exe: &str) -> String {
+    let mut out = format!("# {} completions for fish: {} --completions fish | source\n", exe, exe);
+    let names = format!("({} 2>/dev/null)", names_command(exe));
+    for command in command_names(exe) {
+        out.push_str(&format!("complete -c {} -f -a '{}'\n", command, names));
+        for spec in specs {
+            let long = spec.flag.trim_start_matches('-');
+            let line = match &spec.arg {
+                CompletionArg::None => format!("complete -c {} -l {}", command, long),
+                CompletionArg::AliasName => format!("complete -c {} -l {} -x -a '{}'", command, long, names),
+                CompletionArg::Path => format!("complete -c {} -l {} -r -F", command, long),
+                CompletionArg::Choice(values) => format!("complete -c {} -l {} -x -a '{}'", command, long, values.join(" ")),
+            };
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn ps_list(items: &[&str]) -> String {
+    items.iter().map(|i| format!("'{}'", i.replace('\'', "''"))).collect::<Vec<_>>().join(",")
+}
+
+fn powershell_script(specs: &[CompletionSpec], exe: &str) -> String {
+    let all: Vec<&str> = specs.iter().map(|s| s.flag.as_str()).collect();
+    let commands: Vec<String> = command_names(exe);
+    let commands: Vec<&str> = commands.iter().map(String::as_str).collect();
+    let mut out = format!("# {} completions for PowerShell: {} --completions powershell | Out-String | Invoke-Expression\n", exe, exe);
+    out.push_str(&format!("Register-ArgumentCompleter -Native -CommandName {} -ScriptBlock {{\n", ps_list(&commands)));
+    out.push_str("    param($wordToComplete, $commandAst, $cursorPosition)\n");
+    out.push_str(&format!("    $flags = @({})\n", ps_list(&all)));
+    out.push_str(&format!("    $named = @({})\n", ps_list(&flags_taking(specs, &CompletionArg::AliasName))));
+    out.push_str(&format!("    $paths = @({})\n", ps_list(&flags_taking(specs, &CompletionArg::Path))));
+    out.push_str("    $choices = @{\n");
+    for (flag, values) in choices(specs) {
+        let values: Vec<&str> = values.iter().map(String::as_str).collect();
+        out.push_str(&format!("        '{}' = @({})\n", flag, ps_list(&values)));
+    }
+    out.push_str("    }\n");
+    out.push_str("    $words = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })\n");
+    out.push_str("    $prev = if ($wordToComplete) { $words[-2] } else { $words[-1] }\n");
+    out.push_str("    # Nothing offered after a path switch: PowerShell falls back to file names\n");
+    out.push_str("    if ($paths -contains $prev) { return }\n");
+    out.push_str("    $candidates = if ($choices.ContainsKey($prev)) { $choices[$prev] }\n");
+    out.push_str(&format!("        elseif (($named -contains $prev) -or -not $wordToComplete.StartsWith('-')) {{ & '{}' --quiet --no-tips --completions {} 2>$null }}\n", exe, NAMES_TARGET));
+    out.push_str("        else { $flags }\n");
+    out.push_str("    $candidates | Where-Object { $_ -like \"$wordToComplete*\" } | ForEach-Object {\n");
+    out.push_str("        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)\n");
+    out.push_str("    }\n}\n");
+    out
+}
+
+fn lua_list(items: &[&str]) -> String {
+    items.iter().map(|i| format!("\"{}\"", i)).collect::<Vec<_>>().join(", ")
+}
+
+fn clink_script(specs: &[CompletionSpec], exe: &str) -> String {
+    let mut out = format!("-- {} completions for clink: {} --completions clink > %LOCALAPPDATA%\\clink\\alias.lua\n", exe, exe);
+    out.push_str("local function alias_names()\n");
+    out.push_str("    local names = {}\n");
+    out.push_str(&format!("    local pipe = io.popen('\"{}\" --quiet --no-tips --completions {} 2>nul')\n", exe, NAMES_TARGET));
+    out.push_str("    if pipe then\n");
+    out.push_str("        for line in pipe:lines() do table.insert(names, line) end\n");
+    out.push_str("        pipe:close()\n");
+    out.push_str("    end\n");
+    out.push_str("    return names\n");
+    out.push_str("end\n\n");
+    out.push_str("local names = clink.argmatcher():addarg(alias_names)\n");
+    out.push_str("local paths = clink.argmatcher():addarg(clink.filematches)\n\n");
+    let commands = command_names(exe);
+    let commands: Vec<&str> = commands.iter().map(String::as_str).collect();
+    out.push_str(&format!("clink.argmatcher({})\n", lua_list(&commands)));
+    out.push_str(":addflags({\n");
+    for spec in specs {
+        let link = match &spec.arg {
+            CompletionArg::None => String::new(),
+            CompletionArg::AliasName => " .. names".to_string(),
+            CompletionArg::Path => " .. paths".to_string(),
+            CompletionArg::Choice(values) => {
+                let values: Vec<&str> = values.iter().map(String::as_str).collect();
+                format!(" .. clink.argmatcher():addarg({{ {} }})", lua_list(&values))
+            },
+        };
+        out.push_str(&format!("    \"{}\"{},\n", spec.flag, link));
+    }
+    out.push_str("})\n");
+    out.push_str(":addarg(alias_names)\n");
+    out.push_str(":loop()\n");
+    out
+}
@@ -0,0 +1,318 @@
+// alias_lib/src/config.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Persistent defaults in `alias.toml` next to the user alias file (ALIAS_CONFIG overrides the
+// location). Flat `key = value` lines, so the same file reads as TOML or INI:
+//   # alias.toml
+//   icons = false
+//   format = "json"
+//   editor = "code --wait"
+//   backups = 25
+// Strings may be quoted; # and ; start comments; [sections] are ignored.
+//
+// PRECEDENCE  command line > environment > alias.toml > built-in default
+// The flag settings (quiet, temp, case, icons, tips, format, scope, file, group, sort) work by prepending their
+// switches to the command line, config first, then ALIAS_OPTS, so the parser's usual
+// "last switch wins" gives exactly that order. The others are read where they are used.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::*;
+
+pub const CONFIG_FILE_NAME: &str = "alias.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingKind {
+    Bool,
+    Choice(&'static [&'static str]),
+    Count,
+    Text,
+}
+
+/// How a setting shows up on the command line (and in ALIAS_OPTS).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingFlag {
+    None,
+    Switch(&'static str, &'static str), // on, off
+    Valued(&'static str),               // --flag=value or --flag value
+    Attached(&'static str),             // --flag=value only
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Setting {
+    pub key: &'static str,
+    pub kind: SettingKind,
+    pub default: &'static str,
+    pub flag: SettingFlag,
+    pub env: &'static [&'static str],
+    pub about: &'static str,
+}
+
+pub const SETTINGS: &[Setting] = &[
+    Setting { key: "quiet", kind: SettingKind::Bool, default: "false", flag: SettingFlag::Switch("--quiet", "--no-quiet"), env: &[ENV_ALIAS_OPTS], about: "Suppress metadata/whisper output" },
+    Setting { key: "temp", kind: SettingKind::Bool, default: "false", flag: SettingFlag::Switch("--temp", "--no-temp"), env: &[ENV_ALIAS_OPTS], about: "Set aliases in RAM only" },
+    Setting { key: "case", kind: SettingKind::Bool, default: "false", flag: SettingFlag::Switch("--case", "--no-case"), env: &[ENV_ALIAS_OPTS], about: "Keep the case of alias names" },
+    Setting { key: "icons", kind: SettingKind::Bool, default: "true", flag: SettingFlag::Switch("--icons", "--no-icons"), env: &[ENV_ALIAS_OPTS], about: "Glyphs in output" },
+    Setting { key: "tips", kind: SettingKind::Bool, default: "true", flag: SettingFlag::Switch("--tips", "--no-tips"), env: &[ENV_ALIAS_OPTS], about: "Random usage tips" },
+    Setting { key: "format", kind: SettingKind::Choice(&["text", "json", "tsv"]), default: "text", flag: SettingFlag::Valued("--format"), env: &[ENV_ALIAS_OPTS], about: "Output format" },
+    Setting { key: "scope", kind: SettingKind::Choice(&["team", "user", "project", "session"]), default: "user", flag: SettingFlag::Valued("--scope"), env: &[ENV_ALIAS_OPTS], about: "Layer that writes go to" },
+    Setting { key: "file", kind: SettingKind::Text, default: "(searched)", flag: SettingFlag::Valued("--file"), env: &[ENV_ALIAS_OPTS, ENV_ALIAS_FILE], about: "Alias file to act on" },
+    Setting { key: "group", kind: SettingKind::Text, default: "(all)", flag: SettingFlag::Valued("--group"), env: &[ENV_ALIAS_OPTS], about: "Only list this [section]" },
+    Setting { key: "sort", kind: SettingKind::Choice(&["file", "name", "length"]), default: "file", flag: SettingFlag::Valued("--sort"), env: &[ENV_ALIAS_OPTS], about: "Listing order" },
+    Setting { key: "editor", kind: SettingKind::Text, default: FALLBACK_EDITOR, flag: SettingFlag::Attached("--edalias"), env: &[ENV_VISUAL, ENV_EDITOR], about: "Editor for --edalias" },
+    Setting { key: "backups", kind: SettingKind::Count, default: "10", flag: SettingFlag::None, env: &[ENV_ALIAS_BACKUPS], about: "Snapshots kept per file (0 = off)" },
+    Setting { key: "expand", kind: SettingKind::Bool, default: "false", flag: SettingFlag::None, env: &[ENV_ALIAS_EXPAND], about: "Resolve ${...} placeholders (opt-in)" },
+    Setting { key: "provider", kind: SettingKind::Choice(&["win32", "wrapper"]), default: "win32", flag: SettingFlag::None, env: &[], about: "Hybrid build: which backend goes first" },
+];
+
+pub fn find_setting(key: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|s| s.key.eq_ignore_ascii_case(key.trim()))
+}
+
+impl Setting {
+    /// The canonical spelling of `raw`, or why it does not fit.
+    pub fn normalize(&self, raw: &str) -> Result<String, String> {
+        let raw = raw.trim();
+        match self.kind {
+            SettingKind::Bool => match raw.to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Ok("true".to_string()),
+                "false" | "no" | "off" | "0" => Ok("false".to_string()),
+                _ => Err(format!("{} expects true or false, got '{}'", self.key, raw)),
+            },
+            SettingKind::Choice(choices) => choices.iter().find(|c| c.eq_ignore_ascii_case(raw)).map(|c| c.to_string())
+                .ok_or_else(|| format!("{} expects one of {}, got '{}'", self.key, choices.join(", "), raw)),
+            SettingKind::Count => raw.parse::<usize>().map(|n| n.to_string())
+                .map_err(|_| format!("{} expects a whole number, got '{}'", self.key, raw)),
+            SettingKind::Text if raw.is_empty() => Err(format!("{} cannot be empty", self.key)),
+            SettingKind::Text => Ok(raw.to_string()),
+        }
+    }
+
+    /// The switches that set `value` on a command line; empty for non-flag settings.
+    pub fn to_args(&self, value: &str) -> Vec<String> {
+        match self.flag {
+            SettingFlag::Switch(on, off) => vec![if value == "true" { on } else { off }.to_string()],
+            SettingFlag::Valued(flag) => vec![format!("{}={}", flag, value)],
+            SettingFlag::None | SettingFlag::Attached(_) => Vec::new(),
+        }
+    }
+
+    /// The last value this setting gets from `args`, which stop at `--` like the parser does.
+    pub fn from_args(&self, args: &[String]) -> Option<String> {
+        let mut found = None;
+        let mut iter = args.iter().take_while(|a| a.as_str() != "--");
+        while let Some(arg) = iter.next() {
+            let low = arg.to_lowercase();
+            match self.flag {
+                SettingFlag::Switch(on, _) if low == on => found = Some("true".to_string()),
+                SettingFlag::Switch(_, off) if low == off => found = Some("false".to_string()),
+                SettingFlag::Valued(flag) | SettingFlag::Attached(flag) => {
+                    if low.strip_prefix(flag).is_some_and(|r| r.starts_with('=')) {
+                        found = arg.split_once('=').map(|(_, v)| v.trim_matches('"').to_string());
+                    } else if low == flag && matches!(self.flag, SettingFlag::Valued(_)) {
+                        found = iter.next().cloned();
+                    }
+                },
+                _ => {},
+            }
+        }
+        found
+    }
+
+    /// The environment's value, if any variable this setting listens to is set.
+    pub fn from_env(&self) -> Option<(String, &'static str)> {
+        self.env.iter().find_map(|var| {
+            let value = env::var(var).ok().filter(|v| !v.trim().is_empty())?;
+            if *var == ENV_ALIAS_OPTS {
+                return self.from_args(&option_args(&value)).map(|v| (v, *var));
+            }
+            // ALIAS_EXPAND=off and friends are still booleans
+            Some((self.normalize(&value).unwrap_or(value), *var))
+        })
+    }
+}
+
+/// Where an effective value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingSource {
+    CommandLine,
+    Env(&'static str),
+    Config(PathBuf, usize),
+    Default,
+}
+
+impl fmt::Display for SettingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CommandLine => write!(f, "command line"),
+            Self::Env(var) => write!(f, "env {}", var),
+            Self::Config(path, line) => write!(f, "{}:{}", path.display(), line),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// The parsed config file. Values are already normalized; bad lines land in `problems`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AliasConfig {
+    pub path: Option<PathBuf>,
+    pub values: Vec<(&'static Setting, String, usize)>,
+    pub problems: Vec<String>,
+}
+
+impl AliasConfig {
+    pub fn parse(content: &str, path: Option<PathBuf>) -> Self {
+        let mut config = Self { path, ..Self::default() };
+        for (i, line) in content.lines().enumerate() {
+            let text = line.trim();
+            if text.is_empty() || text.starts_with('#') || text.starts_with(';') || text.starts_with('[') { continue; }
+            let Some((key, value)) = text.split_once('=') else {
+                config.problems.push(format!("line {}: expected key = value", i + 1));
+                continue;
+            };
+            let Some(setting) = find_setting(key) else {
+                config.problems.push(format!("line {}: unknown setting '{}'", i + 1, key.trim()));
+                continue;
+            };
+            match setting.normalize(&unquote(value)) {
+                Ok(value) => {
+                    config.values.retain(|(s, _, _)| s.key != setting.key);
+                    config.values.push((setting, value, i + 1));
+                },
+                Err(msg) => config.problems.push(format!("line {}: {}", i + 1, msg)),
+            }
+        }
+        config
+    }
+
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content, Some(path.to_path_buf())),
+            Err(_) => Self { path: Some(path.to_path_buf()), ..Self::default() },
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.iter().find(|(s, _, _)| s.key == key).map(|(_, v, _)| v.as_str())
+    }
+
+    /// Switches for every flag setting the file sets, in table order.
+    pub fn to_args(&self) -> Vec<String> {
+        SETTINGS.iter().filter_map(|s| self.get(s.key).map(|v| s.to_args(v))).flatten().collect()
+    }
+}
+
+/// Inline `# comments` are dropped from unquoted values, quotes and \" \\ escapes are undone.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.rsplit_once('"')).map(|(inner, _)| inner) {
+        return inner.replace("\\\"", "\"").replace("\\\\", "\\");
+    }
+    value.split_once(" #").map_or(value, |(v, _)| v).trim().to_string()
+}
+
+fn render(setting: &Setting, value: &str) -> String {
+    match setting.kind {
+        SettingKind::Bool | SettingKind::Count => value.to_string(),
+        _ => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+    }
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(p) = env::var_os(ENV_ALIAS_CONFIG).filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(p));
+    }
+    // get_alias_path's order, minus its drive probes: backup retention asks on every write
+    let home = env::var_os(ENV_ALIAS_FILE).filter(|p| !p.is_empty()).map(PathBuf::from)
+        .map(|p| if p.is_dir() { p } else { p.parent().unwrap_or(Path::new(".")).to_path_buf() })
+        .or_else(|| [APPDATA_PATH, USERPROFILE_PATH].iter()
+            .filter_map(|var| env::var_os(var).map(PathBuf::from))
+            .map(|base| base.join(DEFAULT_APPDATA_ALIAS_DIR))
+            .find(|dir| dir.is_dir()))?;
+    Some(home.join(CONFIG_FILE_NAME))
+}
+
+pub fn load_config() -> AliasConfig {
+    config_path().map(|p| AliasConfig::load(&p)).unwrap_or_default()
+}
+
+/// The config file's value for one of the settings read at the point of use.
+pub fn config_value(key: &str) -> Option<String> {
+    load_config().get(key).map(str::to_string)
+}
+
+/// ALIAS_OPTS, minus anything that is not a flag setting (it used to be a fixed whitelist).
+/// Quotes work: ALIAS_OPTS=--format "json".
+pub fn option_args(opts: &str) -> Vec<String> {
+    let tokens = shlex::split(opts).unwrap_or_else(|| opts.split_whitespace().map(String::from).collect());
+    let mut out = Vec::new();
+    let mut iter = tokens.into_iter();
+    while let Some(token) = iter.next() {
+        let low = token.to_lowercase();
+        for setting in SETTINGS {
+            match setting.flag {
+                SettingFlag::Switch(on, off) if low == on || low == off => out.push(token.clone()),
+                SettingFlag::Valued(flag) if low.strip_prefix(flag).is_some_and(|r| r.starts_with('=')) => out.push(token.clone()),
+                SettingFlag::Valued(flag) if low == flag => {
+                    out.push(token.clone());
+                    out.extend(iter.next());
+                },
+                _ => continue,
+            }
+            break;
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveSetting {
+    pub setting: &'static Setting,
+    pub value: String,
+    pub source: SettingSource,
+}
+
+/// Every setting with the value that wins and where it came from.
+pub fn effective_settings(config: &AliasConfig, cli: &[String]) -> Vec<EffectiveSetting> {
+    SETTINGS.iter().map(|setting| {
+        let (value, source) = if let Some(v) = setting.from_args(cli) {
+            (v, SettingSource::CommandLine)
+        } else if let Some((v, var)) = setting.from_env() {
+            (v, SettingSource::Env(var))
+        } else if let Some((_, v, line)) = config.values.iter().find(|(s, _, _)| s.key == setting.key) {
+            (v.clone(), SettingSource::Config(config.path.clone().unwrap_or_default(), *line))
+        } else {
+            (setting.default.to_string(), SettingSource::Default)
+        };
+        EffectiveSetting { setting, value, source }
+    }).collect()
+}
+
+/// `--config set key value`: edits the key's line in place (comments and order survive) or appends it.
+pub fn set_config_value(verbosity: &Verbosity, path: &Path, key: &str, value: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let setting = find_setting(key).ok_or_else(|| failure!(verbosity, ErrorCode::MissingName, "Unknown setting '{}' ({})",
+        key, SETTINGS.iter().map(|s| s.key).collect::<Vec<_>>().join(", ")))?;
+    let value = setting.normalize(value).map_err(|msg| failure!(verbosity, ErrorCode::Syntax, "{}", msg))?;
+    let line = format!("{} = {}", setting.key, render(setting, &value));
+
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| failure!(verbosity, e))?;
+    }
+    let _lock = lock_alias_file(verbosity, path)?;
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(failure!(verbosity, e)),
+    };
+    let eol = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let is_key = |l: &str| l.split_once('=').is_some_and(|(k, _)| k.trim().eq_ignore_ascii_case(setting.key)) && !l.trim_start().starts_with(['#', ';']);
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    match lines.iter().rposition(|l| is_key(l)) {
+        Some(i) => lines[i] = line,
+        None => lines.push(line),
+    }
+    fs::write(path, lines.iter().map(|l| format!("{}{}", l, eol)).collect::<String>()).map_err(|e| failure!(verbosity, e))?;
+    Ok(value)
+}
@@ -0,0 +1,299 @@
+// alias_lib/src/document.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Lossless model of a .doskey file. Every line becomes a node that remembers its
+// raw text and its own line ending, so comments, blank lines and even garbage
+// survive an edit. Only the node being set/removed/renamed is ever rewritten.
+//
+// Metadata rides in a structured comment directly above its entry:
+//   ;; @desc Show short git status @tags git,nav @created 2025-01-31T09:00:00Z
+//   gs=git status -sb
+// Plain doskey /macrofile never sees it as a macro (no '=' on the line).
+//
+// The byte encoding (BOM, UTF-16, CP437) is remembered too and reused on write; see encoding.rs.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AliasNode {
+    Comment { raw: String, eol: String },
+    Blank { raw: String, eol: String },
+    Entry { raw: String, name: String, value: String, eol: String },
+    // Looks like data but would be dropped by parse_macro_file (bad name, no '=').
+    Unparsable { raw: String, eol: String },
+}
+
+impl AliasNode {
+    fn classify(raw: &str, eol: &str) -> Self {
+        let (raw, eol) = (raw.to_string(), eol.to_string());
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            return AliasNode::Blank { raw, eol };
+        }
+        match is_data_line(&raw) {
+            Some((n, v)) if is_valid_name(n) => {
+                let (name, value) = (n.to_string(), v.to_string());
+                AliasNode::Entry { raw, name, value, eol }
+            },
+            Some(_) => AliasNode::Unparsable { raw, eol },
+            None if trimmed.starts_with(|c: char| c.is_alphanumeric() || c == '_') => AliasNode::Unparsable { raw, eol },
+            None => AliasNode::Comment { raw, eol },
+        }
+    }
+    pub fn raw(&self) -> &str {
+        match self {
+            AliasNode::Comment { raw, .. } | AliasNode::Blank { raw, .. }
+            | AliasNode::Entry { raw, .. } | AliasNode::Unparsable { raw, .. } => raw,
+        }
+    }
+    pub fn eol(&self) -> &str {
+        match self {
+            AliasNode::Comment { eol, .. } | AliasNode::Blank { eol, .. }
+            | AliasNode::Entry { eol, .. } | AliasNode::Unparsable { eol, .. } => eol,
+        }
+    }
+    fn set_eol(&mut self, new_eol: &str) {
+        match self {
+            AliasNode::Comment { eol, .. } | AliasNode::Blank { eol, .. }
+            | AliasNode::Entry { eol, .. } | AliasNode::Unparsable { eol, .. } => *eol = new_eol.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AliasMeta {
+    pub desc: Option<String>,
+    pub tags: Vec<String>,
+    pub created: Option<String>,
+}
+
+impl AliasMeta {
+    pub const PREFIX: &'static str = ";;";
+
+    /// Reads a `;; @desc ... @tags a,b @created ts` line. Plain comments return None.
+    pub fn parse(line: &str) -> Option<Self> {
+        let body = line.trim().strip_prefix(Self::PREFIX)?;
+        let mut meta = AliasMeta::default();
+        let mut key: Option<&str> = None;
+        let mut desc: Vec<&str> = Vec::new();
+        let mut found = false;
+
+        for token in body.split_whitespace() {
+            match token {
+                "@desc" | "@tags" | "@created" => { key = Some(token); found = true; continue; },
+                _ => {},
+            }
+            match key {
+                Some("@desc") => desc.push(token),
+                Some("@tags") => meta.tags.extend(token.split(',').filter(|t| !t.is_empty()).map(str::to_string)),
+                Some("@created") => meta.created = Some(token.to_string()),
+                _ => {},
+            }
+        }
+        if !found { return None; }
+        if !desc.is_empty() { meta.desc = Some(desc.join(" ")); }
+        Some(meta)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.desc.is_none() && self.tags.is_empty() && self.created.is_none()
+    }
+}
+
+impl fmt::Display for AliasMeta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Self::PREFIX)?;
+        if let Some(desc) = &self.desc { write!(f, " @desc {}", desc)?; }
+        if !self.tags.is_empty() { write!(f, " @tags {}", self.tags.join(","))?; }
+        if let Some(created) = &self.created { write!(f, " @created {}", created)?; }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AliasDocument {
+    pub nodes: Vec<AliasNode>,
+    pub encoding: AliasEncoding,
+}
+
+impl AliasDocument {
+    pub fn parse(content: &str) -> Self {
+        let mut nodes = Vec::new();
+        let mut rest = content;
+        while !rest.is_empty() {
+            let (line, eol, tail) = match rest.find('\n') {
+                Some(i) if i > 0 && rest.as_bytes()[i - 1] == b'\r' => (&rest[..i - 1], "\r\n", &rest[i + 1..]),
+                Some(i) => (&rest[..i], "\n", &rest[i + 1..]),
+                None => (rest, "", ""),
+            };
+            nodes.push(AliasNode::classify(line, eol));
+            rest = tail;
+        }
+        Self { nodes, encoding: AliasEncoding::default() }
+    }
+
+    pub fn load(path: &Path, verbosity: &Verbosity) -> Result<Self, Box<dyn std::error::Error>> {
+        match verify_read_readiness(&PathBuf::from(path)) {
+            AccessResult::Blocked(msg) => {
+                Err(failure!(verbosity, ErrorCode::AccessDenied, "Lock detected during parse. {}", msg))
+            },
+            AccessResult::Empty => Ok(Self::default()),
+            AccessResult::Ready => {
+                let (content, encoding) = read_alias_file(path).map_err(|e| failure!(verbosity, e))?;
+                Ok(Self { encoding, ..Self::parse(&content) })
+            },
+        }
+    }
+
+    /// The same (name, value) list parse_macro_file would hand to the providers.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        self.nodes.iter().filter_map(|node| match node {
+            AliasNode::Entry { name, value, .. } => Some((name.clone(), value.clone())),
+            _ => None,
+        }).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.last_entry(name).map(|i| match &self.nodes[i] {
+            AliasNode::Entry { value, .. } => value.as_str(),
+            _ => unreachable!(),
+        })
+    }
+
+    // Doskey loads top to bottom, so the LAST duplicate is the one that wins in RAM.
+    fn last_entry(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().rposition(|node| matches!(node, AliasNode::Entry { name: n, .. } if n == name))
+    }
+
+    /// The newline this file mostly uses. New files get "\n" like before.
+    pub fn dominant_eol(&self) -> &'static str {
+        let crlf = self.nodes.iter().filter(|n| n.eol() == "\r\n").count();
+        let lf = self.nodes.iter().filter(|n| n.eol() == "\n").count();
+        if crlf > lf { "\r\n" } else { "\n" }
+    }
+
+    /// Updates the value in place, or appends a new entry. An empty value removes.
+    pub fn set(&mut self, name: &str, value: &str) -> bool {
+        if value.is_empty() {
+            return self.remove(name);
+        }
+        if let Some(i) = self.last_entry(name) {
+            if let AliasNode::Entry { raw, value: old, .. } = &mut self.nodes[i] {
+                if old == value { return false; }
+                // Keep everything up to and including '=' exactly as the user wrote it
+                let eq = raw.find('=').unwrap_or(raw.len());
+                *raw = format!("{}={}", &raw[..eq], value);
+                *old = value.to_string();
+            }
+            return true;
+        }
+        let eol = self.dominant_eol();
+        // The new line inherits the old "last line" ending, so a missing trailing newline stays missing
+        let tail_eol = match self.nodes.last_mut() {
+            Some(last) => {
+                let kept = last.eol().to_string();
+                if kept.is_empty() { last.set_eol(eol); }
+                kept
+            },
+            None => String::new(),
+        };
+        self.nodes.push(AliasNode::Entry {
+            raw: format!("{}={}", name, value),
+            name: name.to_string(),
+            value: value.to_string(),
+            eol: tail_eol,
+        });
+        true
+    }
+
+    /// Drops every entry for `name` (a surviving duplicate would resurrect it on reload).
+    /// Its metadata line goes with it, otherwise it would latch onto the next entry.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let is_target = |node: &AliasNode| matches!(node, AliasNode::Entry { name: n, .. } if n == name);
+        let doomed: Vec<bool> = (0..self.nodes.len()).map(|i| {
+            is_target(&self.nodes[i])
+                || (self.meta_at(i).is_some() && self.nodes.get(i + 1).is_some_and(is_target))
+        }).collect();
+        if !doomed.contains(&true) { return false; }
+
+        let last_eol = self.nodes.last().map(|n| n.eol().to_string());
+        let mut i = 0;
+        self.nodes.retain(|_| { i += 1; !doomed[i - 1] });
+        if let (Some(eol), Some(last)) = (last_eol, self.nodes.last_mut()) {
+            last.set_eol(&eol);
+        }
+        true
+    }
+
+    pub fn rename(&mut self, old_name: &str, new_name: &str) -> bool {
+        if old_name == new_name || self.last_entry(new_name).is_some() { return false; }
+        let mut touched = false;
+        for node in self.nodes.iter_mut() {
+            if let AliasNode::Entry { raw, name, .. } = node {
+                if *name != old_name { continue; }
+                let start = raw.find(old_name).unwrap_or(0);
+                *raw = format!("{}{}{}", &raw[..start], new_name, &raw[start + old_name.len()..]);
+                *name = new_name.to_string();
+                touched = true;
+            }
+        }
+        touched
+    }
+}
+
+// --- Metadata ---
+impl AliasDocument {
+    fn meta_at(&self, i: usize) -> Option<AliasMeta> {
+        match self.nodes.get(i) {
+            Some(AliasNode::Comment { raw, .. }) => AliasMeta::parse(raw),
+            _ => None,
+        }
+    }
+
+    /// Metadata attached to `name`, i.e. a `;;` line sitting right above the entry.
+    pub fn meta(&self, name: &str) -> Option<AliasMeta> {
+        let i = self.last_entry(name)?;
+        if i == 0 { return None; }
+        self.meta_at(i - 1)
+    }
+
+    pub fn metadata(&self) -> HashMap<String, AliasMeta> {
+        let mut map = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate().skip(1) {
+            if let AliasNode::Entry { name, .. } = node
+                && let Some(meta) = self.meta_at(i - 1) {
+                map.insert(name.clone(), meta);
+            }
+        }
+        map
+    }
+
+    /// Replaces (or inserts) the metadata line above `name`. False if there is no such entry.
+    pub fn set_meta(&mut self, name: &str, meta: &AliasMeta) -> bool {
+        let Some(i) = self.last_entry(name) else { return false; };
+        let has_meta = i > 0 && self.meta_at(i - 1).is_some();
+        match (has_meta, meta.is_empty()) {
+            (true, true) => { self.nodes.remove(i - 1); },
+            (true, false) => {
+                if let AliasNode::Comment { raw, .. } = &mut self.nodes[i - 1] { *raw = meta.to_string(); }
+            },
+            (false, true) => {},
+            (false, false) => {
+                let eol = self.dominant_eol().to_string();
+                self.nodes.insert(i, AliasNode::Comment { raw: meta.to_string(), eol });
+            },
+        }
+        true
+    }
+}
+
+impl fmt::Display for AliasDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.nodes {
+            write!(f, "{}{}", node.raw(), node.eol())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+// alias_lib/src/encoding.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Byte-level I/O for alias files. Notepad saves UTF-8 with a BOM or "Unicode" (UTF-16LE),
+// older editors save in a DOS code page, and doskey.exe itself knows none of it.
+// Every alias file read goes through read_alias_file, which reports the encoding it found;
+// AliasDocument keeps it and writes back in the same encoding. Newlines are the document's
+// business (each line keeps its own ending), so nothing here touches them.
+//
+// DETECTION (first match wins)
+//   EF BB BF        UTF-8 with BOM
+//   FF FE / FE FF   UTF-16LE / UTF-16BE
+//   no BOM, but every other byte NUL in the first 512   UTF-16 without BOM
+//   valid UTF-8     UTF-8
+//   anything else   CP437 (the US console code page; other DOS code pages are not told apart)
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AliasEncoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    Cp437,
+}
+
+// CP437 0x80..=0xFF, the console code page of a stock US Windows
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+impl AliasEncoding {
+    pub const ALL: [Self; 5] = [Self::Utf8, Self::Utf8Bom, Self::Utf16Le, Self::Utf16Be, Self::Cp437];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf8",
+            Self::Utf8Bom => "utf8-bom",
+            Self::Utf16Le => "utf16le",
+            Self::Utf16Be => "utf16be",
+            Self::Cp437 => "cp437",
+        }
+    }
+
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(UTF8_BOM) { return Self::Utf8Bom; }
+        if bytes.starts_with(&[0xFF, 0xFE]) { return Self::Utf16Le; }
+        if bytes.starts_with(&[0xFE, 0xFF]) { return Self::Utf16Be; }
+        let head = &bytes[..bytes.len().min(512) & !1];
+        if !head.is_empty() {
+            let nul_at = |odd: bool| head.iter().skip(odd as usize).step_by(2).filter(|b| **b == 0).count();
+            let pairs = head.len() / 2;
+            // ASCII text in UTF-16 has a NUL in every unit; allow a few non-ASCII characters
+            if nul_at(true) * 4 >= pairs * 3 && nul_at(false) == 0 { return Self::Utf16Le; }
+            if nul_at(false) * 4 >= pairs * 3 && nul_at(true) == 0 { return Self::Utf16Be; }
+        }
+        if std::str::from_utf8(bytes).is_ok() { Self::Utf8 } else { Self::Cp437 }
+    }
+
+    /// Lossy: a broken UTF-16 unit becomes U+FFFD rather than failing the whole file.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let units = |be: bool, body: &[u8]| -> Vec<u16> {
+            body.chunks_exact(2).map(|c| if be { u16::from_be_bytes([c[0], c[1]]) } else { u16::from_le_bytes([c[0], c[1]]) }).collect()
+        };
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Self::Utf8Bom => String::from_utf8_lossy(bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes)).into_owned(),
+            Self::Utf16Le => String::from_utf16_lossy(&units(false, bytes.strip_prefix(&[0xFF, 0xFE]).unwrap_or(bytes))),
+            Self::Utf16Be => String::from_utf16_lossy(&units(true, bytes.strip_prefix(&[0xFE, 0xFF]).unwrap_or(bytes))),
+            Self::Cp437 => bytes.iter().map(|b| if *b < 0x80 { *b as char } else { CP437_HIGH[(*b - 0x80) as usize] }).collect(),
+        }
+    }
+
+    /// The bytes to write, and how many characters the encoding could not hold (written as '?').
+    pub fn encode(&self, text: &str) -> (Vec<u8>, usize) {
+        match self {
+            Self::Utf8 => (text.as_bytes().to_vec(), 0),
+            Self::Utf8Bom => ([UTF8_BOM, text.as_bytes()].concat(), 0),
+            Self::Utf16Le => ([0xFF, 0xFE].into_iter().chain(text.encode_utf16().flat_map(u16::to_le_bytes)).collect(), 0),
+            Self::Utf16Be => ([0xFE, 0xFF].into_iter().chain(text.encode_utf16().flat_map(u16::to_be_bytes)).collect(), 0),
+            Self::Cp437 => {
+                let mut lost = 0;
+                let bytes = text.chars().map(|c| {
+                    if c.is_ascii() { return c as u8; }
+                    CP437_HIGH.iter().position(|h| *h == c).map(|i| 0x80 + i as u8).unwrap_or_else(|| { lost += 1; b'?' })
+                }).collect();
+                (bytes, lost)
+            },
+        }
+    }
+}
+
+impl fmt::Display for AliasEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AliasEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace(['-', '_'], "").as_str() {
+            "utf8" => Ok(Self::Utf8),
+            "utf8bom" | "utf8sig" => Ok(Self::Utf8Bom),
+            "utf16le" | "utf16" | "unicode" => Ok(Self::Utf16Le),
+            "utf16be" => Ok(Self::Utf16Be),
+            "cp437" | "437" => Ok(Self::Cp437),
+            _ => Err(format!("Unknown encoding '{}' (utf8, utf8-bom, utf16le, utf16be, cp437)", s.trim())),
+        }
+    }
+}
+
+/// An alias file (or anything included, imported or applied) as text, with its encoding.
+pub fn read_alias_file(path: &Path) -> io::Result<(String, AliasEncoding)> {
+    let bytes = fs::read(path)?;
+    let encoding = AliasEncoding::detect(&bytes);
+    Ok((encoding.decode(&bytes), encoding))
+}
+
+/// read_alias_file without the encoding: a drop-in for fs::read_to_string.
+pub fn read_alias_text(path: &Path) -> io::Result<String> {
+    read_alias_file(path).map(|(text, _)| text)
+}
@@ -0,0 +1,172 @@
+// alias_lib/src/export.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Pure translator: doskey macros in, bash/zsh/fish/PowerShell/Clink source out.
+// No I/O here. Anything that cannot be carried over faithfully is reported in
+// ExportReport::skipped instead of being emitted half-broken.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::macro_lang::{tokenize_macro, MacroToken};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+    Clink,
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 5] = [Self::Bash, Self::Zsh, Self::Fish, Self::PowerShell, Self::Clink];
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "bash" | "sh" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            "powershell" | "pwsh" | "ps1" => Ok(Self::PowerShell),
+            "clink" | "doskey" => Ok(Self::Clink),
+            other => Err(format!("Unknown export format '{}' (bash, zsh, fish, powershell, clink)", other)),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bash => write!(f, "bash"),
+            Self::Zsh => write!(f, "zsh"),
+            Self::Fish => write!(f, "fish"),
+            Self::PowerShell => write!(f, "powershell"),
+            Self::Clink => write!(f, "clink"),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ExportReport {
+    pub script: String,
+    pub exported: usize,
+    pub skipped: Vec<(String, String)>, // (name, reason)
+}
+
+// cmd-only syntax that would silently change meaning in another shell
+fn cmd_only_reason(text: &str) -> Option<String> {
+    // "%A% x %B%" splits into ["", "A", " x ", "B", ""]: variables sit on the odd slots
+    let segments: Vec<&str> = text.split('%').collect();
+    for i in (1..segments.len().saturating_sub(1)).step_by(2) {
+        let candidate = segments[i];
+        if !candidate.is_empty() && candidate.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Some(format!("cmd variable %{}% has no portable equivalent", candidate));
+        }
+    }
+    None
+}
+
+pub fn translate_macro(name: &str, value: &str, format: ExportFormat) -> Result<String, String> {
+    let value = value.trim();
+    let parsed = tokenize_macro(value);
+    if let Some(issue) = parsed.first_fatal() {
+        return Err(issue.to_string());
+    }
+    if format == ExportFormat::Clink {
+        // Clink hosts doskey macros natively, validation is all it needs
+        return Ok(value.to_string());
+    }
+
+    let mut body = String::new();
+    let mut at_command_start = true;
+
+    for token in parsed.tokens {
+        match token {
+            MacroToken::Text(text) => {
+                if let Some(reason) = cmd_only_reason(&text) { return Err(reason); }
+                let mut text = text.as_str();
+                if at_command_start {
+                    // A macro that calls its own name would recurse as a shell function
+                    let leading = text.trim_start();
+                    let first = leading.split_whitespace().next().unwrap_or("");
+                    if first.eq_ignore_ascii_case(name) {
+                        match format {
+                            ExportFormat::PowerShell => return Err("calls itself; a PowerShell function would recurse".to_string()),
+                            _ => { body.push_str(&text[..text.len() - leading.len()]); body.push_str("command "); text = leading; },
+                        }
+                    }
+                    if !leading.is_empty() { at_command_start = false; }
+                }
+                body.push_str(text);
+            },
+            MacroToken::AllArgs => body.push_str(match format {
+                ExportFormat::Fish => "$argv",
+                ExportFormat::PowerShell => "@args",
+                _ => "\"$@\"",
+            }),
+            MacroToken::Arg(n) => body.push_str(&match format {
+                ExportFormat::Fish => format!("$argv[{}]", n),
+                ExportFormat::PowerShell => format!("$args[{}]", n - 1),
+                _ => format!("\"${}\"", n),
+            }),
+            MacroToken::Separator => {
+                body.push(';');
+                at_command_start = true;
+            },
+            MacroToken::RedirectIn if format == ExportFormat::PowerShell => {
+                return Err("PowerShell has no '<' input redirection ($L)".to_string());
+            },
+            MacroToken::RedirectOut => body.push('>'),
+            MacroToken::Append => body.push_str(">>"),
+            MacroToken::RedirectIn => body.push('<'),
+            MacroToken::Pipe => {
+                body.push('|');
+                at_command_start = true;
+            },
+            MacroToken::Dollar => body.push_str(match format {
+                ExportFormat::PowerShell => "`$",
+                _ => "\\$",
+            }),
+        }
+    }
+    Ok(body.trim().to_string())
+}
+
+fn render_one(name: &str, body: &str, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Bash | ExportFormat::Zsh => format!("{}() {{ {}; }}", name, body),
+        ExportFormat::Fish => format!("function {}; {}; end", name, body),
+        ExportFormat::PowerShell => format!("function {} {{ {} }}", name, body),
+        ExportFormat::Clink => format!("{}={}", name, body),
+    }
+}
+
+pub fn export_aliases(pairs: &[(String, String)], format: ExportFormat) -> ExportReport {
+    let mut report = ExportReport::default();
+    let comment = match format {
+        ExportFormat::Clink => ";",
+        _ => "#",
+    };
+    let mut lines = vec![format!("{} Generated by alias --export {}", comment, format)];
+    if format == ExportFormat::Clink {
+        lines.push(format!("{} Load with: doskey /macrofile=<this file> (Clink picks the macros up)", comment));
+    }
+
+    for (name, value) in pairs {
+        match translate_macro(name, value, format) {
+            Ok(body) => {
+                lines.push(render_one(name, &body, format));
+                report.exported += 1;
+            },
+            Err(reason) => {
+                lines.push(format!("{} skipped {}: {}", comment, name, reason));
+                report.skipped.push((name.clone(), reason));
+            },
+        }
+    }
+    report.script = lines.join("\n") + "\n";
+    report
+}
@@ -0,0 +1,124 @@
+// alias_lib/src/groups.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Alias groups: INI-style section headers in the alias file.
+//   [git]
+//   gs=git status
+//   [docker]
+//   dps=docker ps
+//   []            back to ungrouped
+// A section runs to the next header or the end of its file; an #include'd file starts in the
+// section of the line that includes it. Group names compare case-insensitively.
+//
+// --disable-group / --enable-group only touch RAM (raw_set_macro); the file is never edited.
+// Each member name is set to whatever wins across the merged layers afterwards, so disabling
+// a group falls back to an ungrouped or other-layer definition instead of deleting the macro.
+// Disabled groups are remembered beside the journal, one name per line:
+//   <dir>/.alias-backups/<file_name>.groups
+// and every load (reload, --startup, audits) skips them until they are enabled again.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::*;
+
+/// `[name]` section headers; `Some("")` for `[]`. Guards ([if ...], [end]) are not sections.
+pub fn group_header(line: &str) -> Option<String> {
+    if template::parse_guard(line).is_some() { return None; }
+    let inner = line.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+    inner.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        .then(|| inner.to_lowercase())
+}
+
+pub fn group_state_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    backup::backup_dir(path).join(format!("{}.groups", name))
+}
+
+pub fn disabled_groups(path: &Path) -> Vec<String> {
+    fs::read_to_string(group_state_path(path))
+        .map(|c| c.lines().map(|l| l.trim().to_lowercase()).filter(|l| !l.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+fn save_disabled(path: &Path, groups: &[String]) -> io::Result<()> {
+    let state = group_state_path(path);
+    if groups.is_empty() {
+        return match fs::remove_file(&state) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = state.parent() { fs::create_dir_all(dir)?; }
+    fs::write(&state, groups.iter().map(|g| format!("{}\n", g)).collect::<String>())
+}
+
+/// Drops entries of disabled groups: what a reload of `path` actually injects.
+pub fn enabled_pairs(path: &Path, pairs: Vec<SourcedPair>) -> Vec<SourcedPair> {
+    without_groups(pairs, &disabled_groups(path))
+}
+
+fn without_groups(pairs: Vec<SourcedPair>, disabled: &[String]) -> Vec<SourcedPair> {
+    if disabled.is_empty() { return pairs; }
+    pairs.into_iter().filter(|p| p.source.group.as_ref().is_none_or(|g| !disabled.contains(g))).collect()
+}
+
+/// Puts a group's macros into RAM (enabled) or takes them out (disabled) and remembers the choice.
+/// Returns how many macros were touched.
+pub fn set_group_enabled(provider: &dyn AliasProvider, verbosity: &Verbosity, path: &Path, group: &str, enabled: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    let group = group.trim().to_lowercase();
+    let all = expand_includes(path, verbosity)?;
+    let members: Vec<&SourcedPair> = all.iter()
+        .filter(|p| p.source.group.as_deref() == Some(group.as_str()) && is_valid_name(&p.name))
+        .collect();
+    if members.is_empty() {
+        let mut known: Vec<&str> = Vec::new();
+        for g in all.iter().filter_map(|p| p.source.group.as_deref()) {
+            if !known.contains(&g) { known.push(g); }
+        }
+        return Err(failure!(verbosity, ErrorCode::MissingName, "No group [{}] in {} (groups: {})", group, path.display(),
+            if known.is_empty() { "none".to_string() } else { known.join(", ") }));
+    }
+
+    let state = group_state_path(path);
+    fs::create_dir_all(backup::backup_dir(path)).map_err(|e| failure!(verbosity, e))?;
+    let _lock = lock_alias_file(verbosity, &state)?;
+    let mut disabled = disabled_groups(path);
+    disabled.retain(|g| *g != group);
+    if !enabled { disabled.push(group.clone()); }
+
+    // RAM first, under the state about to be saved; the state is only written once RAM took it
+    let merged = layers::load_layers_with(verbosity, path, |file, v| if file == path {
+        Ok(without_groups(expand_includes(file, v)?, &disabled))
+    } else {
+        read_sourced_pairs(file, v)
+    })?;
+    let mut names: Vec<&str> = Vec::new();
+    for p in &members {
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(&p.name)) { names.push(&p.name); }
+    }
+    let ram = provider.get_all_aliases(verbosity)?;
+    let rollback = |done: &[&str]| for name in done.iter().rev() {
+        let prev = ram.iter().rev().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str());
+        if let Err(e) = inject_macro(provider, name, prev) {
+            shout!(verbosity, AliasIcon::Alert, "Rollback could not restore '{}' in RAM: {}", name, e);
+        }
+    };
+    for (done, name) in names.iter().enumerate() {
+        let set = match merged.iter().find(|e| e.name.eq_ignore_ascii_case(name)) {
+            Some(winner) => inject_macro(provider, &winner.name, Some(&winner.value)),
+            None => inject_macro(provider, name, None),
+        };
+        if let Err(e) = set {
+            rollback(&names[..done]);
+            return Err(failure!(verbosity, ErrorCode::Generic, "RAM refused '{}': {}. Group [{}] left as it was.", name, e, group));
+        }
+    }
+    if let Err(e) = save_disabled(path, &disabled) {
+        rollback(&names);
+        return Err(failure!(verbosity, e));
+    }
+    let verb = if enabled { "Enabled" } else { "Disabled" };
+    say!(verbosity, AliasIcon::Success, "{} group [{}]: {} macros", verb, group, members.len());
+    Ok(members.len())
+}
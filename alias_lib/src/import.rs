@@ -0,0 +1,323 @@
+// alias_lib/src/import.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// The reverse of export.rs. Reads bash/zsh alias files, PowerShell profiles and
+// `doskey /macros` dumps, and turns them into doskey (name, value) pairs.
+// Pure text in, pairs out; the merge into the alias file lives in lib.rs.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Bash,
+    Zsh,
+    PowerShell,
+    DoskeyMacros,
+    Clink,
+}
+
+impl ImportFormat {
+    /// Best guess from the file name when --from is not given.
+    pub fn detect(path: &Path) -> Self {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match ext.as_str() {
+            "ps1" | "psm1" => Self::PowerShell,
+            "zsh" => Self::Zsh,
+            "doskey" | "macros" | "txt" => Self::DoskeyMacros,
+            _ if name.contains("zsh") => Self::Zsh,
+            _ => Self::Bash,
+        }
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "bash" | "sh" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "powershell" | "pwsh" | "ps1" => Ok(Self::PowerShell),
+            "doskey-macros" | "doskey" | "macros" => Ok(Self::DoskeyMacros),
+            "clink" => Ok(Self::Clink),
+            other => Err(format!("Unknown import format '{}' (bash, zsh, powershell, doskey-macros, clink)", other)),
+        }
+    }
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bash => write!(f, "bash"),
+            Self::Zsh => write!(f, "zsh"),
+            Self::PowerShell => write!(f, "powershell"),
+            Self::DoskeyMacros => write!(f, "doskey-macros"),
+            Self::Clink => write!(f, "clink"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportPolicy {
+    Incoming,
+    #[default]
+    Existing,
+}
+
+impl FromStr for ImportPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "incoming" => Ok(Self::Incoming),
+            "existing" => Ok(Self::Existing),
+            other => Err(format!("Unknown --prefer policy '{}' (incoming, existing)", other)),
+        }
+    }
+}
+
+impl fmt::Display for ImportPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Incoming => write!(f, "incoming"),
+            Self::Existing => write!(f, "existing"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportOptions {
+    pub source: PathBuf,
+    pub from: Option<ImportFormat>,
+    pub prefer: ImportPolicy,
+}
+
+impl ImportOptions {
+    pub fn to_cli_args(&self) -> String {
+        let mut s = format!("--import {}", self.source.display());
+        if let Some(from) = self.from { s.push_str(&format!(" --from {}", from)); }
+        if self.prefer != ImportPolicy::default() { s.push_str(&format!(" --prefer {}", self.prefer)); }
+        s
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportReport {
+    pub aliases: Vec<(String, String)>,
+    pub skipped: Vec<(String, String)>, // (name or line, reason)
+}
+
+pub fn parse_import(content: &str, format: ImportFormat) -> ImportReport {
+    match format {
+        ImportFormat::DoskeyMacros | ImportFormat::Clink => parse_doskey_dump(content),
+        ImportFormat::Bash | ImportFormat::Zsh => parse_shell(content, false),
+        ImportFormat::PowerShell => parse_shell(content, true),
+    }
+}
+
+// `doskey /macros` prints name=value; `/macros:all` adds "[cmd.exe]" headers
+fn parse_doskey_dump(content: &str) -> ImportReport {
+    let mut report = ImportReport::default();
+    for line in content.lines() {
+        let line = line.trim_start_matches('\u{feff}');
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('[') || trimmed.starts_with(';') { continue; }
+        match line.split_once('=') {
+            Some((n, v)) => report.aliases.push((n.trim().to_string(), v.to_string())),
+            None => report.skipped.push((trimmed.to_string(), "not a name=value line".to_string())),
+        }
+    }
+    report
+}
+
+fn parse_shell(content: &str, powershell: bool) -> ImportReport {
+    let mut report = ImportReport::default();
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') { continue; }
+
+        // --- Functions (possibly spanning lines until the braces balance) ---
+        if let Some(name) = function_name(trimmed, powershell) {
+            let mut block = trimmed.to_string();
+            while brace_depth(&block) > 0 {
+                match lines.next() {
+                    Some(more) => { block.push('\n'); block.push_str(more.trim()); },
+                    None => break,
+                }
+            }
+            let body = block.split_once('{').map(|(_, b)| b).unwrap_or("");
+            let body = body.rsplit_once('}').map(|(b, _)| b).unwrap_or(body);
+            let body = body.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')).collect::<Vec<_>>().join("; ");
+            push_translated(&mut report, name, &body, powershell);
+            continue;
+        }
+
+        // --- Aliases ---
+        if !powershell && (trimmed.starts_with("alias ") || trimmed.starts_with("alias\t")) {
+            let Some(words) = shlex::split(trimmed) else {
+                report.skipped.push((trimmed.to_string(), "unbalanced quotes".to_string()));
+                continue;
+            };
+            for word in words.iter().skip(1).filter(|w| !w.starts_with('-')) {
+                match word.split_once('=') {
+                    Some((n, v)) => push_translated(&mut report, n, v, false),
+                    None => report.skipped.push((word.clone(), "alias without a value".to_string())),
+                }
+            }
+            continue;
+        }
+        if powershell {
+            let low = trimmed.to_lowercase();
+            if low.starts_with("set-alias") || low.starts_with("new-alias") {
+                match powershell_alias(trimmed) {
+                    // An alias forwards its arguments, so does the macro
+                    Some((n, v)) => report.aliases.push((n, format!("{} $*", v))),
+                    None => report.skipped.push((trimmed.to_string(), "could not read -Name/-Value".to_string())),
+                }
+            }
+        }
+    }
+    report
+}
+
+fn function_name(line: &str, powershell: bool) -> Option<&str> {
+    if let Some(rest) = line.strip_prefix("function ").or_else(|| line.strip_prefix("Function ")) {
+        let name = rest.trim_start().split(|c: char| c.is_whitespace() || c == '(' || c == '{').next()?;
+        return (!name.is_empty()).then_some(name);
+    }
+    if powershell { return None; }
+    // name() { ... }
+    let (name, rest) = line.split_once("()")?;
+    let name = name.trim();
+    (rest.trim_start().starts_with('{') && !name.contains(char::is_whitespace) && !name.is_empty()).then_some(name)
+}
+
+fn brace_depth(block: &str) -> i32 {
+    let (mut depth, mut quote) = (0, None);
+    for c in block.chars() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (q, Some(open)) if q == open => quote = None,
+            ('{', None) => depth += 1,
+            ('}', None) => depth -= 1,
+            _ => {},
+        }
+    }
+    depth
+}
+
+fn powershell_alias(line: &str) -> Option<(String, String)> {
+    let words = shlex::split(line)?;
+    let (mut name, mut value, mut positional) = (None, None, Vec::new());
+    let mut iter = words.iter().skip(1);
+    while let Some(word) = iter.next() {
+        match word.to_lowercase().as_str() {
+            "-name" => name = iter.next().cloned(),
+            "-value" => value = iter.next().cloned(),
+            w if w.starts_with('-') => {},
+            _ => positional.push(word.clone()),
+        }
+    }
+    let mut positional = positional.into_iter();
+    let name = name.or_else(|| positional.next())?;
+    let value = value.or_else(|| positional.next())?;
+    Some((name, value))
+}
+
+fn push_translated(report: &mut ImportReport, name: &str, body: &str, powershell: bool) {
+    match translate_body(body, powershell) {
+        Ok(value) if value.is_empty() => report.skipped.push((name.to_string(), "empty body".to_string())),
+        Ok(value) => report.aliases.push((name.to_string(), value)),
+        Err(reason) => report.skipped.push((name.to_string(), reason)),
+    }
+}
+
+/// Shell syntax -> doskey macro. Positional args become $1..$9 / $*, separators
+/// and redirections outside quotes become $T $B $G $G$G $L.
+pub fn translate_body(body: &str, powershell: bool) -> Result<String, String> {
+    let chars: Vec<char> = body.trim().trim_end_matches(';').trim_end().chars().collect();
+    let mut out = String::new();
+    let mut quote: Option<char> = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..].iter().take(10).collect();
+
+        // Positional arguments work inside double quotes too
+        if c == '$' && quote != Some('\'') {
+            let (token, len) = positional(&rest, powershell)
+                .ok_or_else(|| format!("shell expansion '{}' has no doskey equivalent", rest.split_whitespace().next().unwrap_or("$")))?;
+            // "$@" -> $* : drop the quotes that only existed for the shell
+            if quote == Some('"') && out.ends_with('"') && chars.get(i + len) == Some(&'"') && token == "$*" {
+                out.pop();
+                out.push_str(token.as_str());
+                quote = None;
+                i += len + 1;
+                continue;
+            }
+            out.push_str(&token);
+            i += len;
+            continue;
+        }
+        if c == '@' && powershell && quote.is_none() && rest.starts_with("@args") {
+            out.push_str("$*");
+            i += 5;
+            continue;
+        }
+        if c == '\\' && !powershell && chars.get(i + 1) == Some(&'$') {
+            out.push_str("$$");
+            i += 2;
+            continue;
+        }
+        if c == '`' && powershell && chars.get(i + 1) == Some(&'$') {
+            out.push_str("$$");
+            i += 2;
+            continue;
+        }
+        match (c, quote) {
+            ('\'' | '"', None) => { quote = Some(c); out.push(c); },
+            (q, Some(open)) if q == open => { quote = None; out.push(c); },
+            (';', None) => out.push_str("$T"),
+            ('|', None) if chars.get(i + 1) == Some(&'|') => { out.push_str("||"); i += 1; },
+            ('|', None) => out.push_str("$B"),
+            ('>', None) if chars.get(i + 1) == Some(&'>') => { out.push_str("$G$G"); i += 1; },
+            ('>', None) => out.push_str("$G"),
+            ('<', None) => out.push_str("$L"),
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    if quote.is_some() { return Err("unbalanced quotes".to_string()); }
+    Ok(out)
+}
+
+// Returns the doskey token and how many chars it consumed
+fn positional(rest: &str, powershell: bool) -> Option<(String, usize)> {
+    let digit_at = |i: usize| rest.chars().nth(i).filter(|c| ('1'..='9').contains(c));
+    if powershell {
+        let low = rest.to_lowercase();
+        if let Some(idx) = low.strip_prefix("$args[") {
+            let n: String = idx.chars().take_while(|c| c.is_ascii_digit()).collect();
+            let n: usize = n.parse().ok()?;
+            if n < 9 && idx[n.to_string().len()..].starts_with(']') {
+                return Some((format!("${}", n + 1), "$args[".len() + n.to_string().len() + 1));
+            }
+            return None;
+        }
+        if low.starts_with("$args") { return Some(("$*".to_string(), 5)); }
+        return None;
+    }
+    if rest.starts_with("$@") || rest.starts_with("$*") { return Some(("$*".to_string(), 2)); }
+    if let Some(d) = digit_at(1) { return Some((format!("${}", d), 2)); }
+    if rest.starts_with("${") && rest.chars().nth(3) == Some('}')
+        && let Some(d) = digit_at(2) {
+        return Some((format!("${}", d), 4));
+    }
+    None
+}
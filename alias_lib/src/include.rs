@@ -0,0 +1,181 @@
+// alias_lib/src/include.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Include directives in .doskey files:
+//   #include git.doskey
+//   ;include "C:\Shared Aliases\team.doskey"
+// Both spellings are comments to plain doskey, so an including file still loads (minus the
+// included part) on a machine without this tool. The included entries are spliced in at the
+// directive, so a later line in the including file still wins the usual doskey way.
+// Relative paths resolve against the including file. Cycles and runaway nesting are errors.
+//
+// doskey /macrofile= knows nothing of this (nor of template.rs), so providers that shell out
+// load a flattened temp copy instead (flatten_macro_file).
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::*;
+
+pub const INCLUDE_DEPTH_LIMIT: usize = 8;
+
+/// The file and 1-based line an entry was read from, and its [group] section if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroSource {
+    pub path: PathBuf,
+    pub line: usize,
+    pub group: Option<String>,
+}
+
+impl fmt::Display for MacroSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.path.file_name().map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.display().to_string());
+        write!(f, "{}:{}", name, self.line)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourcedPair {
+    pub name: String,
+    pub value: String,
+    pub source: MacroSource,
+}
+
+/// The target of an include line, without quotes or angle brackets.
+pub fn include_directive(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    let rest = trimmed.strip_prefix('#').or_else(|| trimmed.strip_prefix(';'))?;
+    let keyword = rest.get(..7)?;
+    if !keyword.eq_ignore_ascii_case("include") { return None; }
+    let target = rest[7..].strip_prefix(|c: char| c.is_whitespace())?.trim();
+    let target = target.strip_prefix('"').and_then(|t| t.strip_suffix('"'))
+        .or_else(|| target.strip_prefix('<').and_then(|t| t.strip_suffix('>')))
+        .unwrap_or(target)
+        .trim();
+    (!target.is_empty()).then_some(target)
+}
+
+/// Cheap test used to skip the temp file when there is nothing to flatten.
+pub fn has_includes(content: &str) -> bool {
+    content.lines().any(|line| include_directive(line).is_some())
+}
+
+fn resolve_include(including: &Path, target: &str) -> PathBuf {
+    let target = PathBuf::from(target);
+    if target.is_absolute() { return target; }
+    including.parent().unwrap_or(Path::new(".")).join(target)
+}
+
+/// Every data line of `path` and the files it includes, in load order. Names are not
+/// validated here; that stays the caller's call, exactly as with a single file.
+pub fn expand_includes(path: &Path, verbosity: &Verbosity) -> Result<Vec<SourcedPair>, Box<dyn std::error::Error>> {
+    let content = read_alias_text(path).map_err(|e| failure!(verbosity, e))?;
+    let mut pairs = Vec::new();
+    let mut chain = vec![fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())];
+    // Placeholders on or off is decided once per load, not per included file
+    let expand = template::templates_enabled();
+    expand_into(path, &content, None, expand, &mut chain, &mut pairs, verbosity)?;
+    Ok(pairs)
+}
+
+fn expand_into(path: &Path, content: &str, mut group: Option<String>, expand: bool, chain: &mut Vec<PathBuf>, out: &mut Vec<SourcedPair>, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
+    let alias_dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let resolve = |text: &str, here: &MacroSource| -> String {
+        if !expand { return text.to_string(); }
+        let (resolved, unresolved) = template::expand_placeholders(text, &alias_dir);
+        for placeholder in unresolved {
+            shout!(verbosity, AliasIcon::Alert, "Unresolved {} at {}", placeholder, here);
+        }
+        resolved
+    };
+    // [if] guards are per file: one state per nesting level, all must hold
+    let mut guards: Vec<bool> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let here = MacroSource { path: path.to_path_buf(), line: i + 1, group: group.clone() };
+        match template::parse_guard(line) {
+            Some(template::GuardLine::If(guard)) => { guards.push(guard.holds(&alias_dir)); continue; },
+            Some(template::GuardLine::End) => {
+                if guards.pop().is_none() { shout!(verbosity, AliasIcon::Alert, "Unmatched [end] at {}", here); }
+                continue;
+            },
+            Some(template::GuardLine::Malformed(msg)) => {
+                return Err(failure!(verbosity, ErrorCode::Syntax, "Bad guard at {}: {}", here, msg));
+            },
+            None => {},
+        }
+        if guards.contains(&false) { continue; }
+        if let Some(header) = groups::group_header(line) {
+            group = (!header.is_empty()).then_some(header);
+            continue;
+        }
+        if let Some(target) = include_directive(line) {
+            let child = resolve_include(path, &resolve(target, &here));
+            let key = fs::canonicalize(&child).unwrap_or_else(|_| child.clone());
+            if chain.contains(&key) {
+                return Err(failure!(verbosity, ErrorCode::Syntax, "Include cycle at {}: {} is already being loaded", here, child.display()));
+            }
+            if chain.len() > INCLUDE_DEPTH_LIMIT {
+                return Err(failure!(verbosity, ErrorCode::Syntax, "Includes nested deeper than {} at {}", INCLUDE_DEPTH_LIMIT, here));
+            }
+            let nested = read_alias_text(&child).map_err(|e| {
+                let code = if e.kind() == io::ErrorKind::NotFound { ErrorCode::MissingFile } else { ErrorCode::AccessDenied };
+                failure!(verbosity, code, "Cannot include {} (from {}): {}", child.display(), here, e)
+            })?;
+            chain.push(key);
+            expand_into(&child, &nested, group.clone(), expand, chain, out, verbosity)?;
+            chain.pop();
+            continue;
+        }
+        if let Some((name, value)) = is_data_line(line) {
+            let value = resolve(value, &here);
+            out.push(SourcedPair { name: name.to_string(), value, source: here });
+        }
+    }
+    Ok(())
+}
+
+/// A macro file doskey can load directly. Borrowed when `path` is plain UTF-8 with no includes,
+/// templates or disabled groups, otherwise a temp copy of the expanded stream that is deleted on drop.
+#[derive(Debug)]
+pub enum FlatMacroFile {
+    Borrowed(PathBuf),
+    Temp(PathBuf),
+}
+
+impl FlatMacroFile {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Borrowed(p) | Self::Temp(p) => p,
+        }
+    }
+}
+
+impl Drop for FlatMacroFile {
+    fn drop(&mut self) {
+        if let Self::Temp(p) = self { let _ = fs::remove_file(p); }
+    }
+}
+
+pub fn flatten_macro_file(verbosity: &Verbosity, path: &Path) -> Result<FlatMacroFile, Box<dyn std::error::Error>> {
+    let (content, encoding) = match read_alias_file(path) {
+        Ok(c) => c,
+        // Let doskey report a missing or unreadable file the way it always has
+        Err(_) => return Ok(FlatMacroFile::Borrowed(path.to_path_buf())),
+    };
+    // doskey.exe would read a BOM into the first macro name and UTF-16 as garbage
+    if encoding == AliasEncoding::Utf8 && !has_includes(&content) && !template::has_template_syntax(&content) && groups::disabled_groups(path).is_empty() {
+        return Ok(FlatMacroFile::Borrowed(path.to_path_buf()));
+    }
+
+    let flat: String = groups::enabled_pairs(path, expand_includes(path, verbosity)?).iter()
+        .filter(|p| is_valid_name(&p.name))
+        .map(|p| format!("{}={}\r\n", p.name, p.value))
+        .collect();
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let stamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let temp = env::temp_dir().join(format!("alias-{}-{}-{}", std::process::id(), stamp, name));
+    fs::write(&temp, flat).map_err(|e| failure!(verbosity, e))?;
+    Ok(FlatMacroFile::Temp(temp))
+}
@@ -0,0 +1,311 @@
+// alias_lib/src/journal.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Append-only journal of alias mutations, kept beside the snapshots:
+//   <dir>/.alias-backups/<file_name>.journal
+// dispatch_with() diffs both layers (file and RAM) around set, remove, unalias, clear,
+// reload and apply, and appends one transaction per command (a whole --apply batch is one).
+// --undo/--redo append transactions too, so the journal is never rewritten and the undo
+// stack is recomputed by replaying it.
+//
+// LINE FORMAT (one change per line, a transaction is consecutive lines sharing an id)
+//   id <TAB> stamp <TAB> op <TAB> action <TAB> layer <TAB> name <TAB> prev <TAB> new
+//   op is `do`, `undo:<id>` or `redo:<id>`; layer is `file` or `ram`; action is to_cli_args().
+//   A missing value is `\N`; \t \n \r \\ are escaped.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalOp {
+    Do,
+    Undo(u64),
+    Redo(u64),
+}
+
+impl fmt::Display for JournalOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Do => write!(f, "do"),
+            Self::Undo(id) => write!(f, "undo:{}", id),
+            Self::Redo(id) => write!(f, "redo:{}", id),
+        }
+    }
+}
+
+impl JournalOp {
+    fn parse(s: &str) -> Option<Self> {
+        match s.split_once(':') {
+            None if s == "do" => Some(Self::Do),
+            Some(("undo", id)) => id.parse().ok().map(Self::Undo),
+            Some(("redo", id)) => id.parse().ok().map(Self::Redo),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalLayer {
+    File,
+    Ram,
+}
+
+impl JournalLayer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Ram => "ram",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalChange {
+    pub layer: JournalLayer,
+    pub name: String,
+    pub prev: Option<String>,
+    pub new: Option<String>,
+}
+
+impl JournalChange {
+    fn inverse(&self) -> Self {
+        Self { layer: self.layer, name: self.name.clone(), prev: self.new.clone(), new: self.prev.clone() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalTxn {
+    pub id: u64,
+    pub stamp: String,
+    pub op: JournalOp,
+    pub action: String,
+    pub changes: Vec<JournalChange>,
+}
+
+/// Both layers as (name, value) lists, taken before and after a command.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayerState {
+    pub file: Vec<(String, String)>,
+    pub ram: Vec<(String, String)>,
+}
+
+impl LayerState {
+    pub fn capture(provider: &dyn AliasProvider, verbosity: &Verbosity, path: &Path) -> io::Result<Self> {
+        let file = match read_alias_text(path) {
+            Ok(content) => AliasDocument::parse(&content).entries(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { file, ram: provider.get_all_aliases(verbosity)? })
+    }
+
+    /// Per-name differences, file first. Duplicate names resolve to the last one, as doskey does.
+    pub fn diff(&self, after: &Self) -> Vec<JournalChange> {
+        fn layer_diff(layer: JournalLayer, before: &[(String, String)], after: &[(String, String)]) -> Vec<JournalChange> {
+            let lookup = |list: &[(String, String)], name: &str| list.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.clone());
+            let mut names: Vec<&String> = Vec::new();
+            for (name, _) in before.iter().chain(after) {
+                if !names.contains(&name) { names.push(name); }
+            }
+            names.into_iter()
+                .filter_map(|name| {
+                    let (prev, new) = (lookup(before, name), lookup(after, name));
+                    (prev != new).then(|| JournalChange { layer, name: name.clone(), prev, new })
+                })
+                .collect()
+        }
+        let mut changes = layer_diff(JournalLayer::File, &self.file, &after.file);
+        changes.extend(layer_diff(JournalLayer::Ram, &self.ram, &after.ram));
+        changes
+    }
+}
+
+/// Commands whose effect is journaled by dispatch_with.
+pub fn is_journaled(action: &AliasAction) -> bool {
+    matches!(action, AliasAction::Set(_) | AliasAction::Remove(_) | AliasAction::Unalias(_) | AliasAction::Clear | AliasAction::Reload
+        | AliasAction::Apply(_))
+}
+
+pub fn journal_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    backup::backup_dir(path).join(format!("{}.journal", name))
+}
+
+fn escape(value: Option<&str>) -> String {
+    match value {
+        None => "\\N".to_string(),
+        Some(v) => v.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r"),
+    }
+}
+
+fn unescape(cell: &str) -> Option<String> {
+    if cell == "\\N" { return None; }
+    let mut out = String::with_capacity(cell.len());
+    let mut chars = cell.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' { out.push(c); continue; }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    Some(out)
+}
+
+/// Unreadable lines are skipped: a torn append must not lock the user out of --undo.
+pub fn parse_journal(content: &str) -> Vec<JournalTxn> {
+    let mut txns: Vec<JournalTxn> = Vec::new();
+    for line in content.lines() {
+        let cells: Vec<&str> = line.split('\t').collect();
+        let [id, stamp, op, action, layer, name, prev, new] = cells[..] else { continue; };
+        let (Ok(id), Some(op)) = (id.parse::<u64>(), JournalOp::parse(op)) else { continue; };
+        let layer = match layer {
+            "file" => JournalLayer::File,
+            "ram" => JournalLayer::Ram,
+            _ => continue,
+        };
+        let change = JournalChange { layer, name: unescape(name).unwrap_or_default(), prev: unescape(prev), new: unescape(new) };
+        match txns.last_mut() {
+            Some(txn) if txn.id == id => txn.changes.push(change),
+            _ => txns.push(JournalTxn {
+                id,
+                stamp: stamp.to_string(),
+                op,
+                action: unescape(action).unwrap_or_default(),
+                changes: vec![change],
+            }),
+        }
+    }
+    txns
+}
+
+pub fn load_journal(path: &Path) -> io::Result<Vec<JournalTxn>> {
+    match fs::read_to_string(journal_path(path)) {
+        Ok(content) => Ok(parse_journal(&content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Appends one transaction and returns its id. Empty change sets are not recorded.
+pub fn append_txn(verbosity: &Verbosity, path: &Path, op: JournalOp, action: &str, changes: &[JournalChange]) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    if changes.is_empty() { return Ok(None); }
+    let journal = journal_path(path);
+    if let Some(dir) = journal.parent() {
+        fs::create_dir_all(dir).map_err(|e| failure!(verbosity, e))?;
+    }
+    // The id is read and written under one lock, so two shells never share a transaction
+    let _lock = lock_alias_file(verbosity, &journal)?;
+    let id = load_journal(path).map_err(|e| failure!(verbosity, e))?.last().map_or(1, |t| t.id + 1);
+    let stamp = utc_timestamp();
+    let block: String = changes.iter().map(|c| format!("{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        id, stamp, op, escape(Some(action)), c.layer.as_str(), escape(Some(&c.name)), escape(c.prev.as_deref()), escape(c.new.as_deref()))).collect();
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(&journal).map_err(|e| failure!(verbosity, e))?;
+    file.write_all(block.as_bytes()).map_err(|e| failure!(verbosity, e))?;
+    Ok(Some(id))
+}
+
+/// (undoable, redoable) transaction ids, most recent last.
+pub fn undo_stacks(txns: &[JournalTxn]) -> (Vec<u64>, Vec<u64>) {
+    let (mut done, mut undone) = (Vec::new(), Vec::new());
+    for txn in txns {
+        match txn.op {
+            JournalOp::Do => { done.push(txn.id); undone.clear(); },
+            JournalOp::Undo(target) => {
+                done.retain(|&id| id != target);
+                undone.push(target);
+            },
+            JournalOp::Redo(target) => {
+                undone.retain(|&id| id != target);
+                done.push(target);
+            },
+        }
+    }
+    (done, undone)
+}
+
+/// Plays changes against the alias file and the active provider (RAM). A layer that drifted
+/// since the journal was written is overwritten anyway, but the user is told.
+/// Like --apply: the file is rewritten once (one lock, one backup snapshot), then RAM is set
+/// in one pass. If RAM refuses a macro, RAM and the file are put back and nothing happened.
+fn apply_changes(provider: &dyn AliasProvider, verbosity: &Verbosity, path: &Path, changes: &[JournalChange]) -> Result<(), Box<dyn std::error::Error>> {
+    let now = LayerState::capture(provider, verbosity, path).map_err(|e| failure!(verbosity, e))?;
+    let current = |layer: &[(String, String)], name: &str| layer.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.clone());
+    for change in changes {
+        let list = match change.layer { JournalLayer::File => &now.file, JournalLayer::Ram => &now.ram };
+        if current(list, &change.name) != change.prev {
+            shout!(verbosity, AliasIcon::Alert, "'{}' changed in {} since it was journaled; overwriting", change.name, change.layer.as_str());
+        }
+    }
+
+    let file_changes: Vec<&JournalChange> = changes.iter().filter(|c| c.layer == JournalLayer::File).collect();
+    let _lock = if file_changes.is_empty() { None } else { Some(lock_alias_file(verbosity, path)?) };
+    let existed = path.exists();
+    let original = match (file_changes.is_empty(), existed) {
+        (true, _) => None,
+        (false, true) => Some(AliasDocument::load(path, verbosity)?),
+        (false, false) => Some(AliasDocument::default()),
+    };
+    if let Some(original) = &original {
+        let mut doc = original.clone();
+        for change in &file_changes {
+            doc.set(&change.name, change.new.as_deref().unwrap_or(""));
+        }
+        write_alias_document(verbosity, &doc, path)?;
+    }
+
+    let ram_changes: Vec<&JournalChange> = changes.iter().filter(|c| c.layer == JournalLayer::Ram).collect();
+    for (done, change) in ram_changes.iter().enumerate() {
+        let Err(e) = inject_macro(provider, &change.name, change.new.as_deref()) else { continue; };
+
+        // Put RAM back first (it is what the shell sees), then the file
+        for undo in ram_changes[..done].iter().rev() {
+            if let Err(e) = inject_macro(provider, &undo.name, current(&now.ram, &undo.name).as_deref()) {
+                shout!(verbosity, AliasIcon::Alert, "Rollback could not restore '{}' in RAM: {}", undo.name, e);
+            }
+        }
+        if let Some(original) = &original {
+            let restored = if existed { write_alias_document(verbosity, original, path) }
+                else { fs::remove_file(path).map_err(|e| failure!(verbosity, e).into()) };
+            if let Err(e) = restored {
+                shout!(verbosity, AliasIcon::Alert, "Rollback could not restore {}: {} (see --backups)", path.display(), e);
+            }
+        }
+        return Err(failure!(verbosity, ErrorCode::Generic, "RAM refused '{}': {}. Nothing changed.", change.name, e));
+    }
+    Ok(())
+}
+
+/// Reverts the last `steps` transactions, newest first. Returns how many were undone.
+pub fn undo(provider: &dyn AliasProvider, verbosity: &Verbosity, path: &Path, steps: usize) -> Result<usize, Box<dyn std::error::Error>> {
+    let txns = load_journal(path).map_err(|e| failure!(verbosity, e))?;
+    let (done, _) = undo_stacks(&txns);
+    let mut undone = 0;
+    for id in done.iter().rev().take(steps) {
+        let Some(txn) = txns.iter().find(|t| t.id == *id && t.op == JournalOp::Do) else { continue; };
+        let inverse: Vec<JournalChange> = txn.changes.iter().rev().map(JournalChange::inverse).collect();
+        apply_changes(provider, verbosity, path, &inverse)?;
+        append_txn(verbosity, path, JournalOp::Undo(*id), &txn.action, &inverse)?;
+        say!(verbosity, AliasIcon::Success, "Undid #{}: {}", id, txn.action);
+        undone += 1;
+    }
+    Ok(undone)
+}
+
+/// Re-applies the most recently undone transaction. Returns false when there is nothing to redo.
+pub fn redo(provider: &dyn AliasProvider, verbosity: &Verbosity, path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let txns = load_journal(path).map_err(|e| failure!(verbosity, e))?;
+    let (_, undone) = undo_stacks(&txns);
+    let Some(id) = undone.last() else { return Ok(false); };
+    let Some(txn) = txns.iter().find(|t| t.id == *id && t.op == JournalOp::Do) else { return Ok(false); };
+    apply_changes(provider, verbosity, path, &txn.changes)?;
+    append_txn(verbosity, path, JournalOp::Redo(*id), &txn.action, &txn.changes)?;
+    say!(verbosity, AliasIcon::Success, "Redid #{}: {}", id, txn.action);
+    Ok(true)
+}
@@ -0,0 +1,243 @@
+// alias_lib/src/layers.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Layered alias files, lowest precedence first:
+//   team     ALIAS_TEAM_FILE (a shared file, or a directory holding aliases.doskey)
+//   user     the usual get_alias_path() answer (--file, ALIAS_FILE, %APPDATA%, %USERPROFILE%)
+//   project  the nearest .aliases.doskey at or above the current directory, once --allow'ed
+//   session  RAM only (--temp); it has no file
+// Layers load in that order, so a higher layer wins exactly the way a later doskey line does.
+// Missing team/project files are simply absent layers; the user layer is always there.
+// #include'd files belong to the layer of the file that includes them.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AliasScope {
+    Team,
+    User,
+    Project,
+    Session,
+}
+
+impl AliasScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Team => "team",
+            Self::User => "user",
+            Self::Project => "project",
+            Self::Session => "session",
+        }
+    }
+
+    /// Where `--scope` writes. None for the session layer, or a team layer nobody configured.
+    pub fn default_path(&self) -> Option<PathBuf> {
+        match self {
+            Self::Team => team_alias_path(),
+            Self::User => get_alias_path(""),
+            Self::Project => project_alias_path(),
+            Self::Session => None,
+        }
+    }
+}
+
+impl FromStr for AliasScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "team" | "system" => Ok(Self::Team),
+            "user" => Ok(Self::User),
+            "project" => Ok(Self::Project),
+            "session" => Ok(Self::Session),
+            other => Err(format!("Unknown scope '{}' (team, user, project, session)", other)),
+        }
+    }
+}
+
+impl fmt::Display for AliasScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasLayer {
+    pub scope: AliasScope,
+    pub path: PathBuf,
+}
+
+/// Where a merged alias came from, and which lower layers it hides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerOrigin {
+    pub scope: AliasScope,
+    pub path: PathBuf,
+    pub shadowed: Vec<(AliasScope, String)>,
+    pub source: Option<MacroSource>, // file:line, which differs from `path` for #include'd entries
+}
+
+impl LayerOrigin {
+    /// True when the entry came in through an #include rather than the layer file itself.
+    pub fn is_included(&self) -> bool {
+        self.source.as_ref().is_some_and(|s| s.path != self.path)
+    }
+
+    /// The [group] section the winning entry sits in.
+    pub fn group(&self) -> Option<&str> {
+        self.source.as_ref().and_then(|s| s.group.as_deref())
+    }
+
+    /// Worth printing only when layering (or an include) actually played a part.
+    pub fn is_notable(&self) -> bool {
+        self.scope != AliasScope::User || !self.shadowed.is_empty() || self.is_included()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayeredEntry {
+    pub name: String,
+    pub value: String,
+    pub origin: LayerOrigin,
+}
+
+pub fn team_alias_path() -> Option<PathBuf> {
+    let p = PathBuf::from(env::var(ENV_ALIAS_TEAM_FILE).ok().filter(|v| !v.trim().is_empty())?);
+    Some(if p.is_dir() { p.join(DEFAULT_ALIAS_FILENAME) } else { p })
+}
+
+/// The discovered project file, or where a new one would go (the current directory).
+pub fn project_alias_path() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    Some(project::find_project_file(&cwd).unwrap_or_else(|| cwd.join(PROJECT_ALIAS_FILENAME)))
+}
+
+/// The stack for `user`, lowest first. When `user` is itself the team or project file
+/// (e.g. `--scope project`), the real user file still takes the middle slot.
+pub fn alias_layers(user: &Path) -> Vec<AliasLayer> {
+    let team = team_alias_path();
+    let project = project_alias_path();
+    let user = if team.as_deref() == Some(user) || project.as_deref() == Some(user) {
+        get_alias_path("").unwrap_or_else(|| user.to_path_buf())
+    } else {
+        user.to_path_buf()
+    };
+
+    // An untrusted project file is not a layer at all (see project.rs)
+    let trust = project::ProjectState::for_user(&user);
+    let mut layers: Vec<AliasLayer> = Vec::new();
+    let candidates = [
+        (AliasScope::Team, team.filter(|p| p.is_file())),
+        (AliasScope::User, Some(user)),
+        (AliasScope::Project, project.filter(|p| p.is_file() && project::trust_state(&trust, p) == project::TrustState::Trusted)),
+    ];
+    for (scope, path) in candidates {
+        let Some(path) = path else { continue; };
+        // One file in two roles loads once, in the higher role
+        layers.retain(|l| l.path != path);
+        layers.push(AliasLayer { scope, path });
+    }
+    layers
+}
+
+/// Folds the layers into one view. Names compare case-insensitively, like doskey;
+/// the winner keeps its own spelling and lists the values it hides.
+pub fn merge_layers(layers: Vec<(AliasLayer, Vec<(String, String)>)>) -> Vec<LayeredEntry> {
+    merge_sourced_layers(layers.into_iter()
+        .map(|(layer, pairs)| (layer, pairs.into_iter().map(|(n, v)| (n, v, None)).collect()))
+        .collect())
+}
+
+/// One layer's (name, value, file:line) entries, as read by load_layers.
+pub type SourcedLayer = (AliasLayer, Vec<(String, String, Option<MacroSource>)>);
+
+/// merge_layers for entries that know their file:line.
+pub fn merge_sourced_layers(layers: Vec<SourcedLayer>) -> Vec<LayeredEntry> {
+    let mut merged: Vec<LayeredEntry> = Vec::new();
+    for (layer, pairs) in layers {
+        for (name, value, source) in pairs {
+            let origin = LayerOrigin { scope: layer.scope, path: layer.path.clone(), shadowed: Vec::new(), source };
+            match merged.iter_mut().find(|e| e.name.eq_ignore_ascii_case(&name)) {
+                Some(existing) => {
+                    let mut shadowed = std::mem::take(&mut existing.origin.shadowed);
+                    // A duplicate inside the same file is not a shadow, just a rewrite
+                    if existing.origin.scope != layer.scope {
+                        shadowed.push((existing.origin.scope, existing.value.clone()));
+                    }
+                    *existing = LayeredEntry { name, value, origin: LayerOrigin { shadowed, ..origin } };
+                },
+                None => merged.push(LayeredEntry { name, value, origin }),
+            }
+        }
+    }
+    merged
+}
+
+/// Reads every layer for `user` and merges them. Unreadable lower layers are skipped
+/// with a warning; only the user layer is allowed to fail the call.
+pub fn load_layers(verbosity: &Verbosity, user: &Path) -> Result<Vec<LayeredEntry>, Box<dyn std::error::Error>> {
+    load_layers_with(verbosity, user, read_sourced_pairs)
+}
+
+/// load_layers with each layer file read by `read`, e.g. under a group state not saved yet.
+pub(crate) fn load_layers_with(verbosity: &Verbosity, user: &Path,
+    read: impl Fn(&Path, &Verbosity) -> Result<Vec<SourcedPair>, Box<dyn std::error::Error>>) -> Result<Vec<LayeredEntry>, Box<dyn std::error::Error>> {
+    let mut loaded = Vec::new();
+    for layer in alias_layers(user) {
+        match read(&layer.path, verbosity) {
+            Ok(pairs) => loaded.push((layer, pairs.into_iter().map(|p| (p.name, p.value, Some(p.source))).collect())),
+            Err(e) if layer.scope == AliasScope::User => return Err(e),
+            Err(e) => shout!(verbosity, AliasIcon::Alert, "Skipping {} layer {}: {}", layer.scope, layer.path.display(), e),
+        }
+    }
+    Ok(merge_sourced_layers(loaded))
+}
+
+/// mesh_logic for N sources: the merged winners play the file, and each entry keeps its origin.
+pub fn mesh_layers(os_list: Vec<(String, String)>, layered: Vec<LayeredEntry>) -> Vec<AliasEntryMesh> {
+    let (pairs, origins): (Vec<_>, Vec<_>) = layered.into_iter().map(|e| ((e.name, e.value), e.origin)).unzip();
+    let mut mesh = mesh_logic(os_list, pairs);
+    // mesh_logic keeps file order first, so the origins line up with the head of the mesh
+    for (entry, origin) in mesh.iter_mut().zip(origins) {
+        entry.origin = Some(origin);
+    }
+    mesh
+}
+
+/// Loads the whole stack into RAM, lowest layer first. With a single layer this is
+/// exactly the provider's own reload_full.
+pub fn reload_layers(provider: &dyn AliasProvider, verbosity: &Verbosity, path: &Path, clear: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let layers = alias_layers(path);
+    if let Some(skipped) = project_alias_path().filter(|p| p.is_file() && !layers.iter().any(|l| l.path == *p)) {
+        say!(verbosity, AliasIcon::Info, "Skipping untrusted {} (review it, then run: alias --allow)", skipped.display());
+    }
+    if layers.len() <= 1 {
+        return provider.reload_full(verbosity, path, clear);
+    }
+    if clear { provider.purge_ram_macros(verbosity)?; }
+    for layer in &layers {
+        provider.raw_reload_from_file(verbosity, &layer.path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{} layer {}: {}", layer.scope, layer.path.display(), e)))?;
+    }
+    let merged = load_layers(&Verbosity::silent(), path)?;
+    let names: Vec<String> = layers.iter().map(|l| l.scope.to_string()).collect();
+    say!(verbosity, AliasIcon::Success, "Reload: {} macros injected from {} layers ({}).", merged.len(), layers.len(), names.join(" < "));
+    Ok(())
+}
+
+/// Short text for audits: `[project] shadows user:'git status'`, `[user] defined in git.doskey:12`.
+pub fn origin_note(origin: &LayerOrigin) -> Option<String> {
+    if !origin.is_notable() { return None; }
+    let mut note = format!("[{}]", origin.scope);
+    if let Some(source) = origin.source.as_ref().filter(|_| origin.is_included()) {
+        note.push_str(&format!(" defined in {}", source));
+    }
+    let shadows: Vec<String> = origin.shadowed.iter().map(|(scope, value)| format!("{}:'{}'", scope, value)).collect();
+    if !shadows.is_empty() {
+        note.push_str(&format!(" shadows {}", shadows.join(", ")));
+    }
+    Some(note)
+}
@@ -13,7 +13,9 @@ use std::process::Command;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use fs2::FileExt;
+use regex::RegexBuilder;
 #[allow(unused_imports)]
 use function_name::named;
 
@@ -53,6 +55,9 @@ macro_rules! voice {
             in_startup: false,
             in_setup: false,
             writer: None,
+            format: $crate::OutputFormat::Text,
+            group: None,
+            filter: $crate::AuditFilter::default(),
         }
     };
     // 2. General case
@@ -66,6 +71,9 @@ macro_rules! voice {
             in_startup: false,
             in_setup: false,
             writer: None,
+            format: $crate::OutputFormat::Text,
+            group: None,
+            filter: $crate::AuditFilter::default(),
             // We store the OPTION of the tip string here, once.
             display_tip: match tips_setting {
                 $crate::ShowTips::On => Some($crate::get_random_tip()),
@@ -191,6 +199,46 @@ include!(concat!(env!("OUT_DIR"), "/version_data.rs"));
 #[path = "../../versioning.rs"]
 pub mod versioning;
 pub use versioning::Versioning;
+pub mod memory;
+pub use memory::MemoryProvider;
+pub mod config;
+pub use config::{config_path, effective_settings, load_config, AliasConfig, EffectiveSetting, Setting, SettingSource, CONFIG_FILE_NAME, SETTINGS};
+pub mod encoding;
+pub use encoding::{read_alias_file, read_alias_text, AliasEncoding};
+pub mod document;
+pub use document::{AliasDocument, AliasMeta, AliasNode};
+pub mod export;
+pub use export::{export_aliases, translate_macro, ExportFormat, ExportReport};
+pub mod import;
+pub mod macro_lang;
+pub use macro_lang::{expand_macro, macro_equivalent, split_doskey_args, tokenize_macro, validate_macro, MacroExpansion, MacroIssue, MacroParse, MacroToken};
+pub use import::{parse_import, ImportFormat, ImportOptions, ImportPolicy, ImportReport};
+pub mod output;
+pub mod audit;
+pub use audit::{triple_audit, AuditEntry, AuditFilter, AuditReport, AuditSort, AuditStatus};
+pub use output::{render_records, OutputFormat, Record};
+pub mod backup;
+pub use backup::{find_backup, list_backups, restore_backup, snapshot_alias_file, BackupDiff, BackupInfo};
+pub mod journal;
+pub use journal::{load_journal, undo_stacks, JournalChange, JournalLayer, JournalOp, JournalTxn};
+pub mod template;
+pub use template::{expand_placeholders, parse_guard, Guard, GuardLine, GuardTest};
+pub mod apply;
+pub use apply::{apply_batch, parse_batch, BatchOp, BatchSummary};
+pub mod groups;
+pub use groups::{disabled_groups, group_header, set_group_enabled};
+pub mod options;
+pub use options::{find_option, AliasErrorString, HelpSection, OptionSpec, OptionValue, OPTIONS};
+pub mod completion;
+pub use completion::{completion_script, completion_specs, CompletionArg, CompletionSpec};
+pub mod suggest;
+pub use suggest::{closest, edit_distance, fuzzy_match, glob_match, known_aliases};
+pub mod include;
+pub use include::{expand_includes, flatten_macro_file, FlatMacroFile, MacroSource, SourcedPair, INCLUDE_DEPTH_LIMIT};
+pub mod project;
+pub use project::{allow_project, enter_project, find_project_file, leave_project, trust_state, ProjectState, TrustState};
+pub mod layers;
+pub use layers::{alias_layers, load_layers, merge_layers, merge_sourced_layers, mesh_layers, reload_layers, AliasLayer, AliasScope, LayerOrigin, LayeredEntry};
 
 impl Versioning {
     pub fn current() -> &'static Self {
@@ -260,11 +308,16 @@ pub const APPDATA_PATH: &str = "APPDATA";
 pub const USERPROFILE_PATH: &str = "USERPROFILE";
 pub const ENV_ALIAS_FILE: &str = "ALIAS_FILE";
 pub const ENV_ALIAS_OPTS: &str = "ALIAS_OPTS";
+pub const ENV_ALIAS_BACKUPS: &str = "ALIAS_BACKUPS";
+pub const ENV_ALIAS_TEAM_FILE: &str = "ALIAS_TEAM_FILE";
+pub const ENV_ALIAS_EXPAND: &str = "ALIAS_EXPAND";
+pub const ENV_ALIAS_CONFIG: &str = "ALIAS_CONFIG";
 const ENV_EDITOR: &str = "EDITOR";
 const ENV_VISUAL: &str = "VISUAL";
 const ENV_PATHEXT: &str = "PATHEXT";
 const ENV_PATH: &str = "PATH";
 pub const DEFAULT_ALIAS_FILENAME: &str = "aliases.doskey";
+pub const PROJECT_ALIAS_FILENAME: &str = ".aliases.doskey";
 const DEFAULT_APPDATA_ALIAS_DIR: &str = "alias_tool";
 const FALLBACK_EDITOR: &str = "notepad";
 pub const IO_RESPONSIVENESS_THRESHOLD: Duration = Duration::from_millis(500);
@@ -305,12 +358,16 @@ pub enum ErrorCode {
     AccessDenied = 6,
     MissingName = 7,
     UnknownFileType = 8,
+    Desync = 9,          // Audit ran fine but the layers disagree
+    LockTimeout = 10,    // Another alias process kept the file locked
 }
 
 #[derive(Debug, Clone)]
 pub struct Task {
     pub action: AliasAction,
     pub path: PathBuf,
+    /// The command line as typed, without the alias.toml/ALIAS_OPTS defaults (--config reports on it).
+    pub args: Vec<String>,
 }
 pub struct TaskQueue {
     pub tasks: Vec<Task>,
@@ -324,7 +381,7 @@ impl TaskQueue {
         }
     }
     pub fn push_file(&mut self, action: AliasAction, path: PathBuf) {
-        self.tasks.push(Task { action, path, });
+        self.tasks.push(Task { action, path, args: Vec::new() });
     }
     pub fn push(&mut self, action: AliasAction) {
         self.push_file(action, PathBuf::new());
@@ -487,11 +544,25 @@ pub struct AliasEntryMesh {
     pub name: String,
     pub os_value: Option<String>,
     pub file_value: Option<String>,
+    pub meta: Option<AliasMeta>,
+    pub origin: Option<LayerOrigin>, // None when the file side is a single, unlayered file
 }
 impl AliasEntryMesh {
     pub fn is_empty_definition(&self) -> bool {
         self.os_value.is_none() && self.file_value.is_none()
     }
+    pub fn group(&self) -> Option<&str> {
+        self.origin.as_ref().and_then(LayerOrigin::group)
+    }
+    pub fn status(&self) -> AuditStatus {
+        if !is_valid_name(&self.name) { return AuditStatus::Corrupt; }
+        match (&self.os_value, &self.file_value) {
+            (Some(os), Some(fi)) if macro_equivalent(os, fi) => AuditStatus::Synced,
+            (Some(_), Some(_)) => AuditStatus::Desync,
+            (Some(_), None) => AuditStatus::Phantom,
+            (None, _) => AuditStatus::Pending,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -500,6 +571,7 @@ pub enum ProviderType {
     Win32,       // Native Windows console
     Wrapper,     // Cross-platform wrapper
     Hybrid,
+    Memory,      // In-process table (tests / dry runs)
     Custom(String)
 }
 
@@ -512,6 +584,9 @@ pub struct Verbosity {
     pub in_startup: bool,
     pub in_setup: bool,
     pub writer: Option<Arc<Mutex<dyn std::io::Write + Send>>>,
+    pub format: OutputFormat,
+    pub group: Option<String>, // --group: audits only show this [section]
+    pub filter: AuditFilter,   // --list, --grep, --<status>-only, --sort
 }
 impl Verbosity {
     pub fn is_silent(&self) -> bool {
@@ -526,6 +601,9 @@ impl Verbosity {
             in_startup: false,
             in_setup: false,
             writer: None,
+            format: OutputFormat::Text,
+            group: None,
+            filter: AuditFilter::default(),
         }
     }
 
@@ -538,6 +616,9 @@ impl Verbosity {
             in_startup: false,
             in_setup: false,
             writer: None,
+            format: OutputFormat::Text,
+            group: None,
+            filter: AuditFilter::default(),
         }
     }
 
@@ -550,6 +631,9 @@ impl Verbosity {
             in_startup: false,
             in_setup: false,
             writer: None,
+            format: OutputFormat::Text,
+            group: None,
+            filter: AuditFilter::default(),
         }
     }
     pub fn mute() -> Self {
@@ -561,6 +645,9 @@ impl Verbosity {
             in_startup: false,
             in_setup: false,
             writer: None,
+            format: OutputFormat::Text,
+            group: None,
+            filter: AuditFilter::default(),
         }
     }
 
@@ -606,7 +693,8 @@ impl Verbosity {
     }
 
     pub fn say(&self, msg: &str) {
-        if msg.is_empty() || self.level < VerbosityLevel::Normal { return }
+        // Machine formats own stdout, chatter would corrupt the records
+        if msg.is_empty() || self.level < VerbosityLevel::Normal || self.format.is_machine() { return }
         if msg.trim().is_empty() {
             if !self.emit("") { println!(); }
         } else {
@@ -615,7 +703,7 @@ impl Verbosity {
     }
 
     pub fn shout(&self, msg: &str) {
-        if msg.is_empty() || self.level <= VerbosityLevel::Mute { return }
+        if msg.is_empty() || self.level <= VerbosityLevel::Mute || self.format.is_machine() { return }
         if msg.trim().is_empty() {
             if !self.emit("") { println!(); }
         } else {
@@ -623,6 +711,13 @@ impl Verbosity {
         }
     }
 
+    /// Data output for --format json|tsv. Follows whisper's level rules.
+    pub fn records(&self, records: &[Record]) {
+        for line in render_records(records, self.format) {
+            self.whisper(&line);
+        }
+    }
+
     pub fn scream(&self, msg: &str) {
         if msg.trim().is_empty() {
             if !self.emit("") { eprintln!(); }
@@ -690,6 +785,9 @@ impl Verbosity {
             in_startup: false,
             in_setup: false,
             writer: Some(Arc::new(Mutex::new(buffer))),
+            format: OutputFormat::Text,
+            group: None,
+            filter: AuditFilter::default(),
         }
     }
 }
@@ -701,6 +799,9 @@ impl std::fmt::Debug for Verbosity {
             .field("show_tips", &self.show_tips)
             .field("display_tip", &self.display_tip)
             .field("in_startup", &self.in_startup)
+            .field("format", &self.format)
+            .field("group", &self.group)
+            .field("filter", &self.filter)
             .field("has_writer", &self.writer.is_some()) // Honest check
             .finish()
     }
@@ -838,46 +939,39 @@ pub enum AliasAction {
     Version,
     VersionShort,
     Which,
+    Describe(SetOptions),
+    Export(String),
+    Import(ImportOptions),
+    Expand(String, Vec<String>),
+    Format(String),
+    Backups,
+    Restore(String),
+    Undo(usize),
+    Redo,
+    History(String),
+    Scope(String),
+    Enter,
+    Leave,
+    Allow(String),
+    ExpandFile(String),
+    EnableGroup(String),
+    DisableGroup(String),
+    Group(String),
+    List(String),
+    Grep(String),
+    Only(AuditStatus),
+    Sort(String),
+    Apply(String),
+    Encoding(String),
+    ConfigShow,
+    ConfigSet(String, String),
+    Completions(String),
+    Fuzzy(String),
     Toggle(Box<AliasAction>, bool),
 }
 impl AliasAction {
     pub fn to_cli_args(&self) -> String {
         match self {
-            AliasAction::Clear             => "--clear".to_string(),
-            AliasAction::Edit(None)        => "--edalias".to_string(),
-            AliasAction::Edit(Some(editor)) => format!("--edalias=\"{}\"", editor),
-            AliasAction::File              => "--file".to_string(),
-            AliasAction::Help              => "--help".to_string(),
-            AliasAction::License           => "--license".to_string(),
-            AliasAction::Reload            => "--reload".to_string(),
-            AliasAction::Setup             => "--setup".to_string(),
-            AliasAction::ShowAll           => "--show-all".to_string(),
-            AliasAction::Startup           => "--startup".to_string(),
-            AliasAction::Temp              => "--temp".to_string(),
-            AliasAction::NoTemp            => "--no-temp".to_string(),
-            AliasAction::Version           => "--version".to_string(),
-            AliasAction::VersionShort      => "--ver".to_string(),
-            AliasAction::Which             => "--which".to_string(),
-
-            // --- The Symmetric Toggles ---
-            AliasAction::Case              => "--case".to_string(),
-            AliasAction::NoCase            => "--no-case".to_string(),
-            AliasAction::Quiet             => "--quiet".to_string(),
-            AliasAction::NoQuiet           => "--no-quiet".to_string(),
-            AliasAction::Icons             => "--icons".to_string(),
-            AliasAction::NoIcons           => "--no-icons".to_string(),
-            AliasAction::Tips              => "--tips".to_string(),
-            AliasAction::NoTips            => "--no-tips".to_string(),
-
-            // --- Data-Carrying Variants ---
-            AliasAction::Remove(opts) => {
-                if opts.name.is_empty() { "--remove".to_string() }
-                else { format!("--remove {}", opts.name) }
-            },
-            AliasAction::Unalias(opts) => {
-                if opts.name.is_empty() { "--unalias".to_string() }
-                else { format!("--unalias {}", opts.name) }
-            },
             AliasAction::Query(name)       => name.clone(),
             AliasAction::Set(opts) => {
                 let mut s = format!("{}={}", opts.name, opts.value);
@@ -889,22 +983,17 @@ impl AliasAction {
             AliasAction::Invalid           => "".to_string(),
             AliasAction::Fail              => "".to_string(),
             AliasAction::Toggle(from, to)  => format!("__internal_toggle={}:{}", from, to),
+            // --- Everything with a row in the option table ---
+            _ => self.render_option().unwrap_or_default(),
         }
     }
 
     pub fn requires_file(&self) -> bool {
         match self {
-            | AliasAction::Edit(_)
-            | AliasAction::File
-            | AliasAction::Reload
-            | AliasAction::Remove(_)
-            | AliasAction::Set(_)
-            | AliasAction::ShowAll
-            | AliasAction::Which
-            | AliasAction::Startup
-            => true,
-            // Everything else (Help, Setup, Which, etc.) doesn't touch the d
-            _ => false,
+            AliasAction::Set(_) => true,
+            // Only the alias-name lookup the scripts call back into needs the file
+            AliasAction::Completions(target) => target == completion::NAMES_TARGET,
+            _ => self.option().is_some_and(|(spec, _)| spec.requires_file),
         }
     }
     pub fn intent(arg: &str) -> Self {
@@ -925,13 +1014,11 @@ impl FromStr for AliasAction {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // 1. Tokenize the input immediately to solve E0425
-        let parts: Vec<&str> = s.split_whitespace().collect();
-        if parts.is_empty() { return Ok(Self::Invalid); }
+        let text = s.trim();
+        let Some(first_token) = text.split_whitespace().next() else { return Ok(Self::Invalid); };
 
-        let low = s.to_lowercase();
-
-        // 2. HIGH PRIORITY: The Internal Toggle (Unchanged)
+        // 1. HIGH PRIORITY: The Internal Toggle
+        let low = text.to_lowercase();
         if low.starts_with("__internal_toggle=") {
             if let Some((_, right)) = low.split_once('=') {
                 if let Some((name, state_str)) = right.split_once(':') {
@@ -945,157 +1032,37 @@ impl FromStr for AliasAction {
             return Ok(Self::Invalid);
         }
 
-        // 3. Pre-process Negation on the FIRST token (the command)
-        let first_token = parts[0].to_lowercase();
-        let (is_negated, search_term) = if let Some(stripped) = first_token.strip_prefix("--no-") {
-            (true, format!("--{}", stripped))
-        } else {
-            (false, first_token.clone())
-        };
-
-        // 4. Handle Key=Value Pairs (Unchanged)
-        if let Some((left, right)) = first_token.split_once('=') {
-            return Ok(match left {
-                "--edalias" | "--edaliases" => Self::Edit(Some(right.trim_matches('"').to_string())),
-                _ if !left.starts_with("--") => Self::Set(SetOptions {
-                    name: left.to_string(),
-                    value: right.to_string(),
-                    volatile: false,
-                    force_case: false,
-                }),
-                _ => Self::Invalid,
-            });
-        }
-
-        // 5. Standalone Flags - Now using 'parts' safely
-        match search_term.as_str() {
-            "--icons" => Ok(if is_negated { Self::NoIcons } else { Self::Icons }),
-            "--tips"  => Ok(if is_negated { Self::NoTips  } else { Self::Tips  }),
-            "--quiet" => Ok(if is_negated { Self::NoQuiet } else { Self::Quiet }),
-            "--case" => Ok(if is_negated { Self::NoCase } else { Self::Case }),
-            "--temp" => Ok(if is_negated { Self::NoTemp } else { Self::Temp }),
-
-            "--unalias" => {
-                if is_negated { return Ok(Self::Invalid); }
-                if parts.len() > 1 {
-                    // Symmetry Fix: Reconstruct the name from the second token
-                    Ok(Self::Unalias(SetOptions::volatile(parts[1].to_string(), false)))
-                } else {
-                    Ok(Self::Unalias(SetOptions::empty(true)))
-                }
-            },
-            "--remove" => {
-                if is_negated { return Ok(Self::Invalid); }
-                if parts.len() > 1 {
-                    Ok(Self::Remove(SetOptions::involatile(parts[1].to_string(), false)))
-                } else {
-                    Ok(Self::Remove(SetOptions::empty(false)))
-                }
-            },
-            "--help"                    => Ok(if is_negated { Self::Invalid } else { Self::Help }),
-            "--license"                 => Ok(if is_negated { Self::Invalid } else { Self::License }),
-            "--reload"                  => Ok(if is_negated { Self::Invalid } else { Self::Reload }),
-            "--setup"                   => Ok(if is_negated { Self::Invalid } else { Self::Setup }),
-            "--startup"                 => Ok(if is_negated { Self::Invalid } else { Self::Startup }),
-            "--clear"                   => Ok(if is_negated { Self::Invalid } else { Self::Clear }),
-            "--which"                   => Ok(if is_negated { Self::Invalid } else { Self::Which }),
-            "--ver"                     => Ok(if is_negated { Self::Invalid } else { Self::VersionShort }),
-            "--version"                 => Ok(if is_negated { Self::Invalid } else { Self::Version }),
-            "--edalias" | "--edaliases" => Ok(if is_negated { Self::Invalid } else { Self::Edit(None) }),
-            "--show-all"                => Ok(if is_negated { Self::Invalid } else { Self::ShowAll }),
-            "--file"                    => Ok(if is_negated { Self::Invalid } else { Self::File }),
+        // 2. Switches: the option table knows the spelling, negation and values
+        if first_token.starts_with("--") {
+            return Ok(Self::parse_option(text));
+        }
 
-            _ if first_token.starts_with("--")  => Ok(Self::Invalid),
-            _                                   => Ok(Self::Query(s.to_string())),
+        // 3. name=value, with the modifiers to_cli_args appends
+        if first_token.contains('=') && let Some((name, rest)) = text.split_once('=') {
+            let (mut value, mut volatile, mut force_case) = (rest, false, false);
+            loop {
+                if let Some(v) = value.strip_suffix(" --case") { value = v; force_case = true; }
+                else if let Some(v) = value.strip_suffix(" --temp") { value = v; volatile = true; }
+                else { break; }
+            }
+            return Ok(Self::Set(SetOptions { name: name.to_string(), value: value.to_string(), volatile, force_case }));
         }
+        Ok(Self::Query(s.to_string()))
     }
 }
 impl fmt::Display for AliasAction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Clear                 => write!(f, "--clear"),
-            Self::Edit(None)            => write!(f, "--edalias"),
-            Self::Edit(Some(ed))=> write!(f, "--edalias={}", ed),
-            Self::File                  => write!(f, "--file"),
-            Self::Case                  => write!(f, "--case"),
-            Self::NoCase                => write!(f, "--no-case"),
-            Self::Help                  => write!(f, "--help"),
-            Self::Icons                 => write!(f, "--icons"),
-            Self::NoIcons               => write!(f, "--no-icons"),
-            Self::License               => write!(f, "--license"),
-            Self::Query(q)      => write!(f, "{}", q),
-            Self::Quiet                 => write!(f, "--quiet"),
-            Self::NoQuiet               => write!(f, "--no-quiet"),
-            Self::Reload                => write!(f, "--reload"),
-            Self::Remove(opts) => {
-                if opts.name.is_empty() { write!(f, "--remove") }
-                else { write!(f, "--remove {}", opts.name) }
-            },
-            Self::Set(opt)  => write!(f, "{}={}", opt.name, opt.value),
-            Self::Setup                 => write!(f, "--setup"),
-            Self::ShowAll               => write!(f, "--show-all"),
-            Self::Startup               => write!(f, "--startup"),
-            Self::Temp                  => write!(f, "--temp"),
-            Self::NoTemp                => write!(f, "--no-temp"),
-            Self::Tips                  => write!(f, "--tips"),
-            Self::NoTips                => write!(f, "--no-tips"),
-            Self::Unalias(opts) => {
-                if opts.name.is_empty() { write!(f, "--unalias") }
-                else { write!(f, "--unalias {}", opts.name) }
-            },
-            Self::Version               => write!(f, "--version"),
-            Self::VersionShort          => write!(f, "--ver"),
-            Self::Which                 => write!(f, "--which"),
-            // options the actually have ro CLI
+            // Parser states have no command line
             Self::Fail                  => write!(f, "--fail"),
             Self::Invalid               => write!(f, "--invalid"),
-            Self::Toggle(from, to) => write!(f, "__internal_toggle={}:{}", from, to),
+            _ => write!(f, "{}", self.to_cli_args()),
         }
     }
 }
 impl AliasAction {
     pub fn error (&self) -> AliasErrorString<'_> { AliasErrorString(self) }
 }
-pub struct AliasErrorString<'a>(&'a AliasAction);
-impl<'a> std::fmt::Display for AliasErrorString<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // You must match on self.0 because self is the wrapper struct
-        match self.0 {
-            AliasAction::Case => write!(f, "Error setting/using force case"),
-            AliasAction::NoCase => write!(f, "Error unsetting/disabling force case"),
-            AliasAction::Clear => write!(f, "Clear aliases error"),
-            AliasAction::Edit(path) => match path {
-                Some(exe) => write!(f, "Error editing alias file with: {}", exe),
-                None => write!(f, "Error editing alias file with default editor"),
-            },
-            AliasAction::Fail => write!(f, "General Error"),
-            AliasAction::File => write!(f, "Error loading file for actions or load"),
-            AliasAction::Help => write!(f, "Display help"),
-            AliasAction::Invalid => write!(f, "Unrecognized or malformed command"),
-            AliasAction::Icons => write!(f, "Error setting icons"),
-            AliasAction::NoIcons => write!(f, "Error unsetting icons"),
-            AliasAction::License => write! (f, "Error displaying license"),
-            AliasAction::Query(name) => write!(f, "Error querying alias {}: ", name),
-            AliasAction::Reload => write!(f, "Error reloading configuration"),
-            AliasAction::Remove(opts) => write!(f, "Error removing alias: {}", opts.name),
-            AliasAction::Set(opts) => write!(f, "Error setting alias: {}", opts.name),
-            AliasAction::Setup => write!(f, "Error setting up autorun registry entry"),
-            AliasAction::ShowAll => write!(f, "Error showing all aliases"),
-            AliasAction::Startup => write!(f, "Error setting/using statup mode"),
-            AliasAction::Tips => write!(f, "Error setting tips"),
-            AliasAction::NoTips => write!(f, "Error unsetting tips"),
-            AliasAction::Temp => write!(f, "Error setting/using process as memory only"),
-            AliasAction::NoTemp => write!(f, "Error setting/using process as dual (mem/disk))"),
-            AliasAction::Unalias(opts) => write!(f, "Error unaliasing alias: {}", opts.name),
-            AliasAction::Version => write!(f, "Error getting versions"),
-            AliasAction::VersionShort => write!(f, "Error getting versions"),
-            AliasAction::Which => write!(f, "Error running diagnostics"),
-            AliasAction::Quiet => write!(f, "Error setting/using quiet mode"),
-            AliasAction::NoQuiet => write!(f, "Error unsetting/disabling quiet mode"),
-            AliasAction::Toggle(from, to) => write!(f, "Error reverse mapping {} to {}", from, to),
-        }
-    }
-}
 
 // --- Indexes ---
 pub const ICON_TYPES: usize = AliasIcon::_VariantCount as usize;
@@ -1173,18 +1140,21 @@ pub const TIPS_ARRAY: &[&str] = &[
 //////////////////////////////////////////////////////
 
 // --- Providers/Interface ---
+// Providers are values, not types. Anything a backend needs (exe silo, registry
+// handle, a fake table) lives on the struct, so instances can be built at runtime,
+// composed (see Hybrid) and handed to run_with as a &dyn AliasProvider.
 pub trait AliasProvider {
-    fn raw_set_macro(name: &str, value: Option<&str>) -> io::Result<bool>;
-    fn raw_reload_from_file(verbosity: &Verbosity, path: &Path) -> io::Result<()>;
-    fn get_all_aliases(verbosity: &Verbosity) -> io::Result<Vec<(String, String)>>;
-    fn write_autorun_registry(cmd: &str, verbosity: &Verbosity) -> io::Result<()>;
-    fn read_autorun_registry() -> String;
-    fn purge_ram_macros(verbosity: &Verbosity) -> io::Result<PurgeReport>;
-    fn purge_file_macros(verbosity: &Verbosity, path: &Path) -> io::Result<PurgeReport> {
+    fn raw_set_macro(&self, name: &str, value: Option<&str>) -> io::Result<bool>;
+    fn raw_reload_from_file(&self, verbosity: &Verbosity, path: &Path) -> io::Result<()>;
+    fn get_all_aliases(&self, verbosity: &Verbosity) -> io::Result<Vec<(String, String)>>;
+    fn write_autorun_registry(&self, cmd: &str, verbosity: &Verbosity) -> io::Result<()>;
+    fn read_autorun_registry(&self) -> String;
+    fn purge_ram_macros(&self, verbosity: &Verbosity) -> io::Result<PurgeReport>;
+    fn purge_file_macros(&self, verbosity: &Verbosity, path: &Path) -> io::Result<PurgeReport> {
         let mut report = PurgeReport::default();
 
         // 1. Read the file into memory
-        let content = std::fs::read_to_string(path)
+        let content = read_alias_text(path)
             .map_err(|e| io::Error::new(io::ErrorKind::NotFound, format!("Could not read alias file: {}", e)))?;
         whisper!(verbosity, AliasIcon::File, "Clearing macros from {}", path.display());
         // 2. Iterate through every data line in the file
@@ -1192,7 +1162,7 @@ pub trait AliasProvider {
             if let Some((name, _)) = is_data_line(line) {
                 // 3. Unset the alias from the Win32/OS RAM
                 // Passing None to raw_set_macro is the trigger to delete
-                if Self::raw_set_macro(name, None)? {
+                if self.raw_set_macro(name, None)? {
                     report.cleared.push(name.to_string());
                 } else {
                     report.failed.push((name.to_string(), 0));
@@ -1201,24 +1171,24 @@ pub trait AliasProvider {
         }
         Ok(report)
     }
-    fn reload_full(verbosity: &Verbosity, path: &Path, clear: bool) -> Result<(), Box<dyn std::error::Error>> {
+    fn reload_full(&self, verbosity: &Verbosity, path: &Path, clear: bool) -> Result<(), Box<dyn std::error::Error>> {
         // Call our own purge logic
-        if clear { Self::purge_ram_macros(verbosity)?; }
+        if clear { self.purge_ram_macros(verbosity)?; }
 
-        let content = std::fs::read_to_string(path).map_err(|e| failure!(verbosity, e))?;
+        let content = read_alias_text(path).map_err(|e| failure!(verbosity, e))?;
         let count = content.lines().filter_map(is_data_line).count();
 
         // Call the engine
-        Self::raw_reload_from_file(verbosity, path)?;
+        self.raw_reload_from_file(verbosity, path)?;
 
         say!(verbosity, AliasIcon::Success, "Reload: {} macros injected.", count);
         Ok(())
     }
-    fn sanitize_path(original: &PathBuf) -> String {
+    fn sanitize_path(&self, original: &Path) -> String {
         // Strip existing quotes and wrap in exactly ONE set of double quotes
         format!("\"{}\"", original.to_string_lossy().trim_matches('"'))
     }
-    fn setup_alias(verbosity: &Verbosity, queue: &TaskQueue) -> io::Result<()> {
+    fn setup_alias(&self, verbosity: &Verbosity, queue: &TaskQueue) -> io::Result<()> {
         let mut parts: Vec<String> = Vec::new();
 
         for task in &queue.tasks {
@@ -1228,7 +1198,7 @@ pub trait AliasProvider {
             match &task.action {
                 // Reconstruct the pivot exactly as it was resolved
                 AliasAction::File => {
-                    parts.push(format!("--file {}", Self::sanitize_path(&task.path)));                }
+                    parts.push(format!("--file {}", self.sanitize_path(&task.path)));                }
                 // Trust the mapper for everything else
                 _ => {
                     let cmd = task.action.to_cli_args();
@@ -1240,9 +1210,9 @@ pub trait AliasProvider {
         }
         // Join with a single space - No trailing spaces, no double spaces.
         let reconstructed = parts.join(" ");
-        Self::install_autorun(verbosity, &reconstructed)
+        self.install_autorun(verbosity, &reconstructed)
     }
-    fn install_autorun(verbosity: &Verbosity, payload: &str) -> io::Result<()> {
+    fn install_autorun(&self, verbosity: &Verbosity, payload: &str) -> io::Result<()> {
         // 1. & 2. Identity Resolution (Your excellent Audit logic)
         let current_exe_name = get_alias_exe_nofail(verbosity);
         let full_exe_path = get_alias_exe()?;
@@ -1306,23 +1276,23 @@ pub trait AliasProvider {
         };
 
         // 5. Final Write
-        Self::write_autorun_registry(&our_cmd, verbosity)
+        self.write_autorun_registry(&our_cmd, verbosity)
     }
-    fn query_alias(name: &str, verbosity: &Verbosity) -> Vec<String>;
-    fn set_alias(opts: SetOptions, path: &Path, verbosity: &Verbosity) -> io::Result<()>;
-    fn run_diagnostics(path: &Path, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>>;
-    fn alias_show_all(verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>>;
-    fn provider_type() -> ProviderType {
+    fn query_alias(&self, name: &str, verbosity: &Verbosity) -> Vec<String>;
+    fn set_alias(&self, opts: SetOptions, path: &Path, verbosity: &Verbosity) -> io::Result<()>;
+    fn run_diagnostics(&self, path: &Path, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>>;
+    fn alias_show_all(&self, verbosity: &Verbosity) -> Result<AuditReport, Box<dyn std::error::Error>>;
+    fn provider_type(&self) -> ProviderType {
         ProviderType::NotLinked
     }
-    fn is_api_responsive(_timeout: Duration) -> bool { true }
-    fn get_version() -> &'static Versioning;
-    fn get_versions() -> Vec<&'static Versioning> {
+    fn is_api_responsive(&self, _timeout: Duration) -> bool { true }
+    fn get_version(&self) -> &'static Versioning;
+    fn get_versions(&self) -> Vec<&'static Versioning> {
         vec![
-            Self::get_version(),
+            self.get_version(),
         ]
     }
-    fn get_full_version() -> &'static Versioning {
+    fn get_full_version(&self) -> &'static Versioning {
         &SYSTEM_REALITY
     }
 }
@@ -1332,21 +1302,33 @@ pub trait AliasProvider {
 // Phase A: Calls parse_arguments to build the TaskQueue.
 // Phase B (The Executor Loop): Iterates over every Task in the queue and passes it to dispatch.
 // Special Case: Handles --setup separately before the loop
+// run::<P> is the classic entry point for a default-built provider; run_with takes any instance.
+pub fn run<P: AliasProvider + Default>(args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    run_with(&P::default(), args)
+}
 #[cfg_attr(debug_assertions, named)]
-pub fn run<P: AliasProvider>(mut args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    // 1. ENV Injection (unchanged)
-    if let Ok(opts) = env::var(ENV_ALIAS_OPTS) {
-        let extra: Vec<String> = opts.split_whitespace()
-            .map(String::from)
-            .filter(|opt| matches!(opt.as_str(),
-              "--quiet" | "--temp" | "--tips" | "--no-tips" | "--icons" | "--no-icons" | "--case" | "--no-case" ))
-            .collect();
+pub fn run_with(provider: &dyn AliasProvider, mut args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    // 1. Defaults: alias.toml, then ALIAS_OPTS, then the real command line (last switch wins).
+    // --setup gets a clean command line: it installs, it does not use the defaults.
+    let config = load_config();
+    let typed: Vec<String> = args.iter().skip(1).cloned().collect();
+    if args.get(1).is_none_or(|a| !a.eq_ignore_ascii_case("--setup")) {
+        let mut extra = config.to_args();
+        if let Ok(opts) = env::var(ENV_ALIAS_OPTS) {
+            extra.extend(config::option_args(&opts));
+        }
         args.splice(1..1, extra);
     }
 
     // 2. Parse intent
     // This now returns a queue where tasks have their own .path (some valid, some raw/Fail)
     let (mut queue, verbosity) = parse_arguments(&args);
+    for task in &mut queue.tasks {
+        task.args.clone_from(&typed);
+    }
+    for problem in &config.problems {
+        shout!(verbosity, AliasIcon::Alert, "{}: {}", config.path.as_deref().unwrap_or(Path::new(CONFIG_FILE_NAME)).display(), problem);
+    }
 
     // check the failure state first.
     if queue.tasks.iter().any(|t| matches!(t.action, AliasAction::Fail)) {
@@ -1372,7 +1354,7 @@ pub fn run<P: AliasProvider>(mut args: Vec<String>) -> Result<(), Box<dyn std::e
             // Clean stack! Pass the original args (minus the app name) to the installer.
             // We bypass hydration, anchors, and the execution loop entirely.
             // In your run loop
-            return <P>::setup_alias(&verbosity, &queue).map_err(|e| e.into());
+            return provider.setup_alias(&verbosity, &queue).map_err(|e| e.into());
         }
     }
 
@@ -1399,6 +1381,7 @@ pub fn run<P: AliasProvider>(mut args: Vec<String>) -> Result<(), Box<dyn std::e
     }
 
     // 6. EXECUTION LOOP (The Forensic Dispatcher)
+    let mut verdict: Option<Box<dyn std::error::Error>> = None;
     for task in queue {
         // 1. Resolve the target for this specific task
         let target_path = if task.path.as_os_str().is_empty() {
@@ -1419,7 +1402,7 @@ pub fn run<P: AliasProvider>(mut args: Vec<String>) -> Result<(), Box<dyn std::e
         // This is the ONLY place we should scream if the pivot is bad.
         if task.action == AliasAction::File {
             if let Some(concrete_path) = resolve_viable_path(&target_path) {
-                <P>::reload_full(&verbosity, &concrete_path, false)?;
+                provider.reload_full(&verbosity, &concrete_path, false)?;
                 continue;
             } else {
                 // THIS is the "Record" that matters.
@@ -1430,7 +1413,12 @@ pub fn run<P: AliasProvider>(mut args: Vec<String>) -> Result<(), Box<dyn std::e
 
         // 4. THE DISPATCH
         // Only healthy, non-Fail, non-File tasks reach the provider.
-        if let Err(e) = dispatch::<P>(task, &verbosity) {
+        if let Err(e) = dispatch_with(provider, task, &verbosity) {
+            // A desync is a verdict, not a crash: hold it for the exit code
+            if e.downcast_ref::<AliasError>().is_some_and(|a| a.code == ErrorCode::Desync as u8) {
+                verdict = Some(e);
+                continue;
+            }
             scream!(verbosity, AliasIcon::Alert, &format!("Action Failed: {}", e));
         }
     }
@@ -1443,12 +1431,23 @@ pub fn run<P: AliasProvider>(mut args: Vec<String>) -> Result<(), Box<dyn std::e
     }
     #[cfg(debug_assertions)]
     trace!("Verbosity {:?}", verbosity);
-    Ok(())
+    verdict.map_or(Ok(()), Err)
 }
 // --- Argument --- Processing
 // Logic Block 1: Flag Harvesting.
 // Logic Block 2: Sticky Path / Context Resolution (Hydrating the Task.path).
 // Logic Block 3: Greedy Payload Collection.
+fn trailing_format(rest: &[String]) -> Option<OutputFormat> {
+    rest.iter().enumerate().find_map(|(k, arg)| {
+        let low = arg.to_lowercase();
+        match low.strip_prefix("--format") {
+            Some("") => rest.get(k + 1)?.parse().ok(),
+            Some(inline) => inline.strip_prefix('=')?.parse().ok(),
+            None => None,
+        }
+    })
+}
+
 macro_rules! parse_continue {
     ($pivot:ident, $i:ident) => {
         $pivot = $i + 1;
@@ -1470,6 +1469,7 @@ pub fn parse_arguments(args: &[String]) -> (TaskQueue, Verbosity) {
     let mut skip_count = 0;
     let mut saw_unknown = false;
     let mut is_literal = false;
+    let mut scoped: Option<(usize, PathBuf)> = None; // (first task it applies to, layer file)
     // --- STEP 1: FLAG HARVESTING ---
     for (i, arg) in args.iter().enumerate().skip(1) {
         if skip_count > 0 { skip_count -= 1; continue; }
@@ -1496,6 +1496,8 @@ pub fn parse_arguments(args: &[String]) -> (TaskQueue, Verbosity) {
                 return (queue, voice);
             }
             AliasAction::Version=> {
+                // Version short-circuits the loop, so pick up a trailing --format here
+                if let Some(format) = trailing_format(&args[i + 1..]) { voice.format = format; }
                 queue.clear();
                 queue.push(AliasAction::Version);
                 return (queue, voice);
@@ -1517,8 +1519,12 @@ pub fn parse_arguments(args: &[String]) -> (TaskQueue, Verbosity) {
             // Modifiers
             AliasAction::Icons   => { voice.show_icons = ShowFeature::On; parse_continue!(pivot_index, i); },
             AliasAction::NoIcons => { voice.show_icons = ShowFeature::Off; parse_continue!(pivot_index, i); },
-            AliasAction::Tips    => { voice.show_icons = ShowFeature::On; parse_continue!(pivot_index, i); },
-            AliasAction::NoTips  => { voice.show_tips = ShowTips::Off; parse_continue!(pivot_index, i); },
+            AliasAction::Tips    => {
+                voice.show_tips = ShowTips::Random;
+                voice.display_tip = voice.display_tip.or_else(random_tip_show);
+                parse_continue!(pivot_index, i);
+            },
+            AliasAction::NoTips  => { voice.show_tips = ShowTips::Off; voice.display_tip = None; parse_continue!(pivot_index, i); },
             AliasAction::Quiet   => {
                 voice.level = VerbosityLevel::Silent;
                 voice.show_icons = ShowFeature::Off;
@@ -1560,7 +1566,10 @@ pub fn parse_arguments(args: &[String]) -> (TaskQueue, Verbosity) {
 
             AliasAction::File => {
                 let mut invalidate = false;
-                if let Some(path_str) = args.get(i + 1) {
+                // --file=x.doskey (how alias.toml and ALIAS_OPTS hand it over) or --file x.doskey
+                let attached = arg.split_once('=').map(|(_, p)| p.trim_matches('"').to_string());
+                let consumed = usize::from(attached.is_none());
+                if let Some(path_str) = attached.as_ref().or(args.get(i + 1)) {
                     custom_path = PathBuf::from(path_str);
                     let raw_p = PathBuf::from(path_str);
                     let resolved = resolve_viable_path(&raw_p);
@@ -1576,8 +1585,8 @@ pub fn parse_arguments(args: &[String]) -> (TaskQueue, Verbosity) {
                         else if task.action.requires_file() { task.action = AliasAction::Fail; }
                     }
                     queue.push_file(AliasAction::File, raw_p);
-                    skip_count = 1;
-                    parse_continue!(pivot_index, i, 2);
+                    skip_count = consumed;
+                    parse_continue!(pivot_index, i, 1 + consumed);
                 } else {
                     queue.push(AliasAction::Invalid);
                     scream!(voice, AliasIcon::Alert, "--file requires a path");
@@ -1606,6 +1615,372 @@ pub fn parse_arguments(args: &[String]) -> (TaskQueue, Verbosity) {
                 parse_continue!(pivot_index, i);
             },
 
+            AliasAction::Describe(_) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                if let Some(name) = args.get(i + 1).filter(|n| is_valid_name(n)) {
+                    // The text is optional: `--describe gs` clears the description
+                    let text = args.get(i + 2).filter(|t| !AliasAction::is_switch(t));
+                    let consumed = if text.is_some() { 2 } else { 1 };
+                    queue.push(AliasAction::Describe(SetOptions {
+                        name: name.clone(),
+                        value: text.cloned().unwrap_or_default(),
+                        volatile: false,
+                        force_case,
+                    }));
+                    skip_count = consumed;
+                    parse_continue!(pivot_index, i, 1 + consumed);
+                }
+                scream!(voice, AliasIcon::Alert, "{} requires a valid target", arg);
+                queue.push(AliasAction::Fail);
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::Export(format) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // Either --export=bash or --export bash
+                let (format, consumed) = if format.is_empty() {
+                    (args.get(i + 1).map(|f| f.to_lowercase()).unwrap_or_default(), 1)
+                } else {
+                    (format, 0)
+                };
+                if let Err(msg) = format.parse::<ExportFormat>() {
+                    scream!(voice, AliasIcon::Alert, "{}", msg);
+                    queue.push(AliasAction::Fail);
+                } else {
+                    queue.push(AliasAction::Export(format));
+                }
+                skip_count = consumed;
+                parse_continue!(pivot_index, i, 1 + consumed);
+            },
+
+            AliasAction::Format(format) => {
+                // Either --format=json or --format json
+                let (format, consumed) = if format.is_empty() {
+                    (args.get(i + 1).cloned().unwrap_or_default(), 1)
+                } else {
+                    (format, 0)
+                };
+                match format.parse::<OutputFormat>() {
+                    Ok(f) => voice.format = f,
+                    Err(msg) => {
+                        scream!(voice, AliasIcon::Alert, "{}", msg);
+                        queue.push(AliasAction::Fail);
+                    },
+                }
+                skip_count = consumed;
+                parse_continue!(pivot_index, i, 1 + consumed);
+            },
+
+            AliasAction::Scope(scope) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // Either --scope=project or --scope project
+                let (scope, consumed) = if scope.is_empty() {
+                    (args.get(i + 1).cloned().unwrap_or_default(), 1)
+                } else {
+                    (scope, 0)
+                };
+                match scope.parse::<AliasScope>() {
+                    Ok(AliasScope::Session) => volatile = true,
+                    Ok(layer) => match layer.default_path() {
+                        Some(p) => scoped = Some((queue.len(), p)),
+                        None => {
+                            scream!(voice, AliasIcon::Alert, "No {} layer configured (set {})", layer, ENV_ALIAS_TEAM_FILE);
+                            queue.push(AliasAction::Fail);
+                        },
+                    },
+                    Err(msg) => {
+                        scream!(voice, AliasIcon::Alert, "{}", msg);
+                        queue.push(AliasAction::Fail);
+                    },
+                }
+                skip_count = consumed;
+                parse_continue!(pivot_index, i, 1 + consumed);
+            },
+
+            AliasAction::Expand(..) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                if let Some(name) = args.get(i + 1).filter(|n| is_valid_name(n)) {
+                    // Everything after the name is sample input for the macro, switches included
+                    let sample: Vec<String> = args.get(i + 2..).unwrap_or(&[]).to_vec();
+                    let consumed = 1 + sample.len();
+                    queue.push(AliasAction::Expand(name.clone(), sample));
+                    skip_count = consumed;
+                    parse_continue!(pivot_index, i, 1 + consumed);
+                }
+                scream!(voice, AliasIcon::Alert, "{} requires a valid target", arg);
+                queue.push(AliasAction::Fail);
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::Import(_) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                let Some(source) = args.get(i + 1).filter(|s| !AliasAction::is_switch(s)) else {
+                    scream!(voice, AliasIcon::Alert, "--import requires a path");
+                    queue.push(AliasAction::Fail);
+                    parse_continue!(pivot_index, i);
+                };
+                let mut opts = ImportOptions { source: PathBuf::from(source), from: None, prefer: ImportPolicy::default() };
+                let mut consumed = 1;
+                let mut bad = None;
+                // Sub-options ride directly behind the path
+                while let (Some(flag), Some(val)) = (args.get(i + 1 + consumed), args.get(i + 2 + consumed)) {
+                    match flag.to_lowercase().as_str() {
+                        "--from" => match val.parse() { Ok(f) => opts.from = Some(f), Err(e) => bad = Some(e) },
+                        "--prefer" => match val.parse() { Ok(p) => opts.prefer = p, Err(e) => bad = Some(e) },
+                        _ => break,
+                    }
+                    consumed += 2;
+                }
+                if let Some(msg) = bad {
+                    scream!(voice, AliasIcon::Alert, "{}", msg);
+                    queue.push(AliasAction::Fail);
+                } else {
+                    queue.push(AliasAction::Import(opts));
+                }
+                skip_count = consumed;
+                parse_continue!(pivot_index, i, 1 + consumed);
+            },
+
+            AliasAction::Restore(which) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // Either --restore=3 or --restore 3
+                let (which, consumed) = if which.is_empty() {
+                    (args.get(i + 1).filter(|w| !AliasAction::is_switch(w)).cloned().unwrap_or_default(), 1)
+                } else {
+                    (which, 0)
+                };
+                if which.is_empty() {
+                    scream!(voice, AliasIcon::Alert, "--restore requires a backup number or timestamp (see --backups)");
+                    queue.push(AliasAction::Fail);
+                    parse_continue!(pivot_index, i);
+                }
+                queue.push(AliasAction::Restore(which));
+                skip_count = consumed;
+                parse_continue!(pivot_index, i, 1 + consumed);
+            },
+
+            AliasAction::Backups => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                queue.push(AliasAction::Backups);
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::Undo(steps) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // The count is optional: --undo, --undo 3, --undo=3
+                if !arg.contains('=') && let Some(n) = args.get(i + 1).and_then(|n| n.parse::<usize>().ok()) {
+                    queue.push(AliasAction::Undo(n));
+                    skip_count = 1;
+                    parse_continue!(pivot_index, i, 2);
+                }
+                queue.push(AliasAction::Undo(steps));
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::Redo => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                queue.push(AliasAction::Redo);
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::Enter | AliasAction::Leave => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                queue.push(trigger);
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::Allow(project) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // The path is optional (default: the discovered project); only take an argument that exists
+                if project.is_empty() && let Some(p) = args.get(i + 1).filter(|p| !AliasAction::is_switch(p) && Path::new(p.as_str()).exists()) {
+                    queue.push(AliasAction::Allow(p.clone()));
+                    skip_count = 1;
+                    parse_continue!(pivot_index, i, 2);
+                }
+                queue.push(AliasAction::Allow(project));
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::ExpandFile(file) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // The path is optional (default: the active alias file)
+                if file.is_empty() && let Some(p) = args.get(i + 1).filter(|p| !AliasAction::is_switch(p) && Path::new(p.as_str()).is_file()) {
+                    queue.push(AliasAction::ExpandFile(p.clone()));
+                    skip_count = 1;
+                    parse_continue!(pivot_index, i, 2);
+                }
+                queue.push(AliasAction::ExpandFile(file));
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::ConfigShow | AliasAction::ConfigSet(..) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // --config, --config show, --config set <key> <value>
+                match args.get(i + 1).map(|a| a.to_lowercase()).as_deref() {
+                    Some("show") => {
+                        queue.push(AliasAction::ConfigShow);
+                        skip_count = 1;
+                        parse_continue!(pivot_index, i, 2);
+                    },
+                    Some("set") => {
+                        let (Some(key), Some(value)) = (args.get(i + 2), args.get(i + 3)) else {
+                            scream!(voice, AliasIcon::Alert, "Usage: --config set <key> <value>");
+                            queue.push(AliasAction::Fail);
+                            parse_continue!(pivot_index, i);
+                        };
+                        queue.push(AliasAction::ConfigSet(key.to_lowercase(), value.clone()));
+                        skip_count = 3;
+                        parse_continue!(pivot_index, i, 4);
+                    },
+                    _ => {
+                        queue.push(AliasAction::ConfigShow);
+                        parse_continue!(pivot_index, i);
+                    },
+                }
+            },
+
+            AliasAction::Encoding(target) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // The target is optional (default: report the current encoding); only take a known one
+                if target.is_empty() && let Some(e) = args.get(i + 1).filter(|e| e.parse::<AliasEncoding>().is_ok()) {
+                    queue.push(AliasAction::Encoding(e.to_lowercase()));
+                    skip_count = 1;
+                    parse_continue!(pivot_index, i, 2);
+                }
+                queue.push(AliasAction::Encoding(target));
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::Completions(shell) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // Either --completions=bash or --completions bash
+                let (shell, consumed) = if shell.is_empty() {
+                    (args.get(i + 1).map(|s| s.to_lowercase()).unwrap_or_default(), 1)
+                } else {
+                    (shell, 0)
+                };
+                if shell != completion::NAMES_TARGET && let Err(msg) = shell.parse::<ExportFormat>() {
+                    scream!(voice, AliasIcon::Alert, "{}", msg);
+                    queue.push(AliasAction::Fail);
+                } else {
+                    queue.push(AliasAction::Completions(shell));
+                }
+                skip_count = consumed;
+                parse_continue!(pivot_index, i, 1 + consumed);
+            },
+
+            AliasAction::Apply(source) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // The source is optional (default: stdin); take `-` or an existing file
+                if source.is_empty() && let Some(p) = args.get(i + 1).filter(|p| p.as_str() == "-" || Path::new(p.as_str()).is_file()) {
+                    queue.push(AliasAction::Apply(p.clone()));
+                    skip_count = 1;
+                    parse_continue!(pivot_index, i, 2);
+                }
+                queue.push(AliasAction::Apply(source));
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::EnableGroup(ref group) | AliasAction::DisableGroup(ref group) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                let enable = matches!(trigger, AliasAction::EnableGroup(_));
+                // Either --enable-group=git or --enable-group git
+                let (group, consumed) = if group.is_empty() {
+                    (args.get(i + 1).filter(|g| !AliasAction::is_switch(g)).map(|g| g.to_lowercase()).unwrap_or_default(), 1)
+                } else {
+                    (group.clone(), 0)
+                };
+                if group.is_empty() {
+                    scream!(voice, AliasIcon::Alert, "{} requires a group name", if enable { "--enable-group" } else { "--disable-group" });
+                    queue.push(AliasAction::Fail);
+                    parse_continue!(pivot_index, i);
+                }
+                queue.push(if enable { AliasAction::EnableGroup(group) } else { AliasAction::DisableGroup(group) });
+                skip_count = consumed;
+                parse_continue!(pivot_index, i, 1 + consumed);
+            },
+
+            AliasAction::Group(group) => {
+                // Either --group=git or --group git; only audits (--show-all, the bare listing) look at it
+                let (group, consumed) = if group.is_empty() {
+                    (args.get(i + 1).filter(|g| !AliasAction::is_switch(g)).map(|g| g.to_lowercase()).unwrap_or_default(), 1)
+                } else {
+                    (group, 0)
+                };
+                if group.is_empty() {
+                    scream!(voice, AliasIcon::Alert, "--group requires a group name");
+                    queue.push(AliasAction::Fail);
+                    parse_continue!(pivot_index, i);
+                }
+                voice.group = Some(group);
+                skip_count = consumed;
+                parse_continue!(pivot_index, i, 1 + consumed);
+            },
+
+            AliasAction::Fuzzy(pattern) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // --fuzzy=git or --fuzzy "*git*"; the pattern keeps its case, matching ignores it
+                let (pattern, consumed) = if pattern.is_empty() {
+                    (args.get(i + 1).filter(|p| !AliasAction::is_switch(p)).cloned().unwrap_or_default(), 1)
+                } else {
+                    (pattern, 0)
+                };
+                if pattern.is_empty() {
+                    scream!(voice, AliasIcon::Alert, "--fuzzy requires some text or a glob");
+                    queue.push(AliasAction::Fail);
+                    parse_continue!(pivot_index, i);
+                }
+                queue.push(AliasAction::Fuzzy(pattern));
+                skip_count = consumed;
+                parse_continue!(pivot_index, i, 1 + consumed);
+            },
+
+            AliasAction::List(_) | AliasAction::Grep(_) | AliasAction::Sort(_) => {
+                // Either --list=git* or --list "git*"; like --group, only the listings look at them
+                let given = trigger.option_values().into_iter().next().unwrap_or_default();
+                let (value, consumed) = if given.is_empty() {
+                    (args.get(i + 1).filter(|v| !AliasAction::is_switch(v)).cloned().unwrap_or_default(), 1)
+                } else {
+                    (given, 0)
+                };
+                let flag = trigger.option().map(|(spec, _)| spec.long).unwrap_or_default();
+                if value.is_empty() {
+                    scream!(voice, AliasIcon::Alert, "{} requires a value", flag);
+                    queue.push(AliasAction::Fail);
+                    parse_continue!(pivot_index, i);
+                }
+                let applied = match trigger {
+                    AliasAction::List(_) => { voice.filter.names = Some(value); Ok(()) },
+                    AliasAction::Grep(_) => RegexBuilder::new(&value).case_insensitive(true).build()
+                        .map(|re| voice.filter.values = Some(re))
+                        .map_err(|e| format!("Bad --grep pattern '{}': {}", value, e)),
+                    _ => value.parse::<AuditSort>().map(|sort| voice.filter.sort = sort),
+                };
+                if let Err(msg) = applied {
+                    scream!(voice, AliasIcon::Alert, "{}", msg);
+                    queue.push(AliasAction::Fail);
+                }
+                skip_count = consumed;
+                parse_continue!(pivot_index, i, 1 + consumed);
+            },
+
+            AliasAction::Only(status) => {
+                if !voice.filter.statuses.contains(&status) { voice.filter.statuses.push(status); }
+                parse_continue!(pivot_index, i);
+            },
+
+            AliasAction::History(name) => {
+                if voice.in_setup { setup_failure!(voice, queue, arg); }
+                // The name is optional: --history alone lists every change
+                if name.is_empty() && let Some(n) = args.get(i + 1).filter(|n| !AliasAction::is_switch(n) && is_valid_name(n)) {
+                    queue.push(AliasAction::History(n.clone()));
+                    skip_count = 1;
+                    parse_continue!(pivot_index, i, 2);
+                }
+                queue.push(AliasAction::History(name));
+                parse_continue!(pivot_index, i);
+            },
+
             AliasAction::Reload  => { queue.push(AliasAction::Reload);  parse_continue!(pivot_index, i); },
             AliasAction::Which   => { queue.push(AliasAction::Which);   parse_continue!(pivot_index, i); },
             AliasAction::Clear   => { queue.push(AliasAction::Clear);   parse_continue!(pivot_index, i); },
@@ -1622,7 +1997,7 @@ pub fn parse_arguments(args: &[String]) -> (TaskQueue, Verbosity) {
 
                 // Check for garbage flags (starts with -- but failed intent check)
                 if matches!(trigger, AliasAction::Invalid) && arg.starts_with("--") {
-                    scream!(voice, AliasIcon::Alert, "Unknown option: {}", arg);
+                    scream!(voice, AliasIcon::Alert, "Unknown option: {}{}", arg, suggest::flag_hint(arg));
                     saw_unknown = true;
 
                     // --- THE MISSING LINK ---
@@ -1729,6 +2104,15 @@ pub fn parse_arguments(args: &[String]) -> (TaskQueue, Verbosity) {
     #[cfg(debug_assertions)]
     trace!("STEP 3 COMPLETE");
 
+    // --scope retargets everything after it, payload included, at that layer's file
+    if let Some((from, layer_path)) = scoped {
+        for task in queue.tasks.iter_mut().skip(from) {
+            if task.action.requires_file() && task.action != AliasAction::File {
+                task.path = layer_path.clone();
+            }
+        }
+    }
+
     // 4, Finalize
     if queue.is_empty() && !saw_unknown {
         queue.push(AliasAction::ShowAll);
@@ -1828,43 +2212,98 @@ pub fn parse_set_argument(
 }
 // --- Dipatcher, does what you think
 // Matches on AliasAction and executes the specific command strategy.
+pub fn dispatch<P: AliasProvider + Default>(task: Task, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
+    dispatch_with(&P::default(), task, verbosity)
+}
+pub fn dispatch_with(provider: &dyn AliasProvider, task: Task, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
+    if !journal::is_journaled(&task.action) {
+        return dispatch_action(provider, task, verbosity);
+    }
+    // Diff both layers around the command, so a half-applied failure is journaled too
+    let path = task.path.clone();
+    let action = task.action.to_cli_args();
+    let before = journal::LayerState::capture(provider, verbosity, &path);
+    let outcome = dispatch_action(provider, task, verbosity);
+    let changes = before.and_then(|b| Ok(b.diff(&journal::LayerState::capture(provider, verbosity, &path)?)));
+    let recorded = changes.map_err(|e| e.into())
+        .and_then(|changes| journal::append_txn(verbosity, &path, JournalOp::Do, &action, &changes));
+    if let Err(e) = recorded {
+        shout!(verbosity, AliasIcon::Alert, "Journal not updated ({}); --undo will not see '{}'", e, action);
+    }
+    outcome
+}
 #[cfg_attr(debug_assertions, named)]
-pub fn dispatch<P: AliasProvider>(task: Task, verbosity: &Verbosity, ) -> Result<(), Box<dyn std::error::Error>> {
+fn dispatch_action(provider: &dyn AliasProvider, task: Task, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
     // Convenience reference to the baked-in path
     let path = &task.path;
 
     match task.action {
         AliasAction::Clear => {
             whisper!(verbosity, "Purging RAM macros...");
-            P::purge_ram_macros(verbosity)?;
+            provider.purge_ram_macros(verbosity)?;
         }
         AliasAction::Edit(custom_editor) => {
-            let report = P::purge_file_macros(verbosity, path)?;
+            let report = provider.purge_file_macros(verbosity, path)?;
             for (failed_name, _error_code) in report.failed {
                 shout!(verbosity, AliasIcon::Fail, &format!("Ghost Warning: Failed to unset '{}' from memory.", failed_name));
             }
             open_editor(path, custom_editor, verbosity)?;
             // Immediate sync so the edits are live in RAM
-            P::reload_full(verbosity, path, false)?;
+            provider.reload_full(verbosity, path, false)?;
         }
         AliasAction::File  => {
-            P::reload_full(verbosity, path, false)?;
+            provider.reload_full(verbosity, path, false)?;
         }
         AliasAction::Help => print_help(verbosity, HelpMode::Full, Some(path)),
         AliasAction::License => print_license(verbosity),
         AliasAction::Query(term) => {
-            for line in P::query_alias(&term, verbosity) {
-                verbosity.whisper(&line);
+            let lines = provider.query_alias(&term, verbosity);
+            if verbosity.format.is_machine() {
+                // "not found" chatter has no '=' and simply yields no record
+                let records: Vec<Record> = lines.iter()
+                    .filter_map(|line| parse_alias_line(line))
+                    .map(|(n, v)| output::query_record(&n, &v))
+                    .collect();
+                verbosity.records(&records);
+            } else {
+                let missed = !lines.iter().any(|line| parse_alias_line(line).is_some());
+                for line in lines {
+                    verbosity.whisper(&line);
+                }
+                if missed {
+                    let hint = suggest::name_hint(&term, &known_aliases(provider, verbosity, path));
+                    if !hint.is_empty() { say!(verbosity, AliasIcon::Hint, "{}", hint.trim_start()); }
+                }
             }
         }
-        AliasAction::Reload => P::reload_full(verbosity, path, true)?,
+        AliasAction::Fuzzy(pattern) => {
+            let hits: Vec<(String, String)> = known_aliases(provider, verbosity, path).into_iter()
+                .filter(|(n, v)| fuzzy_match(&pattern, n, v))
+                .collect();
+            if verbosity.format.is_machine() {
+                let records: Vec<Record> = hits.iter().map(|(n, v)| output::query_record(n, v)).collect();
+                verbosity.records(&records);
+            } else if hits.is_empty() {
+                say!(verbosity, AliasIcon::Info, "No aliases match '{}'", pattern);
+            } else {
+                for (name, value) in hits {
+                    verbosity.whisper(&format!("{}={}", name, value));
+                }
+            }
+        }
+        AliasAction::Reload => reload_layers(provider, verbosity, path, true)?,
         AliasAction::Remove(opts) => {
             if !opts.name.is_empty() {
                 if opts.volatile == true {
                     return Err(failure!(verbosity, ErrorCode::Syntax, "Remove is a dual action"));
                 }
                 let name = opts.name.to_string();
-                P::set_alias(opts, path, verbosity)?;
+                let known = known_aliases(provider, verbosity, path);
+                // Removing a stranger stays a harmless no-op; only point at the likely typo
+                if !known.iter().any(|(n, _)| n.eq_ignore_ascii_case(&name)) {
+                    shout!(verbosity, AliasIcon::Hint, "'{}' is not a known alias.{}", name, suggest::name_hint(&name, &known));
+                }
+                provider.set_alias(opts, path, verbosity)?;
                 say!(verbosity, AliasIcon::File, "Removed alias '{}' from {}", name, path.display());
             } else {
                 return Err(failure!(verbosity, ErrorCode::MissingName, "Error: name required"));
@@ -1872,9 +2311,87 @@ pub fn dispatch<P: AliasProvider>(task: Task, verbosity: &Verbosity, ) -> Result
         }
         AliasAction::Set(opts) => {
             // Path is guaranteed by the 'run' hydration
-            P::set_alias(opts, path, verbosity)?;
+            provider.set_alias(opts, path, verbosity)?;
+        }
+        AliasAction::ShowAll => {
+            let report = provider.alias_show_all(verbosity)?;
+            audit_verdict(verbosity, &report)?;
+        }
+        AliasAction::Describe(opts) => describe_alias(verbosity, &opts, path)?,
+        AliasAction::Import(opts) => import_aliases(provider, verbosity, &opts, path)?,
+        AliasAction::Expand(name, args) => {
+            let value = lookup_macro_value(provider, &name, path)?
+                .ok_or_else(|| failure!(verbosity, ErrorCode::MissingName, "'{}' is not a known alias", name))?;
+            let expansion = expand_macro(&value, &args.join(" "));
+            for issue in &expansion.issues {
+                shout!(verbosity, AliasIcon::Alert, "Macro '{}': {}", name, issue);
+            }
+            for (n, command) in expansion.commands.iter().enumerate() {
+                if expansion.commands.len() > 1 { verbosity.whisper(&format!("[{}] {}", n + 1, command.trim())); }
+                else { verbosity.whisper(command.trim()); }
+            }
+        }
+        AliasAction::Export(format) => {
+            let format: ExportFormat = format.parse()
+                .map_err(|msg: String| failure!(verbosity, ErrorCode::Syntax, "{}", msg))?;
+            let pairs = parse_macro_file(path, verbosity)?;
+            let report = export_aliases(&pairs, format);
+            verbosity.whisper(report.script.trim_end());
+            for (name, reason) in &report.skipped {
+                scream!(verbosity, AliasIcon::Alert, "Not exported '{}': {}", name, reason);
+            }
+        }
+        AliasAction::Backups => {
+            let backups = backup::list_backups(path).map_err(|e| failure!(verbosity, e))?;
+            if verbosity.format.is_machine() {
+                verbosity.records(&backups.iter().map(output::backup_record).collect::<Vec<_>>());
+            } else if backups.is_empty() {
+                say!(verbosity, AliasIcon::Info, "No backups for {}", path.display());
+            } else {
+                say!(verbosity, AliasIcon::File, "Backups in {} (newest first):", backup::backup_dir(path).display());
+                for b in &backups {
+                    let diff = if b.diff.is_empty() { "same as current".to_string() }
+                        else { format!("restore: +{} -{} ~{}", b.diff.added, b.diff.removed, b.diff.changed) };
+                    verbosity.whisper(&format!("  [{}] {}  {:>4} entries  {}", b.index, b.stamp, b.entries, diff));
+                }
+            }
+        }
+        AliasAction::Restore(which) => {
+            let backups = backup::list_backups(path).map_err(|e| failure!(verbosity, e))?;
+            let chosen = backup::find_backup(&backups, &which)
+                .ok_or_else(|| failure!(verbosity, ErrorCode::MissingFile, "No backup matches '{}' (see --backups)", which))?;
+            restore_backup(verbosity, chosen, path)?;
+            say!(verbosity, AliasIcon::Success, "Restored {} from backup [{}] {}", path.display(), chosen.index, chosen.stamp);
+            provider.reload_full(verbosity, path, true)?;
+        }
+        AliasAction::Undo(steps) => {
+            if journal::undo(provider, verbosity, path, steps.max(1))? == 0 {
+                say!(verbosity, AliasIcon::Info, "Nothing to undo for {}", path.display());
+            }
+        }
+        AliasAction::Redo => {
+            if !journal::redo(provider, verbosity, path)? {
+                say!(verbosity, AliasIcon::Info, "Nothing to redo for {}", path.display());
+            }
+        }
+        AliasAction::History(name) => {
+            let txns = load_journal(path).map_err(|e| failure!(verbosity, e))?;
+            let wanted = |c: &JournalChange| name.is_empty() || c.name.eq_ignore_ascii_case(&name);
+            if verbosity.format.is_machine() {
+                let records: Vec<Record> = txns.iter()
+                    .flat_map(|t| t.changes.iter().filter(|c| wanted(c)).map(move |c| output::history_record(t, c)))
+                    .collect();
+                verbosity.records(&records);
+            } else {
+                let show = |v: &Option<String>| v.clone().unwrap_or_else(|| "(none)".to_string());
+                for txn in &txns {
+                    for change in txn.changes.iter().filter(|c| wanted(c)) {
+                        verbosity.whisper(&format!("#{:<4} {} {:<7} {:<4} {}: {} -> {}   [{}]",
+                            txn.id, txn.stamp, txn.op, change.layer.as_str(), change.name, show(&change.prev), show(&change.new), txn.action));
+                    }
+                }
+            }
         }
-        AliasAction::ShowAll => P::alias_show_all(verbosity)?,
         AliasAction::Unalias(mut opts) => {
             if !opts.name.is_empty() {
                 #[cfg(debug_assertions)]
@@ -1887,26 +2404,35 @@ pub fn dispatch<P: AliasProvider>(task: Task, verbosity: &Verbosity, ) -> Result
                     return Err(failure!(verbosity, ErrorCode::MissingName, "Error: Alias name required"));
                 }
                 let display_name = opts.name.clone();
-                P::set_alias(opts, path, verbosity)?;
+                provider.set_alias(opts, path, verbosity)?;
                 say!(verbosity, AliasIcon::File, "Removed alias '{}'", display_name);
             } else {
                 return Err(failure!(verbosity, ErrorCode::MissingName, "Error:Alias name ia required"));
             }
         }
         AliasAction::Version => {
-            let full_version = P::get_full_version();
-            let library_versions = P::get_versions();
-            Versioning::display_versions(verbosity, &full_version, &library_versions);
+            let full_version = provider.get_full_version();
+            let library_versions = provider.get_versions();
+            if verbosity.format.is_machine() {
+                let records: Vec<Record> = std::iter::once(output::version_record("overall", full_version))
+                    .chain(library_versions.iter().map(|v| output::version_record("component", v)))
+                    .collect();
+                verbosity.records(&records);
+            } else {
+                Versioning::display_versions(verbosity, &full_version, &library_versions);
+            }
         }
         AliasAction::VersionShort => {
-            let full_version = P::get_full_version();
-            let library_versions = P::get_versions();
+            let full_version = provider.get_full_version();
+            let library_versions = provider.get_versions();
             Versioning::display_short_version(verbosity, false, &full_version, &library_versions);
         }
         AliasAction::Which => {
-            P::alias_show_all(verbosity)?;
+            let report = provider.alias_show_all(verbosity)?;
+            // say! stays quiet in machine formats, so the two record streams just concatenate
             say!(verbosity, AliasIcon::None, "\n");
-            P::run_diagnostics(path, verbosity)?;
+            provider.run_diagnostics(path, verbosity)?;
+            audit_verdict(verbosity, &report)?;
         },
         // errors and mismatches
         AliasAction::Invalid => {
@@ -1924,6 +2450,102 @@ pub fn dispatch<P: AliasProvider>(task: Task, verbosity: &Verbosity, ) -> Result
         AliasAction::NoIcons => {dispatch_failure!(verbosity, AliasAction::NoIcons, "Metadata Leak: Parser state variant reached the executor.");}
         AliasAction::Quiet => {dispatch_failure!(verbosity, AliasAction::Quiet, "Metadata Leak: Parser state variant reached the executor.");}
         AliasAction::NoQuiet => {dispatch_failure!(verbosity, AliasAction::NoQuiet, "Metadata Leak: Parser state variant reached the executor.");}
+        AliasAction::Format(f) => {dispatch_failure!(verbosity, AliasAction::Format(f), "Metadata Leak: Parser state variant reached the executor.");}
+        AliasAction::Scope(s) => {dispatch_failure!(verbosity, AliasAction::Scope(s), "Metadata Leak: Parser state variant reached the executor.");}
+        AliasAction::Enter => {
+            let project = env::current_dir().ok().and_then(|cwd| find_project_file(&cwd))
+                .ok_or_else(|| failure!(verbosity, ErrorCode::MissingFile, "No {} at or above the current directory", PROJECT_ALIAS_FILENAME))?;
+            enter_project(provider, verbosity, &ProjectState::for_user(path), &project)?;
+        }
+        AliasAction::Leave => {
+            if leave_project(provider, verbosity, &ProjectState::for_user(path))?.is_none() {
+                say!(verbosity, AliasIcon::Info, "No entered project to leave");
+            }
+        }
+        AliasAction::Allow(project) => {
+            let project = if project.is_empty() {
+                env::current_dir().ok().and_then(|cwd| find_project_file(&cwd))
+                    .ok_or_else(|| failure!(verbosity, ErrorCode::MissingFile, "No {} at or above the current directory", PROJECT_ALIAS_FILENAME))?
+            } else {
+                PathBuf::from(project)
+            };
+            let hash = allow_project(verbosity, &ProjectState::for_user(path), &project)?;
+            say!(verbosity, AliasIcon::Success, "Allowed {} (sha256 {})", project.display(), &hash[..12]);
+        }
+        AliasAction::ExpandFile(file) => {
+            // Dry run: what a reload of this file would inject, guards and placeholders resolved
+            let file = if file.is_empty() { path.to_path_buf() } else { PathBuf::from(file) };
+            let pairs: Vec<SourcedPair> = expand_includes(&file, verbosity)?.into_iter().filter(|p| is_valid_name(&p.name)).collect();
+            if verbosity.format.is_machine() {
+                verbosity.records(&pairs.iter().map(output::entry_record).collect::<Vec<_>>());
+            } else {
+                for p in &pairs {
+                    verbosity.whisper(&format!("{}={}", p.name, p.value));
+                }
+                say!(verbosity, AliasIcon::Info, "{} entries resolved from {}", pairs.len(), file.display());
+            }
+        }
+        AliasAction::ConfigShow => {
+            let config = load_config();
+            let settings = effective_settings(&config, &task.args);
+            if verbosity.format.is_machine() {
+                verbosity.records(&settings.iter().map(output::setting_record).collect::<Vec<_>>());
+            } else {
+                match &config.path {
+                    Some(p) if p.exists() => say!(verbosity, AliasIcon::File, "Settings (config: {})", p.display()),
+                    Some(p) => say!(verbosity, AliasIcon::File, "Settings (no config yet; --config set writes {})", p.display()),
+                    None => say!(verbosity, AliasIcon::File, "Settings (no config file location)"),
+                }
+                for s in &settings {
+                    verbosity.whisper(&format!("  {:<9}= {:<14} [{}]", s.setting.key, s.value, s.source));
+                }
+                for problem in &config.problems {
+                    shout!(verbosity, AliasIcon::Alert, "Ignored {}", problem);
+                }
+            }
+        }
+        AliasAction::ConfigSet(key, value) => {
+            let config = config_path()
+                .ok_or_else(|| failure!(verbosity, ErrorCode::MissingFile, "No place for {}: set {} or {}", CONFIG_FILE_NAME, ENV_ALIAS_FILE, ENV_ALIAS_CONFIG))?;
+            let value = config::set_config_value(verbosity, &config, &key, &value)?;
+            say!(verbosity, AliasIcon::Success, "Set {} = {} in {}", key, value, config.display());
+        }
+        AliasAction::Encoding(target) => {
+            let target = if target.is_empty() { None } else {
+                Some(target.parse::<AliasEncoding>().map_err(|msg| failure!(verbosity, ErrorCode::Syntax, "{}", msg))?)
+            };
+            convert_encoding(verbosity, target, path)?;
+        }
+        AliasAction::Completions(target) => {
+            if target == completion::NAMES_TARGET {
+                // Called back by the scripts on every <Tab>: names only, no chatter
+                for (name, _) in parse_macro_file(path, verbosity)? {
+                    verbosity.whisper(&name);
+                }
+            } else {
+                let shell: ExportFormat = target.parse()
+                    .map_err(|msg: String| failure!(verbosity, ErrorCode::Syntax, "{}", msg))?;
+                let exe = get_alias_exe_nofail(verbosity);
+                verbosity.whisper(completion::completion_script(shell, &exe).trim_end());
+            }
+        }
+        AliasAction::Apply(source) => {
+            let content = apply::read_batch_source(&source).map_err(|e| failure!(verbosity, e))?;
+            let from = if source.is_empty() || source == "-" { "stdin".to_string() } else { source };
+            let ops = match parse_batch(&content) {
+                Ok(ops) => ops,
+                Err(errors) => {
+                    for e in &errors {
+                        scream!(verbosity, AliasIcon::Alert, "{}: {}", from, e);
+                    }
+                    return Err(failure!(verbosity, ErrorCode::Syntax, "{} invalid lines in {}; nothing applied", errors.len(), from));
+                },
+            };
+            let summary = apply_batch(provider, verbosity, &ops, path)?;
+            say!(verbosity, AliasIcon::Success, "Applied {} to {}: {}", from, path.display(), summary);
+        }
+        AliasAction::EnableGroup(group) => { set_group_enabled(provider, verbosity, path, &group, true)?; }
+        AliasAction::DisableGroup(group) => { set_group_enabled(provider, verbosity, path, &group, false)?; }
         AliasAction::Setup => {
             scream!(verbosity, AliasIcon::Alert, "Setup should never be dispatched (Handled separately).");
             print_help(verbosity, HelpMode::Short, Some(path));
@@ -1933,6 +2555,11 @@ pub fn dispatch<P: AliasProvider>(task: Task, verbosity: &Verbosity, ) -> Result
         AliasAction::NoTemp => {dispatch_failure!(verbosity, AliasAction::NoTemp, "Metadata Leak: Parser state variant reached the executor.");}
         AliasAction::Tips => {dispatch_failure!(verbosity, AliasAction::Tips, "Metadata Leak: Parser state variant reached the executor.");}
         AliasAction::NoTips => {dispatch_failure!(verbosity, AliasAction::NoTips, "Metadata Leak: Parser state variant reached the executor.");}
+        AliasAction::Group(group) => {dispatch_failure!(verbosity, AliasAction::Group(group), "Metadata Leak: Parser state variant reached the executor.");}
+        AliasAction::List(glob) => {dispatch_failure!(verbosity, AliasAction::List(glob), "Metadata Leak: Parser state variant reached the executor.");}
+        AliasAction::Grep(regex) => {dispatch_failure!(verbosity, AliasAction::Grep(regex), "Metadata Leak: Parser state variant reached the executor.");}
+        AliasAction::Only(status) => {dispatch_failure!(verbosity, AliasAction::Only(status), "Metadata Leak: Parser state variant reached the executor.");}
+        AliasAction::Sort(order) => {dispatch_failure!(verbosity, AliasAction::Sort(order), "Metadata Leak: Parser state variant reached the executor.");}
         // back map
         AliasAction::Toggle(ref _inner, _val) => {dispatch_failure!(verbosity, AliasAction::Fail, "Metadata Leak: Parser failed to unwrap Toggle");}
     }
@@ -1980,6 +2607,8 @@ pub fn open_editor(path: &Path, override_ed: Option<String>, verbosity: &Verbosi
     }
 
     // --- 3. COMMAND LINE PREP ---
+    // The pre-edit state is the one worth keeping if the session goes wrong
+    backup::backup_before_write(verbosity, path);
     profile.args.push(absolute_path.to_string());
     say!(verbosity, &format!("Launching {}...", profile.args[0]));
 
@@ -2021,14 +2650,11 @@ USAGE:
         return
     }
     shout!(verbosity, AliasIcon::None, r#"
-FLAGS:
---help                  Show this help menu
---license               Show Licensing information
---version, --ver        Show versioning information
-
 ENVIRONMENT VARIABLES:
 ALIAS_FILE              Default alias file name ({alias_file})
 ALIAS_OPTS              Override options
+ALIAS_BACKUPS           Snapshots kept per alias file (default 10, 0 = off)
+ALIAS_TEAM_FILE         Shared team layer, loaded below the user file
 VISUAL                  Primary editor for edalias
 EDITOR                  Secondary editor for edalias
 PATHEXT                 CMD extensions list
@@ -2040,35 +2666,10 @@ PATHEXT is used to help detirmine if a 'short name` given for the editor is vali
 PATH is used to find the editor
 USERPROFILE and APPDATA are fallback paths for the aliases file, in case CWD is locked
 if neither VISUAL or EDITOR is set, final fallback is notepad.
-
-MACRO STATE:
-Defaults are --no-temp, --no-case
-  --temp / --no-temp    Force Volatility (RAM only) vs. Persistence
-  --case / --no-case    Force Case-Sensitivity vs. Case-Insensitive
-  --file <path>         Redirect action to a specific alias file
-
-SYMMETRIC TOGGLES:
-Defaults are --no-quiet --icons --tips (at 10%)
-  --quiet / --no-quiet  Metadata/Whisper suppression
-  --icons / --no-icons  ANSI glyphs/icons in output
-  --tips  / --no-tips   Random usage hint injection
-
-PRIMARY ACTIONS:
-  <name>                Query/Lookup a specific alias definition
-  --remove <name>       Delete alias from file and RAM (see --temp and --file)
-  --unalias <name>      Drop alias from current session (RAM)
-  --show-all            List hydrated mapping (File + RAM)
-  --which               Deep-audit sync status across all backends
-  --edalias=[=EDITOR]   Open active file in editor (Path to editor optional)
-  --reload              Force re-sync of Win32 environment strings
-  --                    Stop processing flags (treat rest as name/value)
-
-SYSTEM & BOOT:
-  --setup               Initialize Windows Registry AutoRun hooks
-  --startup             Execute boot-time hydration (via AutoRun)
-  --clear               Nuke all aliases in the active context
-  --version / --ver     Full build metadata vs. Short string
-
+"#, alias_file = DEFAULT_ALIAS_FILENAME);
+    // The switches come straight from the option table
+    shout!(verbosity, AliasIcon::None, "\n{}", options::help_sections());
+    shout!(verbosity, AliasIcon::None, r#"
   EXAMPLES:
   {exe_name} pull=git pull --rebase
   {exe_name} --file G:\tools.alias --show-all
@@ -2081,6 +2682,8 @@ SYSTEM & BOOT:
   Options are queued, so order is preserved. --reload --file=x is not the same as --file=x --relaod
   Specific macro names that match dos/win special files are ignores (e.g. PRN,NUL,AUX,CON etc.)
   --file overlays the in-memory configuration. --file=x --file=y overlays both
+  Alias files may use ${{env:NAME}}, ${{alias_dir}} and ${{home}}, and [if host=X] / [if exists=P] ... [end]
+  --reload loads team < user < project (nearest .aliases.doskey, once allowed); higher wins
   For multi quoted aliases, cmd has a tendancy to be very britle. Using --edalias is usually better
   Don't want to ever fallback to doskey? Use alias_win32. Never want to hit the api? Use alias_wrapper.
 
@@ -2140,6 +2743,8 @@ pub fn mesh_logic(os_list: Vec<(String, String)>, file_list: Vec<(String, String
             name: f_name,
             os_value: os_val,
             file_value: Some(f_val),
+            meta: None,
+            origin: None,
         });
     }
 
@@ -2151,94 +2756,134 @@ pub fn mesh_logic(os_list: Vec<(String, String)>, file_list: Vec<(String, String
             name: o_name,
             os_value: Some(o_val),
             file_value: None,
+            meta: None,
+            origin: None,
         });
     }
 
     mesh_list
 }
 
-pub fn perform_audit(os_pairs: Vec<(String, String)>, verbosity: &Verbosity, provider: &ProviderType) -> Result<(), Box<dyn std::error::Error>> {
-    let file_pairs = dump_alias_file(verbosity)?;
-    let mesh = mesh_logic(os_pairs, file_pairs);
-    display_audit(&mesh, verbosity, provider);
-    Ok(())
+pub fn perform_audit(os_pairs: Vec<(String, String)>, verbosity: &Verbosity, provider: &ProviderType) -> Result<AuditReport, Box<dyn std::error::Error>> {
+    let layered = dump_alias_file(verbosity)?;
+    let mut mesh = mesh_layers(os_pairs, layered);
+    if let Some(group) = &verbosity.group {
+        mesh.retain(|e| e.group().is_some_and(|g| g.eq_ignore_ascii_case(group)));
+    }
+    attach_alias_meta(&mut mesh, verbosity);
+    let entries: Vec<AuditEntry> = mesh.iter().map(|e| AuditEntry::from_mesh(e, provider)).collect();
+    // --list/--grep/--*-only/--sort pick the rows printed; the report (and the exit code) keeps them all
+    let order = verbosity.filter.select(&entries);
+    if verbosity.format.is_machine() {
+        verbosity.records(&order.iter().map(|&i| output::audit_record(&entries[i])).collect::<Vec<_>>());
+    } else {
+        let shown: Vec<AliasEntryMesh> = order.iter().map(|&i| mesh[i].clone()).collect();
+        display_audit(&shown, verbosity, provider);
+    }
+    Ok(AuditReport::from_entries(entries))
+}
+
+// Decorates the mesh with `;; @desc/@tags` lines from the alias file (best effort).
+pub fn attach_alias_meta(mesh: &mut [AliasEntryMesh], verbosity: &Verbosity) {
+    let Some(path) = get_alias_path("") else { return; };
+    let Ok(doc) = AliasDocument::load(&path, verbosity) else { return; };
+    let mut metadata = doc.metadata();
+    for entry in mesh.iter_mut() {
+        entry.meta = metadata.remove(&entry.name);
+    }
+}
+
+// Tags triple-audit rows with the layer their file value came from (best effort).
+pub fn attach_layer_origins(report: &mut AuditReport, verbosity: &Verbosity) {
+    let Some(path) = get_alias_path("") else { return; };
+    let Ok(layered) = load_layers(verbosity, &path) else { return; };
+    for entry in report.entries.iter_mut().filter(|e| e.file.is_some()) {
+        entry.origin = layered.iter().find(|l| l.name.eq_ignore_ascii_case(&entry.name)).map(|l| l.origin.clone());
+    }
+}
+
+// Turns a broken audit (desync or corrupt) into an ErrorCode::Desync so login scripts can test the exit code.
+fn audit_verdict(verbosity: &Verbosity, report: &AuditReport) -> Result<(), Box<dyn std::error::Error>> {
+    if !report.is_broken() { return Ok(()); }
+    Err(failure!(verbosity, ErrorCode::Desync, "Audit: {} desync, {} phantom, {} pending, {} corrupt",
+        report.desync, report.phantom, report.pending, report.corrupt))
 }
 
 pub fn perform_triple_audit(
     verbosity: &Verbosity,
     win32_pairs: Vec<(String, String)>,
-    mut wrap_pairs: Vec<(String, String)>,
-    mut file_pairs: Vec<(String, String)>,
+    wrap_pairs: Vec<(String, String)>,
+    file_pairs: Vec<(String, String)>,
     provider: &ProviderType,
-) {
-    let mut desync_detected = false;
+) -> AuditReport {
+    let mut report = triple_audit(win32_pairs, wrap_pairs, file_pairs);
+    attach_layer_origins(&mut report, verbosity);
+    if let Some(group) = &verbosity.group {
+        report = AuditReport::from_entries(report.entries.into_iter()
+            .filter(|e| e.origin.as_ref().and_then(LayerOrigin::group).is_some_and(|g| g.eq_ignore_ascii_case(group)))
+            .collect());
+    }
+    // As in perform_audit: the filter only decides what is printed
+    let shown = AuditReport::from_entries(verbosity.filter.apply(report.entries.clone()));
+    if verbosity.format.is_machine() {
+        verbosity.records(&shown.entries.iter().map(output::audit_record).collect::<Vec<_>>());
+    } else {
+        render_triple_audit(&shown, verbosity, provider);
+    }
+    report
+}
 
+pub fn render_triple_audit(report: &AuditReport, verbosity: &Verbosity, provider: &ProviderType) {
     // 1. THE "OVERCHECK" WIDTH CALCULATION
     // We calculate based on the RAW strings. If we trim here, alignment drifts.
-    let max_len = win32_pairs.iter()
-        .chain(wrap_pairs.iter())
-        .chain(file_pairs.iter())
-        .map(|(n, v)| n.len() + v.len() + 1)
+    let max_len = report.entries.iter()
+        .flat_map(|e| [&e.win32, &e.doskey, &e.file].into_iter().flatten().map(|v| e.name.len() + v.len() + 1))
         .max()
         .unwrap_or(35) + 5;
 
     say!(verbosity, AliasIcon::Info, "Triple Audit [W=Win32, D=Doskey, F=File]\n");
 
-    // 2. PRIMARY PASS: Win32 Kernel (The "Live" Truth)
-    for (name, w_val) in win32_pairs {
-        // Pluck matches from other lists to avoid double-processing
-        let d_val = wrap_pairs.iter().position(|(n, _)| n == &name).map(|i| wrap_pairs.remove(i).1);
-        let f_val = file_pairs.iter().position(|(n, _)| n == &name).map(|i| file_pairs.remove(i).1);
-
-        // DISPLAY RAW: This preserves the "cxd=ehat? haha no" mess exactly as it is
-        verbosity.align(&name, &w_val, max_len, (true, d_val.is_some(), f_val.is_some()), provider);
-
-        // CHECK 1: Name Corruption (The serious work)
-        if !is_valid_name(&name) {
-            print!(" {}", text!(verbosity, AliasIcon::Fail, "!! CORRUPT NAME"));
-            desync_detected = true;
-        }
-
-        // CHECK 2: Value Desync (Compare intent, but show the drift)
-        if let Some(dv) = d_val {
-            if !functional_cmp(&w_val, &dv) {
-                print!(" {} D: '{}'", text!(verbosity, AliasIcon::Alert, "!!"), dv);
-                desync_detected = true;
-            }
+    for entry in &report.entries {
+        let (name, d_val, f_val) = (&entry.name, &entry.doskey, &entry.file);
+        match (&entry.win32, d_val) {
+            // 2. PRIMARY: Win32 Kernel (The "Live" Truth)
+            (Some(w_val), _) => {
+                // DISPLAY RAW: This preserves the "cxd=ehat? haha no" mess exactly as it is
+                verbosity.align(name, w_val, max_len, (true, d_val.is_some(), f_val.is_some()), provider);
+
+                // CHECK 1: Name Corruption (The serious work)
+                if !is_valid_name(name) {
+                    print!(" {}", text!(verbosity, AliasIcon::Fail, "!! CORRUPT NAME"));
+                }
+                // CHECK 2: Value Desync (Compare intent, but show the drift)
+                if let Some(dv) = d_val.as_ref().filter(|dv| !macro_equivalent(w_val, dv)) {
+                    print!(" {} D: '{}'", text!(verbosity, AliasIcon::Alert, "!!"), dv);
+                }
+                if let Some(fv) = f_val.as_ref().filter(|fv| !macro_equivalent(w_val, fv)) {
+                    print!(" {} F: '{}'", text!(verbosity, AliasIcon::Alert, "!!"), fv);
+                }
+            },
+            // 3. PHANTOM: In Doskey wrapper, but missing from Kernel
+            (None, Some(d_val)) => {
+                verbosity.align(name, d_val, max_len, (false, true, f_val.is_some()), provider);
+                print!(" {}", text!(verbosity, AliasIcon::Alert, "<- PHANTOM (Not in Kernel)"));
+                if !is_valid_name(name) { print!(" !! CORRUPT"); }
+            },
+            // 4. PENDING: In File, but not loaded into OS
+            (None, None) => {
+                verbosity.align(name, f_val.as_deref().unwrap_or(""), max_len, (false, false, true), provider);
+                print!(" {}", text!(verbosity, AliasIcon::Alert, "<- PENDING (Not in RAM)"));
+                if !is_valid_name(name) { print!(" !! CORRUPT"); }
+            },
         }
-        if let Some(fv) = f_val {
-            if !functional_cmp(&w_val, &fv) {
-                print!(" {} F: '{}'", text!(verbosity, AliasIcon::Alert, "!!"), fv);
-                desync_detected = true;
-            }
+        if let Some(note) = entry.origin.as_ref().and_then(layers::origin_note) {
+            print!(" {}", note);
         }
         println!();
     }
 
-    // 3. SECONDARY PASS: Phantom Entries (In Doskey wrapper, but missing from Kernel)
-    for (name, d_val) in wrap_pairs {
-        let f_val = file_pairs.iter().position(|(n, _)| n == &name).map(|i| file_pairs.remove(i).1);
-
-        verbosity.align(&name, &d_val, max_len, (false, true, f_val.is_some()), provider);
-        print!(" {}", text!(verbosity, AliasIcon::Alert, "<- PHANTOM (Not in Kernel)"));
-
-        if !is_valid_name(&name) { print!(" !! CORRUPT"); }
-        println!();
-        desync_detected = true;
-    }
-
-    // 4. TERTIARY PASS: Pending Entries (In File, but not loaded into OS)
-    for (name, f_val) in file_pairs {
-        verbosity.align(&name, &f_val, max_len, (false, false, true), provider);
-        print!(" {}", text!(verbosity, AliasIcon::Alert, "<- PENDING (Not in RAM)"));
-
-        if !is_valid_name(&name) { print!(" !! CORRUPT"); }
-        println!();
-        desync_detected = true;
-    }
-
     // 5. THE SURVIVAL FOOTER
-    if desync_detected {
+    if report.desync_detected() {
         say!(verbosity, AliasIcon::None, "");
         say!(verbosity, AliasIcon::Info, "Tip: Run `alias --reload` to synchronize all layers.");
     }
@@ -2253,7 +2898,22 @@ pub fn display_audit(mesh_list: &[AliasEntryMesh], verbosity: &Verbosity, provid
         })
         .max().unwrap_or(20);
 
-    for entry in mesh_list {
+    // [group] sections print together, in file order, after the ungrouped entries.
+    // Any other --sort is taken as asked: no regrouping, no section headers.
+    let by_section = verbosity.filter.sort == AuditSort::File;
+    let mut sections: Vec<Option<&str>> = vec![None];
+    for g in mesh_list.iter().map(|e| e.group()) {
+        if !sections.contains(&g) { sections.push(g); }
+    }
+    let mut ordered: Vec<&AliasEntryMesh> = mesh_list.iter().collect();
+    if by_section { ordered.sort_by_key(|e| sections.iter().position(|g| *g == e.group())); }
+    let mut current: Option<&str> = None;
+
+    for entry in ordered {
+        if by_section && entry.group() != current {
+            current = entry.group();
+            verbosity.whisper(&format!("[{}]", current.unwrap_or_default()));
+        }
         // 1. Check for corruption in the Name before alignment
         let mut corruption_note = String::new();
         if !is_valid_name(&entry.name) {
@@ -2276,24 +2936,49 @@ pub fn display_audit(mesh_list: &[AliasEntryMesh], verbosity: &Verbosity, provid
         if !corruption_note.is_empty() {
             print!("{}", corruption_note);
         }
+        if let Some(note) = entry.meta.as_ref().and_then(meta_note) {
+            print!(" {}", note);
+        }
+        if let Some(note) = entry.origin.as_ref().and_then(layers::origin_note) {
+            print!(" {}", note);
+        }
         println!(); // Ensure the line terminates if align doesn't
 
         // 4. Check for standard value discrepancies
         if let (Some(os), Some(fi)) = (&entry.os_value, &entry.file_value) {
-            if os != fi {
+            if !macro_equivalent(os, fi) {
                 verbosity.shout(&format!("Desync for {}: File has '{}'", entry.name, fi));
                 desync_detected = true;
             }
         }
     }
 
+    if let Some(path) = get_alias_path("") {
+        let disabled = disabled_groups(&path);
+        if !disabled.is_empty() {
+            say!(verbosity, AliasIcon::Info, "Disabled groups: {} (alias --enable-group <name>)", disabled.join(", "));
+        }
+    }
     if desync_detected && verbosity.show_audit() {
         say!(verbosity, AliasIcon::None, "\n");
         say!(verbosity, AliasIcon::Info, "Tip: Run `alias --reload` to fix corrupted or out-of-sync macros.");
     }
 }
 
+fn meta_note(meta: &AliasMeta) -> Option<String> {
+    let tags = if meta.tags.is_empty() { String::new() } else { format!(" [{}]", meta.tags.join(",")) };
+    match &meta.desc {
+        Some(desc) => Some(format!("{} {}{}", AliasMeta::PREFIX, desc, tags)),
+        None if !tags.is_empty() => Some(format!("{}{}", AliasMeta::PREFIX, tags)),
+        None => None,
+    }
+}
+
 pub fn render_diagnostics(report: DiagnosticReport, verbosity: &Verbosity) {
+    if verbosity.format.is_machine() {
+        verbosity.records(&[output::diagnostics_record(&report)]);
+        return;
+    }
     whisper!(verbosity, AliasIcon::Tools, "--- Alias Tool Diagnostics ---");
     let w = 15;
     let none = (false, false, false);
@@ -2363,10 +3048,6 @@ pub fn is_data_line(line: &str) -> Option<(&str, &str)> {
     trimmed.split_once('=').map(|(n, v)| (n.trim(), v))
 }
 
-fn functional_cmp(a: &str, b: &str) -> bool {
-    a.trim_matches('"') == b.trim_matches('"')
-}
-
 // Progressively looser checks, can pick up anywhere in the chain
 pub fn is_valid_name(name: &str) -> bool {
     // 1. Basic whitespace and emptiness checks
@@ -2472,24 +3153,27 @@ pub fn get_alias_path(current_file: &str) -> Option<PathBuf> {
         })
 }
 
-fn dump_alias_file(verbosity: &Verbosity) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+fn dump_alias_file(verbosity: &Verbosity) -> Result<Vec<LayeredEntry>, Box<dyn std::error::Error>> {
     let path = get_alias_path("").ok_or_else(|| {
         failure!(verbosity, ErrorCode::MissingFile, "Could not locate the alias configuration file.")
     })?;
+    load_layers(verbosity, &path)
+}
+
+// Every data line, corrupt names included (the audit wants to see them).
+pub fn read_alias_pairs(path: &Path, verbosity: &Verbosity) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    Ok(read_sourced_pairs(path, verbosity)?.into_iter().map(|p| (p.name, p.value)).collect())
+}
+
+// Same as read_alias_pairs, but every entry remembers the file:line it came from (#include).
+// Entries of disabled [groups] are left out: this is the file as a reload would load it.
+pub fn read_sourced_pairs(path: &Path, verbosity: &Verbosity) -> Result<Vec<SourcedPair>, Box<dyn std::error::Error>> {
     match verify_read_readiness(&PathBuf::from(&path)) {
         AccessResult::Blocked(msg) => {
             Err(failure!(verbosity, ErrorCode::AccessDenied, "File is currently locked by another process. {}", msg))
         },
         AccessResult::Empty => { Ok(Vec::new()) },
-        AccessResult::Ready => {
-            let content = std::fs::read_to_string(path).map_err(|e| failure!(verbosity, e))?;
-            let pairs = content.lines()
-                .filter_map(is_data_line) // Use the DRY helper
-                .map(|(n, v)| (n.to_string(), v.to_string()))
-                .collect();
-
-            Ok(pairs)
-        },
+        AccessResult::Ready => Ok(groups::enabled_pairs(path, expand_includes(path, verbosity)?)),
     }
 }
 
@@ -2498,6 +3182,7 @@ pub fn get_editor_preference(verbosity: &Verbosity, editor: &Option<String>) ->
     let raw_ed = editor.clone()
         .or_else(|| env::var(ENV_VISUAL).ok())
         .or_else(|| env::var(ENV_EDITOR).ok())
+        .or_else(|| config::config_value("editor"))
         .unwrap_or_else(|| FALLBACK_EDITOR.to_string());
 
     // drop normalizing issues. shlex and win don't mind /
@@ -2568,36 +3253,93 @@ pub fn normalize_path(path: PathBuf) -> String {
     // Otherwise just strip the standard extended prefix
     s.strip_prefix(UNC_PATH).unwrap_or(&s).to_string()
 }
+/// raw_set_macro for callers that must know the macro landed: `Ok(false)` (doskey.exe exited
+/// non-zero, or every hybrid provider declined) is a failure like any error.
+pub fn inject_macro(provider: &dyn AliasProvider, name: &str, value: Option<&str>) -> io::Result<()> {
+    if provider.raw_set_macro(name, value)? { Ok(()) }
+    else { Err(io::Error::other(format!("the console declined '{}'", name))) }
+}
+
+// Runs before a value hits RAM. Doskey accepts anything, so this only warns.
+pub fn warn_macro_issues(verbosity: &Verbosity, name: &str, value: &str) {
+    for issue in validate_macro(value) {
+        shout!(verbosity, AliasIcon::Alert, "Macro '{}': {}", name, issue);
+    }
+}
+
 #[cfg_attr(debug_assertions, named)]
 pub fn update_disk_file(verbosity: &Verbosity, name: &str, value: &str, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    // 1. Load existing data
-    let mut pairs = {
+    // 0. Serialize with other alias processes for the whole read-modify-write
+    let _lock = lock_alias_file(verbosity, path)?;
+
+    // 1. Load existing data (lossless: comments, blanks and line endings ride along)
+    let mut doc = {
         if path.exists() {
-            parse_macro_file(path, verbosity)?
+            AliasDocument::load(path, verbosity)?
         } else {
-            Vec::new()
+            AliasDocument::default()
         }
     }; // and DROP THE READ HABDLE
 
-    // 2. Core Logic: Update, Remove, or Append
-    if let Some(pos) = pairs.iter().position(|(n, _)| n == name) {
-        if value.is_empty() {
-            pairs.remove(pos);
-        } else {
-            pairs[pos].1 = value.to_string();
+    // 2. Core Logic: Update, Remove, or Append. Only the affected node is rewritten.
+    doc.set(name, value);
+
+    write_alias_document(verbosity, &doc, path)
+}
+
+/// Advisory exclusive lock on `<file>.lock`, released on drop.
+/// The alias file itself is replaced by rename, so it cannot carry the lock.
+pub struct AliasFileLock {
+    _handle: File,
+}
+
+pub fn alias_lock_path(path: &Path) -> PathBuf {
+    let mut lock = path.as_os_str().to_owned();
+    lock.push(".lock");
+    PathBuf::from(lock)
+}
+
+pub fn lock_alias_file(verbosity: &Verbosity, path: &Path) -> Result<AliasFileLock, Box<dyn std::error::Error>> {
+    let lock_path = alias_lock_path(path);
+    let handle = fs::OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)
+        .map_err(|e| failure!(verbosity, e))?;
+
+    // Bounded wait: a healthy writer holds the lock for a few ms, a stuck one must not hang the shell
+    let deadline = Instant::now() + IO_RESPONSIVENESS_THRESHOLD;
+    loop {
+        match handle.try_lock_exclusive() {
+            Ok(()) => return Ok(AliasFileLock { _handle: handle }),
+            Err(_) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(2)),
+            Err(e) => {
+                return Err(failure!(verbosity, ErrorCode::LockTimeout,
+                    "Alias file is locked by another process ({}): {}", e, lock_path.display()));
+            },
         }
-    } else if !value.is_empty() {
-        pairs.push((name.to_string(), value.to_string()));
     }
+}
+
+pub fn write_alias_document(verbosity: &Verbosity, doc: &AliasDocument, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    // Our own writes must not make an allowed project file untrusted
+    let project = path.file_name().is_some_and(|n| n == PROJECT_ALIAS_FILENAME)
+        .then(|| (project::project_hash(path).ok(), get_alias_path("").map(|user| project::ProjectState::for_user(&user))));
+    let result = write_alias_document_raw(verbosity, doc, path);
+    if result.is_ok() && let Some((before, Some(state))) = project {
+        project::retrust_after_write(verbosity, &state, path, before.as_deref());
+    }
+    result
+}
 
+#[cfg_attr(debug_assertions, named)]
+fn write_alias_document_raw(verbosity: &Verbosity, doc: &AliasDocument, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
     // 3. --- TRANSACTIONAL WRITE ---
+    backup::backup_before_write(verbosity, path);
     let tmp_path = path.with_extension("tmp");
 
-    // Build content string
-    let content: String = pairs.iter()
-        .map(|(n, v)| format!("{}={}", n, v))
-        .collect::<Vec<_>>()
-        .join("\n");
+    // Build content string, in the encoding the file came in
+    let (content, lost) = doc.encoding.encode(&doc.to_string());
+    if lost > 0 {
+        shout!(verbosity, AliasIcon::Alert, "{} characters cannot be stored as {} and were written as '?'", lost, doc.encoding);
+    }
 
     // Attempt the write to temp file
     if let Err(e) = fs::write(&tmp_path, content) {
@@ -2624,6 +3366,92 @@ pub fn update_disk_file(verbosity: &Verbosity, name: &str, value: &str, path: &P
     }
 }
 
+pub fn describe_alias(verbosity: &Verbosity, opts: &SetOptions, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let _lock = lock_alias_file(verbosity, path)?;
+    let mut doc = AliasDocument::load(path, verbosity)?;
+    if doc.get(&opts.name).is_none() {
+        return Err(failure!(verbosity, ErrorCode::MissingName, "Cannot describe '{}': not defined in {}", opts.name, path.display()));
+    }
+
+    // The text may carry its own @tags; a bare description keeps the existing tags
+    let incoming = AliasMeta::parse(&format!("{} @desc {}", AliasMeta::PREFIX, opts.value)).unwrap_or_default();
+    let mut meta = doc.meta(&opts.name).unwrap_or_default();
+    meta.desc = incoming.desc;
+    if !incoming.tags.is_empty() { meta.tags = incoming.tags; }
+    if meta.created.is_none() { meta.created = Some(utc_timestamp()); }
+
+    doc.set_meta(&opts.name, &meta);
+    write_alias_document(verbosity, &doc, path)?;
+    whisper!(verbosity, AliasIcon::Success, "Described alias: {}", opts.name);
+    Ok(())
+}
+
+/// `--encoding`: reports the file's encoding and newline style, or rewrites it in `target`.
+pub fn convert_encoding(verbosity: &Verbosity, target: Option<AliasEncoding>, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let _lock = lock_alias_file(verbosity, path)?;
+    let mut doc = AliasDocument::load(path, verbosity)?;
+    let eol = if doc.dominant_eol() == "\r\n" { "CRLF" } else { "LF" };
+    let Some(target) = target.filter(|t| *t != doc.encoding) else {
+        say!(verbosity, AliasIcon::File, "{}: {}, {} line endings", path.display(), doc.encoding, eol);
+        return Ok(());
+    };
+    let from = doc.encoding;
+    doc.encoding = target;
+    write_alias_document(verbosity, &doc, path)?;
+    say!(verbosity, AliasIcon::Success, "Converted {} from {} to {} ({} line endings kept)", path.display(), from, target, eol);
+    Ok(())
+}
+
+pub fn import_aliases(provider: &dyn AliasProvider, verbosity: &Verbosity, opts: &ImportOptions, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let content = read_alias_text(&opts.source).map_err(|e| failure!(verbosity, e))?;
+    let format = opts.from.unwrap_or_else(|| ImportFormat::detect(&opts.source));
+    let mut report = parse_import(&content, format);
+
+    let _lock = lock_alias_file(verbosity, path)?;
+    let mut doc = if path.exists() { AliasDocument::load(path, verbosity)? } else { AliasDocument::default() };
+    let (mut added, mut updated, mut unchanged, mut conflicts) = (0, 0, 0, 0);
+    let mut touched: Vec<BatchOp> = Vec::new();
+
+    for (i, (raw_name, value)) in report.aliases.into_iter().enumerate() {
+        if !is_valid_name(&raw_name) {
+            report.skipped.push((raw_name, "invalid alias name".to_string()));
+            continue;
+        }
+        // A value doskey would mangle is refused here, as --apply does
+        let issues = validate_macro(&value);
+        if !issues.is_empty() {
+            report.skipped.push((raw_name, issues.iter().map(|i| i.to_string()).collect::<Vec<_>>().join("; ")));
+            continue;
+        }
+        // Doskey names are case-insensitive: an existing 'GS=' is the same alias, and keeps its spelling
+        let existing = doc.entries().into_iter().rev().find(|(n, _)| n.eq_ignore_ascii_case(&raw_name));
+        let name = existing.as_ref().map_or_else(|| raw_name.to_lowercase(), |(n, _)| n.clone());
+        match existing {
+            None => added += 1,
+            Some((_, current)) if current == value => { unchanged += 1; continue; },
+            Some((_, current)) => {
+                conflicts += 1;
+                shout!(verbosity, AliasIcon::Alert, "Conflict '{}': file has '{}', incoming '{}' -> keeping {}", name, current, value, opts.prefer);
+                if opts.prefer == ImportPolicy::Existing { continue; }
+                updated += 1;
+            },
+        }
+        doc.set(&name, &value);
+        touched.push(BatchOp { line: i + 1, name, value: Some(value) });
+    }
+
+    // Same all-or-nothing path as --apply: one write, one RAM pass, rolled back if RAM refuses
+    if !touched.is_empty() {
+        apply::apply_batch_locked(provider, verbosity, &touched, path)?;
+    }
+    for (name, reason) in &report.skipped {
+        shout!(verbosity, AliasIcon::Alert, "Skipped '{}': {}", name, reason);
+    }
+    say!(verbosity, AliasIcon::Success, "Import ({}): {} added, {} updated, {} unchanged, {} conflicts, {} skipped",
+        format, added, updated, unchanged, conflicts, report.skipped.len());
+    Ok(())
+}
+
 pub fn parse_macro_file(path: &Path, verbosity: &Verbosity) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
     match verify_read_readiness(&PathBuf::from(&path)) {
         AccessResult::Blocked(msg) => {
@@ -2631,17 +3459,28 @@ pub fn parse_macro_file(path: &Path, verbosity: &Verbosity) -> Result<Vec<(Strin
         },
         AccessResult::Empty => { Ok(Vec::new()) },
         AccessResult::Ready => {
-            let content = fs::read_to_string(path).map_err(|e| failure!(verbosity, e))?;
-            let pairs = content.lines()
-                .filter_map(is_data_line)
-                .filter(|(n, _)| is_valid_name(n)) // Firewall: Drops anything not starting with alpha/underscore
-                .map(|(n, v)| (n.to_string(), v.to_string())) // No more .trim() here!
+            // Includes are spliced in place, so the stream loads like one flat file (minus disabled groups)
+            let pairs = groups::enabled_pairs(path, expand_includes(path, verbosity)?).into_iter()
+                .filter(|p| is_valid_name(&p.name)) // Firewall: Drops anything not starting with alpha/underscore
+                .map(|p| (p.name, p.value)) // No more .trim() here!
                 .collect();
             Ok(pairs)
         },
     }
 }
 
+// RAM first (that is what cmd.exe will expand), then the file for aliases not loaded yet.
+pub fn lookup_macro_value(provider: &dyn AliasProvider, name: &str, path: &Path) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let matching = |lines: Vec<String>| lines.iter()
+        .filter_map(|line| parse_alias_line(line))
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v);
+    if let Some(value) = matching(provider.query_alias(name, &Verbosity::silent())) {
+        return Ok(Some(value));
+    }
+    Ok(matching(query_alias_file(name, path, &Verbosity::silent())?))
+}
+
 pub fn query_alias_file(name: &str, path: &Path, verbosity: &Verbosity) -> Result<Vec<String>, Box<dyn std::error::Error>> {
     match verify_read_readiness(&PathBuf::from(&path)) {
         AccessResult::Blocked(msg) => {
@@ -2652,7 +3491,7 @@ pub fn query_alias_file(name: &str, path: &Path, verbosity: &Verbosity) -> Resul
             let mut results = Vec::new();
             let search = format!("{}=", name.to_lowercase());
             // Read the file (The Source of Truth)
-            let content = std::fs::read_to_string(path).map_err(|e| failure!(verbosity, e))?;
+            let content = read_alias_text(path).map_err(|e| failure!(verbosity, e))?;
             let found = content
                 .lines()
                 .find(|line| line.to_lowercase().starts_with(&search));
@@ -2822,6 +3661,25 @@ pub fn peek_pe_metadata(path: &Path) -> io::Result<(BinarySubsystem, bool)> {
     Ok((subsystem, is_32bit))
 }
 
+// ISO-8601 UTC ("2025-01-31T09:00:00Z") without pulling in a date crate.
+pub fn utc_timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Civil-from-days (Howard Hinnant), valid for any date after 1970
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rem / 3_600, (rem % 3_600) / 60, rem % 60)
+}
+
 pub fn random_num_bounded(limit: usize) -> usize {
     if limit == 0 { return 0 };
     // 1. Get the time (ms or ns)
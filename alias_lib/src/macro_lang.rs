@@ -0,0 +1,212 @@
+// alias_lib/src/macro_lang.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Tokenizer for the right-hand side of `name=value`, i.e. the doskey macro language:
+//   $1..$9  $*  $T  $G  $G$G  $L  $B  $$   and everything else is literal text.
+// Tokenizing never fails. Anything doskey would misread is collected as a MacroIssue
+// so callers can decide: set_alias warns, export refuses, the audit compares tokens.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroToken {
+    Text(String),
+    Arg(u8),     // $1..$9
+    AllArgs,     // $*
+    Separator,   // $T
+    RedirectOut, // $G
+    Append,      // $G$G
+    RedirectIn,  // $L
+    Pipe,        // $B
+    Dollar,      // $$
+}
+
+impl fmt::Display for MacroToken {
+    /// Canonical spelling, so `$t` and `$T` print (and compare) the same.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => write!(f, "{}", text),
+            Self::Arg(n) => write!(f, "${}", n),
+            Self::AllArgs => write!(f, "$*"),
+            Self::Separator => write!(f, "$T"),
+            Self::RedirectOut => write!(f, "$G"),
+            Self::Append => write!(f, "$G$G"),
+            Self::RedirectIn => write!(f, "$L"),
+            Self::Pipe => write!(f, "$B"),
+            Self::Dollar => write!(f, "$$"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroIssue {
+    /// `$0` - doskey has no "macro name" parameter
+    ZeroArg { pos: usize },
+    /// `$10` - doskey reads `$1` followed by a literal `0`
+    ArgOutOfRange { pos: usize, text: String },
+    /// `$Q` and friends - doskey passes these through untouched
+    UnknownToken { pos: usize, text: String },
+    DanglingDollar { pos: usize },
+    /// An odd `"` swallows the rest of the line, separators included (the XCD tail murder)
+    UnbalancedQuote { pos: usize },
+}
+
+impl MacroIssue {
+    /// Issues that make the macro meaningless outside cmd.exe. The others still run, just not as intended.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Self::ZeroArg { .. } | Self::UnknownToken { .. } | Self::DanglingDollar { .. })
+    }
+}
+
+impl fmt::Display for MacroIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroArg { .. } => write!(f, "unsupported doskey token '$0'"),
+            Self::ArgOutOfRange { text, .. } => write!(f, "'{}' is read as '$1' followed by '{}'", text, &text[2..]),
+            Self::UnknownToken { text, .. } => write!(f, "unsupported doskey token '{}'", text),
+            Self::DanglingDollar { .. } => write!(f, "dangling '$' at end of macro"),
+            Self::UnbalancedQuote { pos } => write!(f, "unbalanced quote at column {}", pos + 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MacroParse {
+    pub tokens: Vec<MacroToken>,
+    pub issues: Vec<MacroIssue>,
+}
+
+impl MacroParse {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+    pub fn first_fatal(&self) -> Option<&MacroIssue> {
+        self.issues.iter().find(|issue| issue.is_fatal())
+    }
+}
+
+pub fn tokenize_macro(value: &str) -> MacroParse {
+    let mut parse = MacroParse::default();
+    let bytes = value.as_bytes();
+    let (mut i, mut start) = (0, 0);
+    let mut open_quote: Option<usize> = None;
+
+    let flush = |tokens: &mut Vec<MacroToken>, from: usize, to: usize| {
+        if from < to { tokens.push(MacroToken::Text(value[from..to].to_string())); }
+    };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                open_quote = if open_quote.is_some() { None } else { Some(i) };
+                i += 1;
+                continue;
+            },
+            b'$' => {},
+            _ => { i += 1; continue; },
+        }
+        let upper = |k: usize| bytes.get(k).map(|b| b.to_ascii_uppercase());
+        let token = match upper(i + 1) {
+            Some(b'*') => Some(MacroToken::AllArgs),
+            Some(b'0') => {
+                parse.issues.push(MacroIssue::ZeroArg { pos: i });
+                None
+            },
+            Some(d @ b'1'..=b'9') => {
+                if upper(i + 2).is_some_and(|b| b.is_ascii_digit()) {
+                    let end = (i + 2..bytes.len()).find(|&k| !bytes[k].is_ascii_digit()).unwrap_or(bytes.len());
+                    parse.issues.push(MacroIssue::ArgOutOfRange { pos: i, text: value[i..end].to_string() });
+                }
+                Some(MacroToken::Arg(d - b'0'))
+            },
+            Some(b'T') => Some(MacroToken::Separator),
+            Some(b'G') if bytes.get(i + 2) == Some(&b'$') && upper(i + 3) == Some(b'G') => {
+                flush(&mut parse.tokens, start, i);
+                parse.tokens.push(MacroToken::Append);
+                i += 4;
+                start = i;
+                continue;
+            },
+            Some(b'G') => Some(MacroToken::RedirectOut),
+            Some(b'L') => Some(MacroToken::RedirectIn),
+            Some(b'B') => Some(MacroToken::Pipe),
+            Some(b'$') => Some(MacroToken::Dollar),
+            Some(_) => {
+                let end = value[i + 1..].chars().next().map_or(value.len(), |c| i + 1 + c.len_utf8());
+                parse.issues.push(MacroIssue::UnknownToken { pos: i, text: value[i..end].to_string() });
+                None
+            },
+            None => {
+                parse.issues.push(MacroIssue::DanglingDollar { pos: i });
+                None
+            },
+        };
+        // Unrecognised sequences stay in the text run, exactly as doskey leaves them
+        let Some(token) = token else { i += 1; continue; };
+        flush(&mut parse.tokens, start, i);
+        parse.tokens.push(token);
+        i += 2;
+        start = i;
+    }
+    flush(&mut parse.tokens, start, bytes.len());
+
+    if let Some(pos) = open_quote {
+        parse.issues.push(MacroIssue::UnbalancedQuote { pos });
+    }
+    parse
+}
+
+/// Problems worth telling the user about before the macro reaches RAM.
+pub fn validate_macro(value: &str) -> Vec<MacroIssue> {
+    tokenize_macro(value).issues
+}
+
+/// True when both values expand the same way in doskey. Token case (`$t`/`$T`), a wrapping
+/// pair of quotes and surrounding whitespace are presentation, not meaning.
+pub fn macro_equivalent(a: &str, b: &str) -> bool {
+    fn canonical(value: &str) -> Vec<MacroToken> {
+        let value = value.trim();
+        let unwrapped = value.strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+            .filter(|inner| !inner.contains('"'))
+            .unwrap_or(value);
+        tokenize_macro(unwrapped).tokens
+    }
+    a == b || canonical(a) == canonical(b)
+}
+
+/// What cmd.exe would be handed for one invocation of a macro.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MacroExpansion {
+    pub commands: Vec<String>, // one per $T segment
+    pub issues: Vec<MacroIssue>,
+}
+
+/// Doskey splits the tail on spaces and tabs only. Quotes do NOT group, so
+/// `"a b"` arrives as `$1="a` and `$2=b"`.
+pub fn split_doskey_args(tail: &str) -> Vec<&str> {
+    tail.split([' ', '\t']).filter(|a| !a.is_empty()).collect()
+}
+
+/// Simulates doskey: `tail` is everything typed after the macro name.
+pub fn expand_macro(value: &str, tail: &str) -> MacroExpansion {
+    let parse = tokenize_macro(value);
+    let args = split_doskey_args(tail);
+    let mut expansion = MacroExpansion { commands: Vec::new(), issues: parse.issues };
+    let mut current = String::new();
+
+    for token in parse.tokens {
+        match token {
+            MacroToken::Text(text) => current.push_str(&text),
+            MacroToken::Arg(n) => current.push_str(args.get(n as usize - 1).copied().unwrap_or("")),
+            MacroToken::AllArgs => current.push_str(tail.trim_start_matches([' ', '\t'])),
+            MacroToken::Separator => expansion.commands.push(std::mem::take(&mut current)),
+            MacroToken::RedirectOut => current.push('>'),
+            MacroToken::Append => current.push_str(">>"),
+            MacroToken::RedirectIn => current.push('<'),
+            MacroToken::Pipe => current.push('|'),
+            MacroToken::Dollar => current.push('$'),
+        }
+    }
+    expansion.commands.push(current);
+    expansion
+}
@@ -0,0 +1,147 @@
+// alias_lib/src/memory.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// The In-Memory provider. No kernel32, no doskey.exe, no registry.
+// The macro table and the AutoRun string live on the instance, so every
+// test (or dry run) gets its own private "session".
+
+use std::{env, io};
+use std::path::Path;
+use std::sync::Mutex;
+use crate::*;
+
+#[derive(Default)]
+pub struct MemoryProvider {
+    macros: Mutex<Vec<(String, String)>>,
+    autorun: Mutex<String>,
+}
+
+impl MemoryProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pre-loads the macro table, e.g. to simulate a session that already has macros.
+    pub fn seeded(pairs: &[(&str, &str)]) -> Self {
+        let provider = Self::new();
+        for (n, v) in pairs {
+            let _ = provider.raw_set_macro(n, Some(v));
+        }
+        provider
+    }
+}
+
+impl AliasProvider for MemoryProvider {
+    fn raw_set_macro(&self, name: &str, value: Option<&str>) -> io::Result<bool> {
+        if name.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Memory table rejected empty alias name"));
+        }
+        // Console aliases match case-insensitively, so the table does too.
+        let key = name.to_lowercase();
+        let mut table = self.macros.lock().map_err(|_| io::Error::other("Memory table poisoned"))?;
+        let pos = table.iter().position(|(n, _)| n.to_lowercase() == key);
+        match (pos, value) {
+            (Some(i), Some(v)) => table[i] = (name.to_string(), v.to_string()),
+            (None, Some(v)) => table.push((name.to_string(), v.to_string())),
+            (Some(i), None) => { table.remove(i); },
+            (None, None) => {},
+        }
+        Ok(true)
+    }
+    fn raw_reload_from_file(&self, verbosity: &Verbosity, path: &Path) -> io::Result<()> {
+        let macros = parse_macro_file(path, verbosity)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+        for (n, v) in macros {
+            self.raw_set_macro(&n, Some(&v))?;
+        }
+        Ok(())
+    }
+    fn get_all_aliases(&self, _verbosity: &Verbosity) -> io::Result<Vec<(String, String)>> {
+        let table = self.macros.lock().map_err(|_| io::Error::other("Memory table poisoned"))?;
+        Ok(table.clone())
+    }
+    fn write_autorun_registry(&self, cmd: &str, verbosity: &Verbosity) -> io::Result<()> {
+        let mut autorun = self.autorun.lock().map_err(|_| io::Error::other("Memory AutoRun poisoned"))?;
+        *autorun = cmd.to_string();
+        shout!(verbosity, AliasIcon::Success, "AutoRun synchronized (Memory-mode).");
+        Ok(())
+    }
+    fn read_autorun_registry(&self) -> String {
+        self.autorun.lock().map(|a| a.clone()).unwrap_or_default()
+    }
+    fn purge_ram_macros(&self, verbosity: &Verbosity) -> io::Result<PurgeReport> {
+        let mut report = PurgeReport::default();
+        for (name, _) in self.get_all_aliases(verbosity)? {
+            if self.raw_set_macro(&name, None)? {
+                report.cleared.push(name);
+            } else {
+                report.failed.push((name, 0));
+            }
+        }
+        Ok(report)
+    }
+    fn query_alias(&self, name: &str, verbosity: &Verbosity) -> Vec<String> {
+        let search_target = name.to_lowercase();
+        let os_list = self.get_all_aliases(verbosity).unwrap_or_default();
+
+        for (n, v) in os_list {
+            if n.to_lowercase() == search_target {
+                return vec![format!("{}={}", n, v)];
+            }
+        }
+        vec![text!(verbosity, AliasIcon::Alert, "'{}' not found in memory table.", name)]
+    }
+    fn set_alias(&self, opts: SetOptions, path: &Path, verbosity: &Verbosity) -> io::Result<()> {
+        let name = if opts.force_case { opts.name.clone() } else { opts.name.to_lowercase() };
+        let val_opt = if opts.value.is_empty() { None } else { Some(opts.value.as_str()) };
+        warn_macro_issues(verbosity, &name, &opts.value);
+
+        self.raw_set_macro(&name, val_opt)?;
+
+        if opts.volatile {
+            say!(verbosity, AliasIcon::Win32, "Volatile alias (RAM Only): {}", name);
+            return Ok(());
+        }
+
+        update_disk_file(verbosity, &name, &opts.value, path)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        whisper!(verbosity, AliasIcon::Success, "{} alias: {}", if opts.value.is_empty() { "Deleted" } else { "Set" }, name);
+        Ok(())
+    }
+    fn run_diagnostics(&self, path: &Path, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
+        let autorun = self.read_autorun_registry();
+        let registry_status = if autorun.is_empty() {
+            RegistryStatus::NotFound
+        } else if autorun.contains("alias") {
+            RegistryStatus::Synced
+        } else {
+            RegistryStatus::Mismatch(autorun)
+        };
+        let report = DiagnosticReport {
+            binary_path: env::current_exe().ok(),
+            resolved_path: path.to_path_buf(),
+            env_file: env::var(ENV_ALIAS_FILE).unwrap_or_else(|_| "NOT SET".to_string()),
+            env_opts: env::var(ENV_ALIAS_OPTS).unwrap_or_else(|_| "NOT SET".to_string()),
+            file_exists: path.exists(),
+            is_readonly: path.metadata().map(|m| m.permissions().readonly()).unwrap_or(false),
+            drive_responsive: matches!(is_drive_responsive(path, IO_RESPONSIVENESS_THRESHOLD), AccessResult::Ready),
+            registry_status,
+            api_status: Some("CONNECTED (In-Memory)".to_string()),
+        };
+        render_diagnostics(report, verbosity);
+        Ok(())
+    }
+    fn alias_show_all(&self, verbosity: &Verbosity) -> Result<AuditReport, Box<dyn std::error::Error>> {
+        if verbosity.level == VerbosityLevel::Mute { return Ok(AuditReport::default()); }
+
+        let os_pairs = self.get_all_aliases(verbosity)?;
+        perform_audit(os_pairs, verbosity, &self.provider_type())
+    }
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Memory
+    }
+    fn get_version(&self) -> &'static Versioning {
+        Versioning::current()
+    }
+}
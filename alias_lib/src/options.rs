@@ -0,0 +1,480 @@
+// alias_lib/src/options.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// The option table: one row per command-line switch. FromStr, to_cli_args (and so Display),
+// requires_file, the error text, AliasAction::catalog (and so --completions) and the FLAGS
+// part of --help all read it. Each row also carries the two functions that build its action
+// from the typed values and read them back, so a switch lives in exactly one place.
+//
+// RENDERING (to_cli_args)
+//   --flag                    no value, or an optional value left out
+//   --flag v1 v2              values in order; a value with blanks (or an empty one) is "quoted"
+//   --flag="v"                Attached values (--edalias) always use `=`
+//   --no-flag                 the off side of a negatable row
+// FromStr splits the same way (blanks separate, "..." groups, no escapes), so for every
+// action parse(render(action)) == action. A value holding `"` does not survive the trip.
+
+use std::fmt;
+use std::path::PathBuf;
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionValue {
+    None,
+    /// `--flag <meta>`; the meta is shown in --help as written.
+    Required(&'static str),
+    /// `--flag [meta]`
+    Optional(&'static str),
+    /// `--flag[=meta]`: only `=` attaches a value, the next argument is never taken.
+    Attached(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpSection {
+    Info,
+    MacroState,
+    Toggles,
+    Actions,
+    System,
+}
+
+impl HelpSection {
+    pub const ALL: [Self; 5] = [Self::Info, Self::MacroState, Self::Toggles, Self::Actions, Self::System];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Info => "FLAGS:",
+            Self::MacroState => "MACRO STATE:\nDefaults are --no-temp, --no-case",
+            Self::Toggles => "SYMMETRIC TOGGLES:\nDefaults are --no-quiet --icons --tips (at 10%)",
+            Self::Actions => "PRIMARY ACTIONS:\n  <name>                Query/Lookup a specific alias definition",
+            Self::System => "SYSTEM & BOOT:",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OptionSpec {
+    pub long: &'static str,
+    /// Other spellings FromStr accepts; never rendered.
+    pub aliases: &'static [&'static str],
+    /// Also accepted as `--no-<long>`, which selects the off variant.
+    pub negatable: bool,
+    pub value: OptionValue,
+    pub requires_file: bool,
+    pub section: HelpSection,
+    /// One --help line; `\n` continues on the next line, indented to the text column.
+    pub help: &'static str,
+    /// Error context, followed by `: <values>` when the action carries any.
+    pub error: &'static str,
+    /// Error context for the `--no-` side.
+    pub error_off: &'static str,
+    pub action: OptionAction,
+}
+
+/// Whether a switch is the `--no-` side, and the values that follow it.
+pub type SwitchValues = (bool, Vec<String>);
+
+/// The code half of a row.
+#[derive(Debug, Clone, Copy)]
+pub struct OptionAction {
+    /// The action for the `--no-` side (or not) and the values that followed; None when they do not fit.
+    pub build: fn(bool, &[String]) -> Option<AliasAction>,
+    /// The reverse, for an action this row owns: the side and the values, unquoted, a default
+    /// that need not be written left out. None for any other action.
+    pub extract: fn(&AliasAction) -> Option<SwitchValues>,
+}
+
+const fn flag(long: &'static str, action: OptionAction, section: HelpSection, help: &'static str, error: &'static str) -> OptionSpec {
+    OptionSpec { long, aliases: &[], negatable: false, value: OptionValue::None, requires_file: false, section, help, error, error_off: "", action }
+}
+
+const fn toggle(long: &'static str, action: OptionAction, section: HelpSection, help: &'static str, error: &'static str, error_off: &'static str) -> OptionSpec {
+    OptionSpec { negatable: true, error_off, ..flag(long, action, section, help, error) }
+}
+
+const fn valued(long: &'static str, action: OptionAction, value: OptionValue, section: HelpSection, help: &'static str, error: &'static str) -> OptionSpec {
+    OptionSpec { value, ..flag(long, action, section, help, error) }
+}
+
+// A variant without a payload
+macro_rules! bare {
+    ($($variant:tt)+) => { OptionAction {
+        build: |_, _| Some(AliasAction::$($variant)+),
+        extract: |action| matches!(action, AliasAction::$($variant)+).then(|| (false, Vec::new())),
+    } };
+}
+
+// The two sides of a negatable row
+macro_rules! on_off {
+    ($on:ident, $off:ident) => { OptionAction {
+        build: |negated, _| Some(if negated { AliasAction::$off } else { AliasAction::$on }),
+        extract: |action| match action {
+            AliasAction::$on => Some((false, Vec::new())),
+            AliasAction::$off => Some((true, Vec::new())),
+            _ => None,
+        },
+    } };
+}
+
+// One String payload: the first value as typed, or lowercased
+macro_rules! text_option {
+    ($variant:ident) => { text_option!(@ $variant, String::clone) };
+    ($variant:ident, lower) => { text_option!(@ $variant, str::to_lowercase) };
+    (@ $variant:ident, $fold:path) => { OptionAction {
+        build: |_, values| Some(AliasAction::$variant(values.first().map(|v| $fold(v)).unwrap_or_default())),
+        extract: |action| match action { AliasAction::$variant(v) => Some((false, non_empty(v))), _ => None },
+    } };
+}
+
+const EDIT: OptionAction = OptionAction {
+    build: |_, values| Some(AliasAction::Edit(values.first().cloned())),
+    extract: |action| match action { AliasAction::Edit(editor) => Some((false, editor.iter().cloned().collect())), _ => None },
+};
+
+const REMOVE: OptionAction = OptionAction {
+    build: |_, values| Some(AliasAction::Remove(match values.first() {
+        Some(name) => SetOptions::involatile(name.clone(), false),
+        None => SetOptions::empty(false),
+    })),
+    extract: |action| match action { AliasAction::Remove(opts) => Some((false, non_empty(&opts.name))), _ => None },
+};
+
+const UNALIAS: OptionAction = OptionAction {
+    build: |_, values| Some(AliasAction::Unalias(match values.first() {
+        Some(name) => SetOptions::volatile(name.clone(), false),
+        None => SetOptions::empty(true),
+    })),
+    extract: |action| match action { AliasAction::Unalias(opts) => Some((false, non_empty(&opts.name))), _ => None },
+};
+
+const DESCRIBE: OptionAction = OptionAction {
+    build: |_, values| Some(AliasAction::Describe(SetOptions {
+        name: values.first().cloned().unwrap_or_default(),
+        value: values.get(1..).map(|t| t.join(" ")).unwrap_or_default(),
+        volatile: false,
+        force_case: false,
+    })),
+    extract: |action| match action {
+        AliasAction::Describe(opts) if opts.name.is_empty() => Some((false, Vec::new())),
+        AliasAction::Describe(opts) => Some((false, vec![opts.name.clone(), opts.value.clone()])),
+        _ => None,
+    },
+};
+
+const EXPAND: OptionAction = OptionAction {
+    build: |_, values| Some(AliasAction::Expand(values.first().cloned().unwrap_or_default(), values.get(1..).map(<[String]>::to_vec).unwrap_or_default())),
+    extract: |action| match action {
+        AliasAction::Expand(name, _) if name.is_empty() => Some((false, Vec::new())),
+        AliasAction::Expand(name, args) => Some((false, std::iter::once(name).chain(args).cloned().collect())),
+        _ => None,
+    },
+};
+
+const IMPORT: OptionAction = OptionAction {
+    build: |_, values| {
+        let mut opts = ImportOptions { source: PathBuf::from(values.first().cloned().unwrap_or_default()), ..ImportOptions::default() };
+        for pair in values.get(1..).unwrap_or(&[]).chunks(2) {
+            match (pair[0].to_lowercase().as_str(), pair.get(1)) {
+                ("--from", Some(f)) => opts.from = f.parse().ok(),
+                ("--prefer", Some(p)) => opts.prefer = p.parse().unwrap_or_default(),
+                _ => return None,
+            }
+        }
+        Some(AliasAction::Import(opts))
+    },
+    extract: |action| {
+        let AliasAction::Import(opts) = action else { return None; };
+        let mut values = vec![opts.source.display().to_string()];
+        if let Some(from) = opts.from { values.extend(["--from".to_string(), from.to_string()]); }
+        if opts.prefer != ImportPolicy::default() { values.extend(["--prefer".to_string(), opts.prefer.to_string()]); }
+        Some((false, values))
+    },
+};
+
+const UNDO: OptionAction = OptionAction {
+    build: |_, values| match values.first() {
+        None => Some(AliasAction::Undo(1)),
+        Some(n) => n.parse().ok().map(AliasAction::Undo),
+    },
+    extract: |action| match action {
+        AliasAction::Undo(steps) => Some((false, if *steps <= 1 { Vec::new() } else { vec![steps.to_string()] })),
+        _ => None,
+    },
+};
+
+const CONFIG: OptionAction = OptionAction {
+    build: |_, values| Some(match values.first().map(|v| v.to_lowercase()).as_deref() {
+        Some("set") => AliasAction::ConfigSet(
+            values.get(1).map(|k| k.to_lowercase()).unwrap_or_default(),
+            values.get(2..).map(|v| v.join(" ")).unwrap_or_default(),
+        ),
+        _ => AliasAction::ConfigShow,
+    }),
+    extract: |action| match action {
+        AliasAction::ConfigShow => Some((false, vec!["show".to_string()])),
+        AliasAction::ConfigSet(key, value) => Some((false, vec!["set".to_string(), key.clone(), value.clone()])),
+        _ => None,
+    },
+};
+
+const fn on_file(spec: OptionSpec) -> OptionSpec {
+    OptionSpec { requires_file: true, ..spec }
+}
+
+use HelpSection::{Actions, Info, MacroState, System, Toggles};
+use OptionValue::{Attached, Optional, Required};
+
+pub static OPTIONS: &[OptionSpec] = &[
+    flag("--help", bare!(Help), Info, "Show this help menu", "Display help"),
+    flag("--license", bare!(License), Info, "Show Licensing information", "Error displaying license"),
+    flag("--version", bare!(Version), Info, "Show full build metadata", "Error getting versions"),
+    flag("--ver", bare!(VersionShort), Info, "Show the short version string", "Error getting versions"),
+
+    toggle("--temp", on_off!(Temp, NoTemp), MacroState, "Force Volatility (RAM only) vs. Persistence",
+        "Error setting/using process as memory only", "Error setting/using process as dual (mem/disk)"),
+    toggle("--case", on_off!(Case, NoCase), MacroState, "Force Case-Sensitivity vs. Case-Insensitive",
+        "Error setting/using force case", "Error unsetting/disabling force case"),
+    on_file(valued("--file", bare!(File), Required("<path>"), MacroState, "Redirect action to a specific alias file",
+        "Error loading file for actions or load")),
+    valued("--scope", text_option!(Scope, lower), Required("<layer>"), MacroState, "Write to the team, user or project file (session = --temp)",
+        "Error selecting alias layer"),
+    valued("--group", text_option!(Group, lower), Required("<name>"), MacroState, "Only show the [name] section in --show-all and the audit",
+        "Error filtering by alias group"),
+    valued("--list", text_option!(List), Required("<glob>"), MacroState, "Only list names matching a glob (git* or g?)", "Error filtering aliases by name"),
+    valued("--grep", text_option!(Grep), Required("<regex>"), MacroState, "Only list aliases whose value, in any layer, matches (case-insensitive)",
+        "Error filtering aliases by value"),
+    flag("--pending-only", bare!(Only(AuditStatus::Pending)), MacroState, "Only list entries in the file but not loaded in RAM", "Error filtering pending aliases"),
+    flag("--phantom-only", bare!(Only(AuditStatus::Phantom)), MacroState, "Only list entries in RAM but missing from the file", "Error filtering phantom aliases"),
+    flag("--desync-only", bare!(Only(AuditStatus::Desync)), MacroState, "Only list entries whose layers disagree on the value", "Error filtering desynced aliases"),
+    flag("--corrupt-only", bare!(Only(AuditStatus::Corrupt)), MacroState, "Only list names cmd.exe can never trigger", "Error filtering corrupt aliases"),
+    valued("--sort", text_option!(Sort, lower), Required("<order>"), MacroState, "List by name, file (default) or length (longest value first)",
+        "Error sorting aliases by"),
+
+    toggle("--quiet", on_off!(Quiet, NoQuiet), Toggles, "Metadata/Whisper suppression", "Error setting/using quiet mode", "Error unsetting/disabling quiet mode"),
+    toggle("--icons", on_off!(Icons, NoIcons), Toggles, "ANSI glyphs/icons in output", "Error setting icons", "Error unsetting icons"),
+    toggle("--tips", on_off!(Tips, NoTips), Toggles, "Random usage hint injection", "Error setting tips", "Error unsetting tips"),
+    valued("--format", text_option!(Format, lower), Required("<fmt>"), Toggles, "json (one object per line), tsv or text records for\n--show-all, <name>, --which and --version",
+        "Error setting output format"),
+
+    on_file(valued("--remove", REMOVE, Required("<name>"), Actions, "Delete alias from file and RAM (see --temp and --file)", "Error removing alias")),
+    valued("--unalias", UNALIAS, Required("<name>"), Actions, "Drop alias from current session (RAM)", "Error unaliasing alias"),
+    on_file(valued("--describe", DESCRIBE, Required("<name>"), Actions, "Attach \"text\" (and optional @tags a,b) as a file comment", "Error describing alias")),
+    on_file(valued("--export", text_option!(Export, lower), Required("<shell>"), Actions, "Print aliases for bash, zsh, fish, powershell or clink", "Error exporting aliases as")),
+    on_file(valued("--expand", EXPAND, Required("<name> args"), Actions, "Show the command lines cmd.exe would run for these args", "Error expanding alias")),
+    on_file(valued("--import", IMPORT, Required("<path>"), Actions, "Merge aliases from a shell file [--from bash|zsh|powershell|\ndoskey-macros|clink] [--prefer incoming|existing]",
+        "Error importing aliases from")),
+    on_file(flag("--show-all", bare!(ShowAll), Actions, "List hydrated mapping (File + RAM)", "Error showing all aliases")),
+    on_file(flag("--which", bare!(Which), Actions, "Deep-audit sync status across all backends", "Error running diagnostics")),
+    on_file(flag("--backups", bare!(Backups), Actions, "List snapshots of the alias file (in .alias-backups)", "Error listing alias file backups")),
+    on_file(valued("--restore", text_option!(Restore), Required("<n|stamp>"), Actions, "Put a snapshot back (1 = newest) and reload RAM", "Error restoring alias file backup")),
+    on_file(valued("--undo", UNDO, Optional("[n]"), Actions, "Revert the last n journaled changes (file and RAM)", "Error undoing changes")),
+    on_file(flag("--redo", bare!(Redo), Actions, "Replay the last undone change", "Error redoing the last undone change")),
+    on_file(valued("--history", text_option!(History), Optional("[name]"), Actions, "Show how aliases (or just <name>) changed over time", "Error reading alias history")),
+    on_file(flag("--enter", bare!(Enter), Actions, "Load the nearest .aliases.doskey on top of RAM", "Error entering project aliases")),
+    on_file(flag("--leave", bare!(Leave), Actions, "Put back the values --enter shadowed", "Error leaving project aliases")),
+    on_file(valued("--allow", text_option!(Allow), Optional("[path]"), Actions, "Trust the project alias file as it is now (required before it loads)", "Error allowing project alias file")),
+    valued("--config", CONFIG, Optional("[show|set <k> <v>]"), Actions, "Print every setting and where it came from, or write a default\nto alias.toml (quiet, icons, format, editor, backups, ...)",
+        "Error with setting"),
+    on_file(valued("--encoding", text_option!(Encoding, lower), Optional("[enc]"), Actions, "Show the file's encoding, or convert it (utf8, utf8-bom, utf16le, utf16be, cp437)",
+        "Error converting alias file encoding")),
+    valued("--completions", text_option!(Completions, lower), Required("<shell>"), Actions, "Print a completion script for clink, powershell, bash, zsh or fish",
+        "Error generating completions for"),
+    on_file(valued("--apply", text_option!(Apply), Optional("[file|-]"), Actions, "Set/remove many aliases in one validated, all-or-nothing batch (default: stdin)",
+        "Error applying alias batch")),
+    on_file(valued("--fuzzy", text_option!(Fuzzy), Required("<text|glob>"), Actions, "List aliases (File + RAM) whose name or value contains <text>\nor matches a glob (* and ?)",
+        "Error searching aliases for")),
+    on_file(valued("--enable-group", text_option!(EnableGroup, lower), Required("<name>"), Actions, "Load every macro of the [name] section into RAM (file untouched)", "Error enabling alias group")),
+    on_file(valued("--disable-group", text_option!(DisableGroup, lower), Required("<name>"), Actions, "Drop them from RAM; stays disabled across --startup and --reload", "Error disabling alias group")),
+    on_file(valued("--expand-file", text_option!(ExpandFile), Optional("[path]"), Actions, "Print the file as a reload would load it (includes, ${...}, [if] guards)", "Error expanding alias file")),
+    on_file(OptionSpec { aliases: &["--edaliases"], ..valued("--edalias", EDIT, Attached("EDITOR"), Actions, "Open active file in editor (Path to editor optional)",
+        "Error editing alias file with") }),
+    on_file(flag("--reload", bare!(Reload), Actions, "Force re-sync of Win32 environment strings", "Error reloading configuration")),
+
+    flag("--setup", bare!(Setup), System, "Initialize Windows Registry AutoRun hooks", "Error setting up autorun registry entry"),
+    on_file(flag("--startup", bare!(Startup), System, "Execute boot-time hydration (via AutoRun)", "Error setting/using startup mode")),
+    flag("--clear", bare!(Clear), System, "Nuke all aliases in the active context", "Clear aliases error"),
+];
+
+/// The row for a switch as typed (any case, any spelling), and whether it was the `--no-` form.
+pub fn find_option(flag: &str) -> Option<(&'static OptionSpec, bool)> {
+    let flag = flag.to_lowercase();
+    let matches = |spec: &OptionSpec, name: &str| spec.long == name || spec.aliases.contains(&name);
+    if let Some(spec) = OPTIONS.iter().find(|spec| matches(spec, &flag)) {
+        return Some((spec, false));
+    }
+    let positive = format!("--{}", flag.strip_prefix("--no-")?);
+    OPTIONS.iter().find(|spec| spec.negatable && matches(spec, &positive)).map(|spec| (spec, true))
+}
+
+impl OptionSpec {
+    /// The switch as written on a command line.
+    pub fn flag(&self, negated: bool) -> String {
+        if negated { format!("--no-{}", &self.long[2..]) } else { self.long.to_string() }
+    }
+
+    /// The left column of --help.
+    pub fn usage(&self) -> String {
+        let on_off = if self.negatable { format!("{} / {}", self.long, self.flag(true)) } else { self.long.to_string() };
+        match self.value {
+            OptionValue::None => on_off,
+            OptionValue::Required(meta) | OptionValue::Optional(meta) => format!("{} {}", on_off, meta),
+            OptionValue::Attached(meta) => format!("{}[={}]", on_off, meta),
+        }
+    }
+}
+
+// Blank-separated, "..." groups (and may be empty); no escapes, so Windows paths pass as typed
+fn split_values(text: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let (mut quoted, mut started) = (false, false);
+    for c in text.chars() {
+        match c {
+            '"' => { quoted = !quoted; started = true; },
+            c if c.is_whitespace() && !quoted => {
+                if started { values.push(std::mem::take(&mut current)); }
+                started = false;
+            },
+            c => { current.push(c); started = true; },
+        }
+    }
+    if started { values.push(current); }
+    values
+}
+
+fn quote_value(value: &str) -> String {
+    if value.is_empty() || value.chars().any(char::is_whitespace) { format!("\"{}\"", value) } else { value.to_string() }
+}
+
+fn non_empty(value: &str) -> Vec<String> {
+    if value.is_empty() { Vec::new() } else { vec![value.to_string()] }
+}
+
+impl AliasAction {
+    /// The option row behind this action and whether it is the `--no-` side;
+    /// None for what is not a switch (name=value, a query, parser states).
+    pub fn option(&self) -> Option<(&'static OptionSpec, bool)> {
+        OPTIONS.iter().find_map(|spec| (spec.action.extract)(self).map(|(negated, _)| (spec, negated)))
+    }
+
+    /// The values that follow the switch, unquoted; a default that need not be written is left out.
+    pub fn option_values(&self) -> Vec<String> {
+        OPTIONS.iter().find_map(|spec| (spec.action.extract)(self)).map(|(_, values)| values).unwrap_or_default()
+    }
+
+    /// Builds the action for a row from the values that followed it. None when they do not fit.
+    pub fn from_option(spec: &OptionSpec, negated: bool, values: &[String]) -> Option<Self> {
+        (spec.action.build)(negated, values)
+    }
+
+    /// One bare instance of every switch (both sides of a toggle), in table order:
+    /// the list --completions is generated from.
+    pub fn catalog() -> Vec<Self> {
+        // No `_` arm on purpose: a new variant does not compile until it is sorted in or out here
+        fn user_facing(action: &AliasAction) -> bool {
+            match action {
+                AliasAction::Set(_) | AliasAction::Query(_) | AliasAction::Invalid
+                | AliasAction::Fail | AliasAction::Toggle(..) | AliasAction::Only(AuditStatus::Synced) => false,
+                AliasAction::Case | AliasAction::NoCase | AliasAction::Clear | AliasAction::Edit(_)
+                | AliasAction::File | AliasAction::Help | AliasAction::Icons | AliasAction::NoIcons
+                | AliasAction::License | AliasAction::Reload | AliasAction::Setup | AliasAction::ShowAll
+                | AliasAction::Startup | AliasAction::Temp | AliasAction::NoTemp | AliasAction::Tips
+                | AliasAction::NoTips | AliasAction::Quiet | AliasAction::NoQuiet | AliasAction::Remove(_)
+                | AliasAction::Unalias(_) | AliasAction::Version | AliasAction::VersionShort
+                | AliasAction::Which | AliasAction::Describe(_) | AliasAction::Export(_)
+                | AliasAction::Import(_) | AliasAction::Expand(..) | AliasAction::Format(_)
+                | AliasAction::Backups | AliasAction::Restore(_) | AliasAction::Undo(_)
+                | AliasAction::Redo | AliasAction::History(_) | AliasAction::Scope(_)
+                | AliasAction::Enter | AliasAction::Leave | AliasAction::Allow(_)
+                | AliasAction::ExpandFile(_) | AliasAction::EnableGroup(_) | AliasAction::DisableGroup(_)
+                | AliasAction::Group(_) | AliasAction::List(_) | AliasAction::Grep(_)
+                | AliasAction::Only(AuditStatus::Pending | AuditStatus::Phantom | AuditStatus::Desync | AuditStatus::Corrupt)
+                | AliasAction::Sort(_) | AliasAction::Apply(_) | AliasAction::Encoding(_)
+                | AliasAction::ConfigShow | AliasAction::ConfigSet(..) | AliasAction::Completions(_)
+                | AliasAction::Fuzzy(_) => true,
+            }
+        }
+        let catalog: Vec<Self> = OPTIONS.iter()
+            .flat_map(|spec| [Some(false), spec.negatable.then_some(true)].into_iter().flatten().map(move |negated| (spec, negated)))
+            .filter_map(|(spec, negated)| Self::from_option(spec, negated, &[]))
+            .collect();
+        debug_assert!(catalog.iter().all(user_facing));
+        catalog
+    }
+
+    pub(crate) fn render_option(&self) -> Option<String> {
+        let (spec, negated) = self.option()?;
+        let flag = spec.flag(negated);
+        let values = self.option_values();
+        Some(match (spec.value, values.first()) {
+            (_, None) => flag,
+            (OptionValue::Attached(_), Some(value)) => format!("{}={}", flag, quote_value(value)),
+            _ => std::iter::once(flag).chain(values.iter().map(|v| quote_value(v))).collect::<Vec<_>>().join(" "),
+        })
+    }
+
+    pub(crate) fn parse_option(text: &str) -> Self {
+        let head = text.split_whitespace().next().unwrap_or_default();
+        let (name, attached) = match head.split_once('=') {
+            Some((name, _)) => (name, text.split_once('=').map(|(_, rest)| rest)),
+            None => (head, None),
+        };
+        let Some((spec, negated)) = find_option(name) else { return Self::Invalid; };
+        let values = match (spec.value, attached) {
+            (OptionValue::None, Some(_)) => return Self::Invalid,
+            (_, Some(_)) if negated => return Self::Invalid,
+            // --edalias=code --wait: everything after `=` is the one value
+            (OptionValue::Attached(_), Some(rest)) => vec![rest.trim().trim_matches('"').to_string()],
+            (OptionValue::Attached(_), None) => Vec::new(),
+            (_, Some(rest)) => split_values(rest),
+            (_, None) => split_values(&text[head.len()..]),
+        };
+        Self::from_option(spec, negated, &values).unwrap_or(Self::Invalid)
+    }
+}
+
+/// The switch rows of --help, section by section.
+pub fn help_sections() -> String {
+    let mut out = String::new();
+    for section in HelpSection::ALL {
+        out.push_str(section.title());
+        out.push('\n');
+        for spec in OPTIONS.iter().filter(|spec| spec.section == section) {
+            let mut help = spec.help.lines();
+            let usage = spec.usage();
+            // Long usages keep one blank before the text rather than breaking the line
+            if usage.len() < 22 { out.push_str(&format!("  {:<22}{}\n", usage, help.next().unwrap_or_default())); }
+            else { out.push_str(&format!("  {} {}\n", usage, help.next().unwrap_or_default())); }
+            for more in help { out.push_str(&format!("  {:<22}{}\n", "", more)); }
+        }
+        if section == HelpSection::Actions {
+            out.push_str(&format!("  {:<22}{}\n", "--", "Stop processing flags (treat rest as name/value)"));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub struct AliasErrorString<'a>(pub(crate) &'a AliasAction);
+
+impl fmt::Display for AliasErrorString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = self.0;
+        match action {
+            AliasAction::Fail => write!(f, "General Error"),
+            AliasAction::Invalid => write!(f, "Unrecognized or malformed command"),
+            AliasAction::Query(name) => write!(f, "Error querying alias: {}", name),
+            AliasAction::Set(opts) => write!(f, "Error setting alias: {}", opts.name),
+            AliasAction::Toggle(from, to) => write!(f, "Error reverse mapping {} to {}", from, to),
+            AliasAction::Edit(None) => write!(f, "Error editing alias file with default editor"),
+            _ => {
+                let Some((spec, negated)) = action.option() else { return Ok(()); };
+                let context = if negated { spec.error_off } else { spec.error };
+                match action.option_values() {
+                    values if values.is_empty() => write!(f, "{}", context),
+                    values => write!(f, "{}: {}", context, values.join(" ")),
+                }
+            },
+        }
+    }
+}
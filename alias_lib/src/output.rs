@@ -0,0 +1,283 @@
+// alias_lib/src/output.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Machine-readable records for `--format json|tsv`. Text mode never comes through here.
+//
+// SCHEMA (v1)
+// JSON is JSON Lines: one object per line, every object starts with "kind".
+//   alias        name, status, win32, doskey, file, desc, tags, scope, shadows, defined, group
+//                scope is the layer the file value came from (team | user | project), null if none;
+//                shadows lists the lower layers it overrides; defined is <path>:<line>, null if unknown;
+//                group is the [section] of the file entry, null if ungrouped
+//                status is one of: synced | phantom | pending | desync | corrupt
+//                win32/doskey/file are null when that layer has no entry
+//   query        name, value
+//   entry        name, value, defined    (--expand-file: what a reload would inject)
+//   diagnostics  binary_path, resolved_path, env_file, env_opts, file_exists, is_readonly,
+//                drive_responsive, registry_status, registry_value, api_status
+//                registry_status is one of: uninitialized | synced | mismatch | not_found
+//   version      role (overall | component), lib, version, build, timestamp
+//   backup       index, stamp, entries, added, removed, changed, path
+//                index 1 is the newest; added/removed/changed describe what a restore would do
+//   history      id, stamp, op, action, layer, name, prev, new
+//                op is do | undo:<id> | redo:<id>; layer is file | ram; prev/new are null when absent
+//   setting      key, value, source
+//                source is `command line`, `env <VAR>`, `<alias.toml path>:<line>` or `default`
+// TSV carries the same records: a header line `kind<TAB>keys...` whenever the kind changes,
+// then one row per record in the key order above. null is an empty cell, lists are
+// comma-joined, and \t \n \r \\ are escaped.
+// Keys are append-only. New keys may appear at the end; existing keys keep their meaning.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Tsv,
+}
+
+impl OutputFormat {
+    pub fn is_machine(self) -> bool {
+        self != Self::Text
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" | "txt" => Ok(Self::Text),
+            "json" | "jsonl" => Ok(Self::Json),
+            "tsv" => Ok(Self::Tsv),
+            other => Err(format!("Unknown output format '{}' (json, tsv, text)", other)),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Json => write!(f, "json"),
+            Self::Tsv => write!(f, "tsv"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Null,
+    Str(String),
+    Bool(bool),
+    Num(u64),
+    List(Vec<String>),
+}
+
+impl From<&str> for FieldValue {
+    fn from(s: &str) -> Self { Self::Str(s.to_string()) }
+}
+impl From<String> for FieldValue {
+    fn from(s: String) -> Self { Self::Str(s) }
+}
+impl<T: Into<FieldValue>> From<Option<T>> for FieldValue {
+    fn from(v: Option<T>) -> Self { v.map_or(Self::Null, Into::into) }
+}
+impl From<bool> for FieldValue {
+    fn from(b: bool) -> Self { Self::Bool(b) }
+}
+impl From<u32> for FieldValue {
+    fn from(n: u32) -> Self { Self::Num(n as u64) }
+}
+impl From<u64> for FieldValue {
+    fn from(n: u64) -> Self { Self::Num(n) }
+}
+impl From<usize> for FieldValue {
+    fn from(n: usize) -> Self { Self::Num(n as u64) }
+}
+impl From<Vec<String>> for FieldValue {
+    fn from(list: Vec<String>) -> Self { Self::List(list) }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub kind: &'static str,
+    pub fields: Vec<(&'static str, FieldValue)>,
+}
+
+impl Record {
+    pub fn new(kind: &'static str) -> Self {
+        Self { kind, fields: Vec::new() }
+    }
+
+    pub fn with(mut self, key: &'static str, value: impl Into<FieldValue>) -> Self {
+        self.fields.push((key, value.into()));
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&FieldValue> {
+        self.fields.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = format!("{{\"kind\":{}", json_string(self.kind));
+        for (key, value) in &self.fields {
+            let rendered = match value {
+                FieldValue::Null => "null".to_string(),
+                FieldValue::Str(s) => json_string(s),
+                FieldValue::Bool(b) => b.to_string(),
+                FieldValue::Num(n) => n.to_string(),
+                FieldValue::List(items) => format!("[{}]", items.iter().map(|i| json_string(i)).collect::<Vec<_>>().join(",")),
+            };
+            out.push_str(&format!(",{}:{}", json_string(key), rendered));
+        }
+        out.push('}');
+        out
+    }
+
+    pub fn tsv_header(&self) -> String {
+        std::iter::once("kind").chain(self.fields.iter().map(|(k, _)| *k)).collect::<Vec<_>>().join("\t")
+    }
+
+    pub fn to_tsv(&self) -> String {
+        let cells = self.fields.iter().map(|(_, value)| match value {
+            FieldValue::Null => String::new(),
+            FieldValue::Str(s) => tsv_escape(s),
+            FieldValue::Bool(b) => b.to_string(),
+            FieldValue::Num(n) => n.to_string(),
+            FieldValue::List(items) => tsv_escape(&items.join(",")),
+        });
+        std::iter::once(self.kind.to_string()).chain(cells).collect::<Vec<_>>().join("\t")
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn tsv_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+/// Renders records for the requested format. Text yields nothing: text mode has its own printers.
+pub fn render_records(records: &[Record], format: OutputFormat) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut last_kind = "";
+    for record in records {
+        match format {
+            OutputFormat::Text => {},
+            OutputFormat::Json => lines.push(record.to_json()),
+            OutputFormat::Tsv => {
+                if record.kind != last_kind { lines.push(record.tsv_header()); }
+                lines.push(record.to_tsv());
+            },
+        }
+        last_kind = record.kind;
+    }
+    lines
+}
+
+// --- Record builders (one per schema kind) ---
+
+pub fn audit_record(entry: &AuditEntry) -> Record {
+    Record::new("alias")
+        .with("name", entry.name.as_str())
+        .with("status", entry.status.as_str())
+        .with("win32", entry.win32.clone())
+        .with("doskey", entry.doskey.clone())
+        .with("file", entry.file.clone())
+        .with("desc", entry.meta.as_ref().and_then(|m| m.desc.clone()))
+        .with("tags", entry.meta.as_ref().map(|m| m.tags.clone()).unwrap_or_default())
+        .with("scope", entry.origin.as_ref().map(|o| o.scope.as_str()))
+        .with("shadows", entry.origin.as_ref().map(|o| o.shadowed.iter().map(|(s, _)| s.to_string()).collect::<Vec<_>>()).unwrap_or_default())
+        .with("defined", entry.origin.as_ref().and_then(|o| o.source.as_ref()).map(|s| format!("{}:{}", s.path.display(), s.line)))
+        .with("group", entry.origin.as_ref().and_then(LayerOrigin::group))
+}
+
+pub fn query_record(name: &str, value: &str) -> Record {
+    Record::new("query").with("name", name).with("value", value)
+}
+
+pub fn entry_record(pair: &SourcedPair) -> Record {
+    Record::new("entry")
+        .with("name", pair.name.as_str())
+        .with("value", pair.value.as_str())
+        .with("defined", format!("{}:{}", pair.source.path.display(), pair.source.line))
+}
+
+pub fn setting_record(effective: &EffectiveSetting) -> Record {
+    Record::new("setting")
+        .with("key", effective.setting.key)
+        .with("value", effective.value.as_str())
+        .with("source", effective.source.to_string())
+}
+
+pub fn diagnostics_record(report: &DiagnosticReport) -> Record {
+    let (status, value) = match &report.registry_status {
+        RegistryStatus::Uninitialized => ("uninitialized", None),
+        RegistryStatus::Synced => ("synced", None),
+        RegistryStatus::Mismatch(v) => ("mismatch", Some(v.clone())),
+        RegistryStatus::NotFound => ("not_found", None),
+    };
+    Record::new("diagnostics")
+        .with("binary_path", report.binary_path.as_ref().map(|p| p.to_string_lossy().to_string()))
+        .with("resolved_path", report.resolved_path.to_string_lossy().to_string())
+        .with("env_file", report.env_file.as_str())
+        .with("env_opts", report.env_opts.as_str())
+        .with("file_exists", report.file_exists)
+        .with("is_readonly", report.is_readonly)
+        .with("drive_responsive", report.drive_responsive)
+        .with("registry_status", status)
+        .with("registry_value", value)
+        .with("api_status", report.api_status.clone())
+}
+
+pub fn backup_record(b: &BackupInfo) -> Record {
+    Record::new("backup")
+        .with("index", b.index)
+        .with("stamp", b.stamp.as_str())
+        .with("entries", b.entries)
+        .with("added", b.diff.added)
+        .with("removed", b.diff.removed)
+        .with("changed", b.diff.changed)
+        .with("path", b.path.to_string_lossy().to_string())
+}
+
+pub fn history_record(txn: &JournalTxn, change: &JournalChange) -> Record {
+    Record::new("history")
+        .with("id", txn.id)
+        .with("stamp", txn.stamp.as_str())
+        .with("op", txn.op.to_string())
+        .with("action", txn.action.as_str())
+        .with("layer", change.layer.as_str())
+        .with("name", change.name.as_str())
+        .with("prev", change.prev.clone())
+        .with("new", change.new.clone())
+}
+
+pub fn version_record(role: &str, v: &Versioning) -> Record {
+    Record::new("version")
+        .with("role", role)
+        .with("lib", v.lib)
+        .with("version", format!("{}.{}.{}", v.major, v.minor, v.patch))
+        .with("build", v.compile)
+        .with("timestamp", v.timestamp)
+}
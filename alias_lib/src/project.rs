@@ -0,0 +1,246 @@
+// alias_lib/src/project.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Project-local alias files, found like .editorconfig: the nearest .aliases.doskey
+// at or above the working directory. A cloned repo must never inject macros on its own,
+// so a project file only loads once `alias --allow` has recorded its content hash.
+// Any edit outside this tool changes the hash and the file is untrusted again.
+//
+// Both state files live next to the user alias file:
+//   .alias-trust     <sha256> <TAB> <project file>         one line per allowed project
+//   .alias-entered   <project> <TAB> <name> <TAB> <prev> <TAB> <applied>
+// --enter pushes one block of .alias-entered (the RAM values it shadowed, `\N` = none),
+// --leave pops the newest block and puts those values back.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use crate::*;
+
+pub const TRUST_FILE_NAME: &str = ".alias-trust";
+pub const ENTERED_FILE_NAME: &str = ".alias-entered";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustState {
+    Trusted,
+    Changed, // allowed once, edited since
+    Unknown,
+}
+
+/// Where the trust list and the enter stack for `user` live.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectState {
+    pub trust: PathBuf,
+    pub entered: PathBuf,
+}
+
+impl ProjectState {
+    pub fn for_user(user: &Path) -> Self {
+        let dir = user.parent().unwrap_or(Path::new("."));
+        Self { trust: dir.join(TRUST_FILE_NAME), entered: dir.join(ENTERED_FILE_NAME) }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnteredEntry {
+    pub project: PathBuf,
+    pub name: String,
+    pub prev: Option<String>,
+    pub applied: String,
+}
+
+/// The nearest project file at or above `start`.
+pub fn find_project_file(start: &Path) -> Option<PathBuf> {
+    start.ancestors().map(|dir| dir.join(PROJECT_ALIAS_FILENAME)).find(|p| p.is_file())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// sha256 over the file and everything it #includes, so an included file cannot be
+/// swapped out behind an allowed project either.
+pub fn project_hash(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(fs::read(path)?);
+    if let Ok(pairs) = expand_includes(path, &Verbosity::mute()) {
+        for p in pairs.iter().filter(|p| p.source.path != path) {
+            hasher.update(format!("{}={}\n", p.name, p.value).as_bytes());
+        }
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn read_trust(store: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    match fs::read_to_string(store) {
+        Ok(content) => Ok(content.lines()
+            .filter_map(|l| l.split_once('\t'))
+            .map(|(hash, path)| (hash.to_string(), PathBuf::from(path)))
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn trust_state(state: &ProjectState, project: &Path) -> TrustState {
+    let key = canonical(project);
+    let Some((hash, _)) = read_trust(&state.trust).unwrap_or_default().into_iter().find(|(_, p)| *p == key) else {
+        return TrustState::Unknown;
+    };
+    match project_hash(project) {
+        Ok(current) if current == hash => TrustState::Trusted,
+        _ => TrustState::Changed,
+    }
+}
+
+fn record_trust(verbosity: &Verbosity, state: &ProjectState, project: &Path, hash: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let key = canonical(project);
+    let _lock = lock_alias_file(verbosity, &state.trust)?;
+    let mut entries = read_trust(&state.trust).map_err(|e| failure!(verbosity, e))?;
+    entries.retain(|(_, p)| *p != key);
+    entries.push((hash.to_string(), key));
+    let content: String = entries.iter().map(|(h, p)| format!("{}\t{}\n", h, p.display())).collect();
+    fs::write(&state.trust, content).map_err(|e| failure!(verbosity, e))?;
+    Ok(())
+}
+
+/// `alias --allow`: trust the project file as it is right now. Returns the recorded hash.
+pub fn allow_project(verbosity: &Verbosity, state: &ProjectState, project: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let hash = project_hash(project).map_err(|e| failure!(verbosity, e))?;
+    record_trust(verbosity, state, project, &hash)?;
+    Ok(hash)
+}
+
+/// Writes made by this tool keep a project trusted: a file that was trusted before the
+/// write (or did not exist yet) is re-recorded with its new hash.
+pub fn retrust_after_write(verbosity: &Verbosity, state: &ProjectState, project: &Path, before: Option<&str>) {
+    let key = canonical(project);
+    let known = read_trust(&state.trust).unwrap_or_default().into_iter().find(|(_, p)| *p == key).map(|(h, _)| h);
+    let keep = match (before, known) {
+        (None, _) => true,
+        (Some(before), Some(known)) => before == known,
+        (Some(_), None) => false,
+    };
+    if keep && let Err(e) = allow_project(verbosity, state, project) {
+        shout!(verbosity, AliasIcon::Alert, "Could not update trust for {}: {}", project.display(), e);
+    }
+}
+
+/// Refuses anything but a trusted project, with the hint the user needs.
+pub fn require_trust(verbosity: &Verbosity, state: &ProjectState, project: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    match trust_state(state, project) {
+        TrustState::Trusted => Ok(()),
+        TrustState::Changed => Err(failure!(verbosity, ErrorCode::AccessDenied,
+            "{} changed since it was allowed. Review it, then run: alias --allow", project.display())),
+        TrustState::Unknown => Err(failure!(verbosity, ErrorCode::AccessDenied,
+            "{} is not trusted. Review it, then run: alias --allow", project.display())),
+    }
+}
+
+fn escape(value: Option<&str>) -> String {
+    match value {
+        None => "\\N".to_string(),
+        Some(v) => v.replace('\\', "\\\\").replace('\t', "\\t"),
+    }
+}
+
+fn unescape(cell: &str) -> Option<String> {
+    if cell == "\\N" { return None; }
+    Some(cell.replace("\\t", "\t").replace("\\\\", "\\"))
+}
+
+pub fn load_entered(state: &ProjectState) -> io::Result<Vec<EnteredEntry>> {
+    let content = match fs::read_to_string(&state.entered) {
+        Ok(c) => c,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(content.lines().filter_map(|line| {
+        let cells: Vec<&str> = line.split('\t').collect();
+        let [project, name, prev, applied] = cells[..] else { return None; };
+        Some(EnteredEntry {
+            project: PathBuf::from(project),
+            name: unescape(name)?,
+            prev: unescape(prev),
+            applied: unescape(applied)?,
+        })
+    }).collect())
+}
+
+fn save_entered(state: &ProjectState, entries: &[EnteredEntry]) -> io::Result<()> {
+    if entries.is_empty() {
+        return match fs::remove_file(&state.entered) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let mut file = fs::File::create(&state.entered)?;
+    for e in entries {
+        writeln!(file, "{}\t{}\t{}\t{}", e.project.display(), escape(Some(&e.name)), escape(e.prev.as_deref()), escape(Some(&e.applied)))?;
+    }
+    Ok(())
+}
+
+/// Loads a trusted project file on top of RAM and remembers what it shadowed.
+/// Returns the number of macros applied; 0 when the project is already entered.
+pub fn enter_project(provider: &dyn AliasProvider, verbosity: &Verbosity, state: &ProjectState, project: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+    require_trust(verbosity, state, project)?;
+    let key = canonical(project);
+    let _lock = lock_alias_file(verbosity, &state.entered)?;
+    let mut entered = load_entered(state).map_err(|e| failure!(verbosity, e))?;
+    if entered.iter().any(|e| e.project == key) {
+        say!(verbosity, AliasIcon::Info, "Already entered {}", project.display());
+        return Ok(0);
+    }
+
+    // Last definition wins inside the file, like any doskey reload
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for (name, value) in parse_macro_file(project, verbosity)? {
+        pairs.retain(|(n, _)| !n.eq_ignore_ascii_case(&name));
+        pairs.push((name, value));
+    }
+    let ram = provider.get_all_aliases(verbosity)?;
+    let before = entered.len();
+    for (name, value) in &pairs {
+        let prev = ram.iter().rev().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.clone());
+        if let Err(e) = inject_macro(provider, name, Some(value)) {
+            // No record means --leave could not restore them: put back what was already applied
+            for applied in entered[before..].iter().rev() {
+                if let Err(e) = inject_macro(provider, &applied.name, applied.prev.as_deref()) {
+                    shout!(verbosity, AliasIcon::Alert, "Rollback could not restore '{}' in RAM: {}", applied.name, e);
+                }
+            }
+            return Err(failure!(verbosity, ErrorCode::Generic, "RAM refused '{}': {}. {} not entered.", name, e, project.display()));
+        }
+        entered.push(EnteredEntry { project: key.clone(), name: name.clone(), prev, applied: value.clone() });
+    }
+    if let Err(e) = save_entered(state, &entered) {
+        for applied in entered[before..].iter().rev() {
+            let _ = inject_macro(provider, &applied.name, applied.prev.as_deref());
+        }
+        return Err(failure!(verbosity, e));
+    }
+    say!(verbosity, AliasIcon::Success, "Entered {}: {} macros loaded", project.display(), pairs.len());
+    Ok(pairs.len())
+}
+
+/// Undoes the newest --enter. Returns the project left, or None when nothing was entered.
+pub fn leave_project(provider: &dyn AliasProvider, verbosity: &Verbosity, state: &ProjectState) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    let _lock = lock_alias_file(verbosity, &state.entered)?;
+    let mut entered = load_entered(state).map_err(|e| failure!(verbosity, e))?;
+    let Some(project) = entered.last().map(|e| e.project.clone()) else { return Ok(None); };
+
+    let ram = provider.get_all_aliases(verbosity)?;
+    for entry in entered.iter().rev().filter(|e| e.project == project) {
+        let current = ram.iter().rev().find(|(n, _)| n.eq_ignore_ascii_case(&entry.name)).map(|(_, v)| v.as_str());
+        if current != Some(entry.applied.as_str()) {
+            shout!(verbosity, AliasIcon::Alert, "'{}' changed since --enter; restoring the pre-enter value anyway", entry.name);
+        }
+        inject_macro(provider, &entry.name, entry.prev.as_deref()).map_err(|e| failure!(verbosity, e))?;
+    }
+    entered.retain(|e| e.project != project);
+    save_entered(state, &entered).map_err(|e| failure!(verbosity, e))?;
+    say!(verbosity, AliasIcon::Success, "Left {}", project.display());
+    Ok(Some(project))
+}
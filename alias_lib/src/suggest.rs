@@ -0,0 +1,121 @@
+// alias_lib/src/suggest.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// "Did you mean" for typos, and the matching behind `--fuzzy`.
+// Typos are ranked by optimal string alignment distance (Levenshtein plus adjacent swaps,
+// so --relaod is one edit from --reload), ignoring case. A candidate is offered when it is
+// within a third of the typed length (at least 1, at most 3 edits).
+// Patterns with * or ? are globs over the whole name (or value); anything else is a
+// case-insensitive substring.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::*;
+
+const MAX_SUGGESTIONS: usize = 3;
+
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1).min(row[j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Up to three candidates close to `typed`, nearest first. An exact match is not a suggestion.
+pub fn closest<'a>(typed: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let limit = (typed.trim_start_matches('-').chars().count() / 3).clamp(1, 3);
+    let mut ranked: Vec<(usize, &str)> = candidates.into_iter()
+        .filter(|c| !c.eq_ignore_ascii_case(typed))
+        .map(|c| (edit_distance(typed, c), c))
+        .filter(|(d, _)| *d <= limit)
+        .collect();
+    ranked.sort();
+    ranked.dedup_by(|a, b| a.1.eq_ignore_ascii_case(b.1));
+    ranked.into_iter().take(MAX_SUGGESTIONS).map(|(_, c)| c).collect()
+}
+
+/// ` Did you mean a, b?` (with the leading blank), or nothing.
+pub fn did_you_mean(suggestions: &[&str]) -> String {
+    if suggestions.is_empty() { String::new() } else { format!(" Did you mean {}?", suggestions.join(", ")) }
+}
+
+/// Every spelling the parser accepts, `--no-` forms included.
+pub fn known_flags() -> Vec<String> {
+    OPTIONS.iter().flat_map(|spec| {
+        let mut flags: Vec<String> = std::iter::once(spec.long).chain(spec.aliases.iter().copied()).map(str::to_string).collect();
+        if spec.negatable { flags.push(spec.flag(true)); }
+        flags
+    }).collect()
+}
+
+/// Hint for an unknown switch (`--relaod`, `--formt=json`).
+pub fn flag_hint(arg: &str) -> String {
+    let typed = arg.split('=').next().unwrap_or(arg);
+    let flags = known_flags();
+    did_you_mean(&closest(typed, flags.iter().map(String::as_str)))
+}
+
+/// Alias names in RAM and in the file, RAM's value winning. Either side may be unavailable.
+pub fn known_aliases(provider: &dyn AliasProvider, verbosity: &Verbosity, path: &Path) -> Vec<(String, String)> {
+    let mut merged: BTreeMap<String, (String, String)> = BTreeMap::new();
+    if path.is_file() && let Ok(pairs) = parse_macro_file(path, &Verbosity::silent()) {
+        for (name, value) in pairs { merged.insert(name.to_lowercase(), (name, value)); }
+    }
+    for (name, value) in provider.get_all_aliases(verbosity).unwrap_or_default() {
+        merged.insert(name.to_lowercase(), (name, value));
+    }
+    merged.into_values().collect()
+}
+
+/// Hint for a name that is in neither RAM nor the file.
+pub fn name_hint(typed: &str, known: &[(String, String)]) -> String {
+    did_you_mean(&closest(typed, known.iter().map(|(n, _)| n.as_str())))
+}
+
+/// `*` is any run of characters, `?` any one; the whole text must match. Case-insensitive.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.to_lowercase().chars().collect();
+    let t: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None; // (after the last *, text position it covers up to)
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            backtrack = Some((pi + 1, ti));
+            pi += 1;
+        } else if let Some((star, covered)) = backtrack {
+            pi = star;
+            ti = covered + 1;
+            backtrack = Some((star, covered + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// The `--fuzzy` test: the name or the value contains `pattern`, or matches it as a glob.
+pub fn fuzzy_match(pattern: &str, name: &str, value: &str) -> bool {
+    if is_glob(pattern) {
+        return glob_match(pattern, name) || glob_match(pattern, value);
+    }
+    let needle = pattern.to_lowercase();
+    name.to_lowercase().contains(&needle) || value.to_lowercase().contains(&needle)
+}
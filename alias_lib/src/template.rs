@@ -0,0 +1,146 @@
+// alias_lib/src/template.rs
+// Licensed under PolyForm Noncommercial 1.0.0 via alias_lib.
+//
+// Load-time templating for shared alias files.
+//
+// PLACEHOLDERS (values and #include targets only, never names)
+//   ${env:NAME}   the environment variable; left as written (with a warning) when unset
+//   ${alias_dir}  directory of the file the line sits in
+//   ${home}       USERPROFILE, else HOME
+// Only `${` starts a placeholder, so doskey's own $1..$9, $*, $T, $G... are never touched.
+// `$$` is doskey's literal dollar and is skipped as a pair: `$${home}` stays verbatim.
+// Placeholders are opt-in: ALIAS_EXPAND=on (or `expand = true` in alias.toml). Existing values may
+// hold PowerShell's own ${env:...}, which must not be rewritten behind the user's back.
+// Without it values load verbatim; guards always apply.
+//
+// GUARDS
+//   [if host=BUILD01]            COMPUTERNAME (or HOSTNAME), case-insensitive
+//   [if exists=C:\tools\ncd.exe] placeholders allowed in the path
+//   [if env=CI]                  the variable is set and not empty
+//   [if host!=BUILD01]           any test can be negated with !=
+//   [end]                        closes the innermost [if]; guards nest and end with their file
+// Guard lines are comments to this tool's document model, but plain doskey /macrofile
+// would load them as junk macros, so files using them are flattened for doskey.exe.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardTest {
+    Host,
+    Exists,
+    Env,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Guard {
+    pub test: GuardTest,
+    pub arg: String,
+    pub negated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardLine {
+    If(Guard),
+    End,
+    Malformed(String),
+}
+
+pub fn templates_enabled() -> bool {
+    match env::var(ENV_ALIAS_EXPAND) {
+        Ok(v) => matches!(v.trim().to_lowercase().as_str(), "1" | "on" | "true" | "yes"),
+        Err(_) => config::config_value("expand").is_some_and(|v| v == "true"),
+    }
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    env::var_os("USERPROFILE").or_else(|| env::var_os("HOME")).filter(|h| !h.is_empty()).map(PathBuf::from)
+}
+
+pub fn host_name() -> Option<String> {
+    env::var("COMPUTERNAME").or_else(|_| env::var("HOSTNAME")).ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+}
+
+/// Guard headers and [end]. Any other bracketed line is left alone.
+pub fn parse_guard(line: &str) -> Option<GuardLine> {
+    let inner = line.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+    if inner.eq_ignore_ascii_case("end") || inner.eq_ignore_ascii_case("endif") {
+        return Some(GuardLine::End);
+    }
+    let keyword = inner.get(..2).filter(|k| k.eq_ignore_ascii_case("if"))?;
+    let rest = inner[keyword.len()..].strip_prefix(char::is_whitespace)?.trim();
+    let Some((key, arg)) = rest.split_once('=') else {
+        return Some(GuardLine::Malformed(format!("expected <test>=<value>, got '{}'", rest)));
+    };
+    let (key, negated) = match key.trim().strip_suffix('!') {
+        Some(k) => (k.trim(), true),
+        None => (key.trim(), false),
+    };
+    let test = match key.to_lowercase().as_str() {
+        "host" => GuardTest::Host,
+        "exists" => GuardTest::Exists,
+        "env" => GuardTest::Env,
+        other => return Some(GuardLine::Malformed(format!("unknown test '{}' (host, exists, env)", other))),
+    };
+    Some(GuardLine::If(Guard { test, arg: arg.trim().trim_matches('"').to_string(), negated }))
+}
+
+impl Guard {
+    pub fn holds(&self, alias_dir: &Path) -> bool {
+        let result = match self.test {
+            GuardTest::Host => host_name().is_some_and(|h| h.eq_ignore_ascii_case(&self.arg)),
+            GuardTest::Exists => Path::new(&expand_placeholders(&self.arg, alias_dir).0).exists(),
+            GuardTest::Env => env::var(&self.arg).is_ok_and(|v| !v.is_empty()),
+        };
+        result != self.negated
+    }
+}
+
+/// Resolves the placeholders in `value`. Returns the text and every placeholder left unresolved.
+pub fn expand_placeholders(value: &str, alias_dir: &Path) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(value.len());
+    let mut unresolved = Vec::new();
+    let mut rest = value;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        if let Some(after) = tail.strip_prefix("$$") {
+            out.push_str("$$");
+            rest = after;
+            continue;
+        }
+        let Some(end) = tail.strip_prefix("${").and_then(|t| t.find('}')) else {
+            // $1, $*, $T and friends belong to doskey
+            out.push('$');
+            rest = &tail[1..];
+            continue;
+        };
+        let key = &tail[2..2 + end];
+        let resolved = match key.split_once(':') {
+            Some((kind, name)) if kind.eq_ignore_ascii_case("env") => env::var(name).ok(),
+            None if key.eq_ignore_ascii_case("alias_dir") => Some(alias_dir.display().to_string()),
+            None if key.eq_ignore_ascii_case("home") => home_dir().map(|h| h.display().to_string()),
+            _ => None,
+        };
+        let placeholder = &tail[..3 + end];
+        match resolved {
+            Some(text) => out.push_str(&text),
+            None => {
+                out.push_str(placeholder);
+                unresolved.push(placeholder.to_string());
+            },
+        }
+        rest = &tail[3 + end..];
+    }
+    out.push_str(rest);
+    (out, unresolved)
+}
+
+/// True when a file needs this module (and so a flattened copy for doskey.exe).
+pub fn has_template_syntax(content: &str) -> bool {
+    content.lines().any(|line| parse_guard(line).is_some() || (line.contains("${") && is_data_line(line).is_some()))
+}
@@ -0,0 +1,12 @@
+# Generated by alias --export bash
+gs() { git status; }
+gco() { git checkout "$@"; }
+mkcd() { mkdir "$1" ; cd "$1"; }
+logs() { type app.log | more; }
+save() { dir > listing.txt; }
+append() { echo done >> log.txt; }
+feed() { sort < names.txt; }
+cost() { echo \$5; }
+ls() { command ls --color "$@"; }
+# skipped tmp: cmd variable %TEMP% has no portable equivalent
+# skipped bad: unsupported doskey token '$Q'
@@ -0,0 +1,12 @@
+# Generated by alias --export fish
+function gs; git status; end
+function gco; git checkout $argv; end
+function mkcd; mkdir $argv[1] ; cd $argv[1]; end
+function logs; type app.log | more; end
+function save; dir > listing.txt; end
+function append; echo done >> log.txt; end
+function feed; sort < names.txt; end
+function cost; echo \$5; end
+function ls; command ls --color $argv; end
+# skipped tmp: cmd variable %TEMP% has no portable equivalent
+# skipped bad: unsupported doskey token '$Q'
@@ -0,0 +1,12 @@
+# Generated by alias --export powershell
+function gs { git status }
+function gco { git checkout @args }
+function mkcd { mkdir $args[0] ; cd $args[0] }
+function logs { type app.log | more }
+function save { dir > listing.txt }
+function append { echo done >> log.txt }
+# skipped feed: PowerShell has no '<' input redirection ($L)
+function cost { echo `$5 }
+# skipped ls: calls itself; a PowerShell function would recurse
+# skipped tmp: cmd variable %TEMP% has no portable equivalent
+# skipped bad: unsupported doskey token '$Q'
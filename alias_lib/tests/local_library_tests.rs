@@ -16,6 +16,7 @@ mod test_suite_shared;
 use test_suite_shared::{MockProvider, MOCK_RAM, LAST_CALL, global_test_setup};
 
 #[path = "../../tests/state_restoration.rs"]
+#[cfg(windows)]
 mod stateful;
 
 // shared code end
@@ -25,13 +26,16 @@ fn local_library_tests_init() {
     eprintln!("[PRE-FLIGHT] Warning: System state is starting.");
     // FORCE LINKAGE: This prevents the linker from tree-shaking the module
     // and silences the "unused" warnings by actually "using" them.
-    let _ = stateful::has_backup();
-    if stateful::is_stale() {
-        // This path probably won't be hit, but the compiler doesn't know that.
-        eprintln!("[PRE-FLIGHT] Warning: System state is stale.");
+    #[cfg(windows)]
+    {
+        let _ = stateful::has_backup();
+        if stateful::is_stale() {
+            // This path probably won't be hit, but the compiler doesn't know that.
+            eprintln!("[PRE-FLIGHT] Warning: System state is stale.");
+        }
+        let _ = stateful::has_backup();
+        stateful::pre_flight_inc();
     }
-    let _ = stateful::has_backup();
-    stateful::pre_flight_inc();
     global_test_setup();
 }
 
@@ -39,6 +43,7 @@ fn local_library_tests_init() {
 #[ctor::dtor]
 fn local_library_tests_end() {
     eprintln!("[POST-FLIGHT] Warning: System state is finished.");
+    #[cfg(windows)]
     stateful::post_flight_dec();
 }
 
@@ -421,6 +426,7 @@ mod argument_tests {
         let task = Task {
             action: AliasAction::Set(opts_case),
             path: test_path.clone(),
+            args: Vec::new(),
         };
 
         // Dispatch using your Provider (e.g., Win32Provider or MockProvider)
@@ -439,6 +445,7 @@ mod argument_tests {
         let task_no_case = Task {
             action: AliasAction::Set(opts_no_case),
             path: test_path,
+            args: Vec::new(),
         };
 
         let result_no_case = dispatch::<MockProvider>(task_no_case, &verbosity);
@@ -450,6 +457,7 @@ mod existence_checks {
     use super::*;
     #[cfg(test)]
     mod path_logic_tests {
+        #[cfg_attr(not(windows), allow(unused_imports))]
         use alias_lib::{can_path_exist, is_drive_responsive, is_file_accessible, is_path_healthy, resolve_viable_path, timeout_guard};
         use super::*;
         // --- timeout_guard tests ---
@@ -498,6 +506,7 @@ mod existence_checks {
         }
 
         // --- is_file_accessible tests ---
+        #[cfg(windows)]
         #[test]
         fn test_file_accessible_non_existent() {
             let path = PathBuf::from("z:/definitely_not_a_real_file_123.txt");
@@ -684,7 +693,7 @@ mod parse_and_action {
     fn t25_double_dash() { if let AliasAction::Query(n) = parse_arguments(&to_args(vec!["alias", "--", "--quiet"])).0.pull().unwrap().action { assert_eq!(n, "--quiet"); } else { panic!(); } }
 }
 mod round_trip_tests {
-    use alias_lib::AliasAction;
+    use alias_lib::{parse_arguments, AliasAction, AuditStatus, SetOptions, ShowFeature, ShowTips};
     use super::*;
 
     #[test]
@@ -707,6 +716,37 @@ mod round_trip_tests {
             AliasAction::NoTips,   // New
             AliasAction::Edit(None),
             AliasAction::Query("my_alias".into()),
+            AliasAction::Describe(SetOptions { name: "gs".into(), value: "git status".into(), volatile: false, force_case: false }),
+            AliasAction::Export("fish".into()),
+            AliasAction::Import(alias_lib::ImportOptions {
+                source: "aliases.sh".into(),
+                from: Some(alias_lib::ImportFormat::Zsh),
+                prefer: alias_lib::ImportPolicy::Incoming,
+            }),
+            AliasAction::Expand("ncd".into(), vec!["..".into(), "-v".into()]),
+            AliasAction::Backups,
+            AliasAction::Restore("2".into()),
+            AliasAction::Undo(3),
+            AliasAction::Redo,
+            AliasAction::History("gs".into()),
+            AliasAction::Scope("project".into()),
+            AliasAction::Enter,
+            AliasAction::Leave,
+            AliasAction::Allow("proj/.aliases.doskey".into()),
+            AliasAction::ExpandFile("shared/aliases.doskey".into()),
+            AliasAction::EnableGroup("git".into()),
+            AliasAction::DisableGroup("docker".into()),
+            AliasAction::Group("git".into()),
+            AliasAction::Apply("changes.txt".into()),
+            AliasAction::Encoding("utf16le".into()),
+            AliasAction::ConfigShow,
+            AliasAction::ConfigSet("editor".into(), "code --wait".into()),
+            AliasAction::Completions("zsh".into()),
+            AliasAction::Fuzzy("*git*".into()),
+            AliasAction::List("git*".into()),
+            AliasAction::Grep("^git (log|push)".into()),
+            AliasAction::Only(AuditStatus::Desync),
+            AliasAction::Sort("length".into()),
         ];
 
         for original in test_cases {
@@ -718,6 +758,125 @@ mod round_trip_tests {
             assert_eq!(original, parsed, "Round-trip failed for: {}", cli);
         }
     }
+
+    // Every variant, crossed with awkward values: blanks, case, Windows paths, empty strings
+    fn every_action() -> Vec<AliasAction> {
+        use alias_lib::{ImportFormat, ImportOptions, ImportPolicy};
+        let texts = ["", "x", "two words", r"C:\My Files\a.doskey", "MiXeD", "-"];
+        let lower = ["", "json", "utf8-bom", "git"];
+        let owned = |list: &[&str]| list.iter().map(|t| t.to_string()).collect::<Vec<String>>();
+
+        let mut actions = AliasAction::catalog();
+        actions.extend([AliasAction::Invalid, AliasAction::ConfigShow, AliasAction::Query("gs".into())]);
+        for text in owned(&texts) {
+            actions.extend([
+                AliasAction::Restore(text.clone()),
+                AliasAction::History(text.clone()),
+                AliasAction::Allow(text.clone()),
+                AliasAction::ExpandFile(text.clone()),
+                AliasAction::Apply(text.clone()),
+                AliasAction::Fuzzy(text.clone()),
+                AliasAction::List(text.clone()),
+                AliasAction::Grep(text.clone()),
+                AliasAction::ConfigSet("editor".into(), text.clone()),
+                AliasAction::Describe(SetOptions { name: "gs".into(), value: text.clone(), volatile: false, force_case: false }),
+            ]);
+            if !text.is_empty() {
+                actions.push(AliasAction::Edit(Some(text.clone())));
+                actions.push(AliasAction::Import(ImportOptions { source: text.clone().into(), from: None, prefer: ImportPolicy::default() }));
+            }
+        }
+        for text in owned(&lower) {
+            actions.extend([
+                AliasAction::Export(text.clone()),
+                AliasAction::Format(text.clone()),
+                AliasAction::Scope(text.clone()),
+                AliasAction::Encoding(text.clone()),
+                AliasAction::Completions(text.clone()),
+                AliasAction::EnableGroup(text.clone()),
+                AliasAction::DisableGroup(text.clone()),
+                AliasAction::Group(text.clone()),
+                AliasAction::Sort(text.clone()),
+            ]);
+        }
+        for name in ["gs", "My_Alias"] {
+            actions.push(AliasAction::Remove(SetOptions::involatile(name.to_string(), false)));
+            actions.push(AliasAction::Unalias(SetOptions::volatile(name.to_string(), false)));
+            actions.push(AliasAction::Expand(name.to_string(), owned(&["..", "-v", "two words"])));
+            for value in ["", "git status -sb", "echo %1 $* > nul"] {
+                for (volatile, force_case) in [(false, false), (true, false), (false, true), (true, true)] {
+                    actions.push(AliasAction::Set(SetOptions { name: name.to_string(), value: value.to_string(), volatile, force_case }));
+                }
+            }
+        }
+        for from in [None, Some(ImportFormat::Zsh), Some(ImportFormat::DoskeyMacros)] {
+            for prefer in [ImportPolicy::Existing, ImportPolicy::Incoming] {
+                actions.push(AliasAction::Import(ImportOptions { source: r"C:\Users\me\.bashrc".into(), from, prefer }));
+            }
+        }
+        actions.extend((1..5).map(AliasAction::Undo));
+        actions.extend([true, false].map(|state| AliasAction::Toggle(Box::new(AliasAction::Query("gs".into())), state)));
+        actions
+    }
+
+    #[test]
+    fn test_parse_of_render_is_identity_for_every_variant() {
+        let actions = every_action();
+        // Fail is a parser state: it renders to nothing and is never typed
+        for action in &actions {
+            let cli = action.to_cli_args();
+            let parsed: AliasAction = cli.parse().unwrap();
+            assert_eq!(&parsed, action, "parse(render) changed the action; rendered as: {}", cli);
+        }
+        for spec in alias_lib::OPTIONS {
+            assert!(actions.iter().any(|a| a.option().is_some_and(|(s, _)| s.long == spec.long)), "{} is never exercised", spec.long);
+        }
+    }
+
+    #[test]
+    fn test_every_row_reads_back_what_it_builds() {
+        for spec in alias_lib::OPTIONS {
+            for negated in [false, true].into_iter().filter(|n| !n || spec.negatable) {
+                let action = AliasAction::from_option(spec, negated, &[]).unwrap();
+                let (owner, side) = action.option().unwrap();
+                assert_eq!((owner.long, side), (spec.long, negated), "{:?} belongs to another row", action);
+            }
+        }
+        assert_eq!(AliasAction::Only(alias_lib::AuditStatus::Synced).option().map(|(s, _)| s.long), None, "Synced has no switch");
+    }
+
+    #[test]
+    fn test_display_and_cli_args_agree() {
+        for action in every_action().iter().filter(|a| **a != AliasAction::Invalid) {
+            assert_eq!(action.to_string(), action.to_cli_args());
+        }
+        assert_eq!(AliasAction::Edit(Some("notepad".into())).to_string(), "--edalias=notepad");
+        assert_eq!(AliasAction::Edit(Some("code --wait".into())).to_cli_args(), "--edalias=\"code --wait\"");
+        assert_eq!("--EDALIASES=code --wait".parse::<AliasAction>().unwrap(), AliasAction::Edit(Some("code --wait".into())), "A raw argv keeps its blanks");
+    }
+
+    #[test]
+    fn test_option_table_spellings() {
+        assert_eq!("--no-remove".parse::<AliasAction>().unwrap(), AliasAction::Invalid, "Only toggles negate");
+        assert_eq!("--no-format=json".parse::<AliasAction>().unwrap(), AliasAction::Invalid);
+        assert_eq!("--clear=1".parse::<AliasAction>().unwrap(), AliasAction::Invalid, "A plain switch takes no value");
+        assert_eq!("--undo=x".parse::<AliasAction>().unwrap(), AliasAction::Invalid);
+        assert_eq!("--No-Tips".parse::<AliasAction>().unwrap(), AliasAction::NoTips);
+        assert!(AliasAction::Remove(SetOptions::empty(false)).requires_file());
+        assert!(!AliasAction::Unalias(SetOptions::empty(true)).requires_file());
+        assert_eq!(AliasAction::NoTemp.error().to_string(), "Error setting/using process as dual (mem/disk)");
+        assert_eq!(AliasAction::Export("fish".into()).error().to_string(), "Error exporting aliases as: fish");
+    }
+
+    #[test]
+    fn test_tips_switches_drive_the_tip_not_the_icons() {
+        let (_, voice) = parse_arguments(&["alias", "--no-icons", "--tips"].map(String::from));
+        assert_eq!(voice.show_icons, ShowFeature::Off, "--tips must leave the icons alone");
+        assert_eq!(voice.show_tips, ShowTips::Random);
+        let (_, voice) = parse_arguments(&["alias", "--no-tips"].map(String::from));
+        assert_eq!(voice.show_tips, ShowTips::Off);
+        assert_eq!(voice.display_tip, None, "--no-tips must drop the tip already drawn");
+    }
 }
 
 // =========================================================
@@ -796,7 +955,7 @@ mod data_and_audit {
         assert_eq!(m[0].os_value, Some("o".into()));
     }
     #[test]
-    fn t43_empty_def() { assert!(AliasEntryMesh { name: "x".into(), os_value: None, file_value: None }.is_empty_definition()); }
+    fn t43_empty_def() { assert!(AliasEntryMesh { name: "x".into(), os_value: None, file_value: None, meta: None, origin: None }.is_empty_definition()); }
     #[test]
     fn t44_valid_unicode() { assert!(is_valid_name("ñ")); }
     #[test]
@@ -831,6 +990,7 @@ mod data_and_audit {
     }
     #[test]
     fn t56_line_filter() { assert_eq!("a=1\n\nb=2\n#".lines().filter(|l| l.contains('=')).count(), 2); }
+    #[cfg(windows)]
     #[test] fn t60_nuke() { alias_nuke::kernel_wipe_macros(); }
     #[test]
     fn t53_env_splice() {
@@ -894,6 +1054,7 @@ mod dispatch_and_integration {
         let task = Task {
             action: AliasAction::Unalias("r=c ".into()),
             path: PathBuf::from("f"),
+            args: Vec::new(),
         };
 
         // Dispatch now takes the Task
@@ -910,6 +1071,7 @@ mod dispatch_and_integration {
         let task = Task {
             action: AliasAction::Remove(SetOptions::involatile("ls".to_string(), false)),
             path: PathBuf::from("f"),
+            args: Vec::new(),
         };
 
         dispatch::<MockProvider>(task, &Verbosity::silent()).unwrap();
@@ -932,6 +1094,7 @@ mod dispatch_and_integration {
                 force_case: false,
             }),
             path: PathBuf::from("f"),
+            args: Vec::new(),
         };
         dispatch::<MockProvider>(task, &Verbosity::silent()).unwrap();
 
@@ -953,6 +1116,7 @@ mod dispatch_and_integration {
         let task = Task {
             action: AliasAction::Remove(opts),
             path: PathBuf::from("f"),
+            args: Vec::new(),
         };
 
         dispatch::<MockProvider>(task, &Verbosity::silent()).unwrap();
@@ -1218,3 +1382,2349 @@ mod parse_set_args {
 
 }
 
+
+// =========================================================
+// SECTION 6: IN-MEMORY PROVIDER (End to End, any OS)
+// =========================================================
+#[cfg(test)]
+mod memory_provider_tests {
+    use alias_lib::{run_with, AliasProvider, MemoryProvider, ProviderType, Verbosity};
+    use tempfile::tempdir;
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_memory_raw_set_is_case_insensitive() {
+        let mem = MemoryProvider::new();
+        mem.raw_set_macro("GS", Some("git status")).unwrap();
+        mem.raw_set_macro("gs", Some("git status -sb")).unwrap();
+
+        let all = mem.get_all_aliases(&Verbosity::mute()).unwrap();
+        assert_eq!(all, vec![("gs".to_string(), "git status -sb".to_string())]);
+
+        mem.raw_set_macro("Gs", None).unwrap();
+        assert!(mem.get_all_aliases(&Verbosity::mute()).unwrap().is_empty());
+        assert_eq!(mem.provider_type(), ProviderType::Memory);
+    }
+
+    #[test]
+    fn test_memory_run_set_hits_ram_and_disk() {
+        let mem = MemoryProvider::new();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mem.doskey");
+        fs::write(&path, "").unwrap();
+
+        run_with(&mem, args(&["alias", "--quiet", "--file", path.to_str().unwrap(), "gs=git status"])).unwrap();
+
+        let ram = mem.query_alias("gs", &Verbosity::mute());
+        assert_eq!(ram, vec!["gs=git status".to_string()]);
+        assert!(fs::read_to_string(&path).unwrap().contains("gs=git status"));
+    }
+
+    #[test]
+    fn test_memory_run_temp_skips_disk() {
+        let mem = MemoryProvider::new();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mem.doskey");
+        fs::write(&path, "").unwrap();
+
+        run_with(&mem, args(&["alias", "--quiet", "--temp", "--file", path.to_str().unwrap(), "t=cd /d %TEMP%"])).unwrap();
+
+        let ram = mem.get_all_aliases(&Verbosity::mute()).unwrap();
+        assert_eq!(ram, vec![("t".to_string(), "cd /d %TEMP%".to_string())]);
+        assert!(fs::read_to_string(&path).unwrap().is_empty(), "Volatile set must not touch the file");
+    }
+
+    #[test]
+    fn test_memory_run_remove_hits_ram_and_disk() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mem.doskey");
+        fs::write(&path, "gs=git status\nll=dir /w").unwrap();
+        let mem = MemoryProvider::seeded(&[("gs", "git status"), ("ll", "dir /w")]);
+
+        run_with(&mem, args(&["alias", "--quiet", "--file", path.to_str().unwrap(), "--remove", "gs"])).unwrap();
+
+        let ram = mem.get_all_aliases(&Verbosity::mute()).unwrap();
+        assert_eq!(ram, vec![("ll".to_string(), "dir /w".to_string())]);
+        assert!(!fs::read_to_string(&path).unwrap().contains("gs="));
+    }
+
+    #[test]
+    #[serial] // --reload reads ALIAS_TEAM_FILE, which the layer tests set
+    fn test_memory_run_reload_replaces_ram() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mem.doskey");
+        fs::write(&path, "a=echo 1\n; comment\nb=echo 2\n").unwrap();
+        let mem = MemoryProvider::seeded(&[("stale", "echo old")]);
+
+        run_with(&mem, args(&["alias", "--quiet", "--file", path.to_str().unwrap(), "--reload"])).unwrap();
+
+        let ram = mem.get_all_aliases(&Verbosity::mute()).unwrap();
+        assert_eq!(ram, vec![
+            ("a".to_string(), "echo 1".to_string()),
+            ("b".to_string(), "echo 2".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_memory_run_setup_writes_autorun() {
+        let mem = MemoryProvider::new();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mem.doskey");
+        fs::write(&path, "").unwrap();
+
+        run_with(&mem, args(&["alias", "--setup", "--quiet", "--file", path.to_str().unwrap()])).unwrap();
+
+        let autorun = mem.read_autorun_registry();
+        assert!(autorun.contains("--startup"), "AutoRun was: {}", autorun);
+        assert!(autorun.contains("mem.doskey"), "AutoRun was: {}", autorun);
+    }
+
+    #[test]
+    fn test_run_with_instances_are_isolated() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mem.doskey");
+        fs::write(&path, "").unwrap();
+        let first = MemoryProvider::new();
+        let second = MemoryProvider::seeded(&[("keep", "echo mine")]);
+
+        run_with(&first, args(&["alias", "--quiet", "--temp", "--file", path.to_str().unwrap(), "gs=git status"])).unwrap();
+
+        assert_eq!(first.get_all_aliases(&Verbosity::mute()).unwrap().len(), 1);
+        assert_eq!(second.get_all_aliases(&Verbosity::mute()).unwrap(), vec![("keep".to_string(), "echo mine".to_string())]);
+    }
+}
+
+
+// =========================================================
+// SECTION 7: ALIAS DOCUMENT (Lossless round trip)
+// =========================================================
+#[cfg(test)]
+mod alias_document_tests {
+    use alias_lib::{update_disk_file, AliasAction, AliasDocument, AliasMeta, AliasNode, Verbosity};
+    use tempfile::tempdir;
+    use super::*;
+
+    const TEAM_FILE: &str = ";; --- git ---\r\ngs=git status\r\n\r\n# build helpers\r\nmk = make $*\r\n1bad=nope\r\njust some text\r\nll=dir /w";
+
+    #[test]
+    fn test_document_round_trip_is_byte_for_byte() {
+        let doc = AliasDocument::parse(TEAM_FILE);
+        assert_eq!(doc.to_string(), TEAM_FILE);
+        assert_eq!(doc.nodes.len(), 8);
+        assert!(matches!(doc.nodes[0], AliasNode::Comment { .. }));
+        assert!(matches!(doc.nodes[2], AliasNode::Blank { .. }));
+        assert!(matches!(doc.nodes[5], AliasNode::Unparsable { .. }));
+        assert!(matches!(doc.nodes[6], AliasNode::Unparsable { .. }));
+        assert_eq!(doc.nodes[7].eol(), "");
+    }
+
+    #[test]
+    fn test_document_entries_match_parser() {
+        let doc = AliasDocument::parse(TEAM_FILE);
+        assert_eq!(doc.entries(), vec![
+            ("gs".to_string(), "git status".to_string()),
+            ("mk".to_string(), " make $*".to_string()),
+            ("ll".to_string(), "dir /w".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_document_set_touches_only_that_line() {
+        let mut doc = AliasDocument::parse(TEAM_FILE);
+        assert!(doc.set("gs", "git status -sb"));
+        assert_eq!(doc.to_string(), TEAM_FILE.replace("gs=git status\r\n", "gs=git status -sb\r\n"));
+        assert!(!doc.set("gs", "git status -sb"), "Same value is a no-op");
+    }
+
+    #[test]
+    fn test_document_append_keeps_eol_style() {
+        let mut doc = AliasDocument::parse(TEAM_FILE);
+        doc.set("new", "echo hi");
+        assert_eq!(doc.to_string(), format!("{}\r\nnew=echo hi", TEAM_FILE));
+
+        let mut lf = AliasDocument::parse("a=1\n");
+        lf.set("b", "2");
+        assert_eq!(lf.to_string(), "a=1\nb=2\n");
+    }
+
+    #[test]
+    fn test_document_remove_and_rename() {
+        let mut doc = AliasDocument::parse(TEAM_FILE);
+        assert!(doc.remove("ll"));
+        assert_eq!(doc.to_string(), TEAM_FILE.replace("\r\nll=dir /w", ""));
+        assert!(!doc.remove("ll"));
+
+        assert!(doc.rename("mk", "build"));
+        assert!(doc.to_string().contains("\r\nbuild = make $*\r\n"));
+        assert!(!doc.rename("gs", "build"), "Rename must not clobber an existing alias");
+    }
+
+    #[test]
+    fn test_update_disk_file_keeps_comments() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("team.doskey");
+        fs::write(&path, TEAM_FILE).unwrap();
+
+        update_disk_file(&Verbosity::mute(), "gs", "git status -sb", &path).unwrap();
+        update_disk_file(&Verbosity::mute(), "ll", "", &path).unwrap();
+
+        let expected = TEAM_FILE.replace("gs=git status\r\n", "gs=git status -sb\r\n").replace("\r\nll=dir /w", "");
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_meta_line_parse_and_render() {
+        let meta = AliasMeta::parse(";; @desc Short git status @tags git,nav @created 2025-01-31T09:00:00Z").unwrap();
+        assert_eq!(meta.desc.as_deref(), Some("Short git status"));
+        assert_eq!(meta.tags, vec!["git".to_string(), "nav".to_string()]);
+        assert_eq!(meta.created.as_deref(), Some("2025-01-31T09:00:00Z"));
+        assert_eq!(meta.to_string(), ";; @desc Short git status @tags git,nav @created 2025-01-31T09:00:00Z");
+
+        assert!(AliasMeta::parse(";; just a comment").is_none());
+        assert!(AliasMeta::parse("; @desc single semicolon is not metadata").is_none());
+    }
+
+    #[test]
+    fn test_document_meta_attaches_to_next_entry() {
+        let mut doc = AliasDocument::parse(";; @desc status @tags git\ngs=git status\n;; plain\nll=dir /w\n");
+        assert_eq!(doc.meta("gs").unwrap().desc.as_deref(), Some("status"));
+        assert!(doc.meta("ll").is_none());
+
+        let meta = AliasMeta { desc: Some("wide listing".into()), ..Default::default() };
+        assert!(doc.set_meta("ll", &meta));
+        assert_eq!(doc.to_string(), ";; @desc status @tags git\ngs=git status\n;; plain\n;; @desc wide listing\nll=dir /w\n");
+        assert!(!doc.set_meta("nope", &meta));
+
+        // Removing the entry takes its metadata along, plain comments stay
+        doc.remove("ll");
+        assert_eq!(doc.to_string(), ";; @desc status @tags git\ngs=git status\n;; plain\n");
+        assert_eq!(doc.entries(), vec![("gs".to_string(), "git status".to_string())]);
+    }
+
+    #[test]
+    fn test_describe_action_writes_meta_line() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("team.doskey");
+        fs::write(&path, "; team file\ngs=git status\n").unwrap();
+        let mem = alias_lib::MemoryProvider::new();
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        alias_lib::run_with(&mem, args(&["alias", "--quiet", "--file", path.to_str().unwrap(), "--describe", "gs", "Short status @tags git"])).unwrap();
+
+        let doc = AliasDocument::parse(&fs::read_to_string(&path).unwrap());
+        let meta = doc.meta("gs").expect("meta line written");
+        assert_eq!(meta.desc.as_deref(), Some("Short status"));
+        assert_eq!(meta.tags, vec!["git".to_string()]);
+        assert!(meta.created.is_some());
+        assert!(doc.to_string().starts_with("; team file\n;; @desc Short status @tags git @created "));
+        assert_eq!(doc.entries(), vec![("gs".to_string(), "git status".to_string())], "doskey still sees one macro");
+
+        let (queue, _) = alias_lib::parse_arguments(&args(&["alias", "--describe", "gs"]));
+        assert!(matches!(&queue.tasks[0].action, AliasAction::Describe(o) if o.name == "gs" && o.value.is_empty()));
+    }
+}
+
+
+// =========================================================
+// SECTION 8: EXPORT TRANSLATOR (Golden files)
+// =========================================================
+#[cfg(test)]
+mod export_golden_tests {
+    use alias_lib::{export_aliases, parse_macro_file, translate_macro, ExportFormat, Verbosity};
+    use std::path::Path;
+
+    const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/export");
+
+    fn check_golden(format: ExportFormat, golden: &str) {
+        let dir = Path::new(GOLDEN_DIR);
+        let pairs = parse_macro_file(&dir.join("aliases.doskey"), &Verbosity::mute()).unwrap();
+        let report = export_aliases(&pairs, format);
+        // Golden files are checked out with whatever newline git likes, compare line-wise
+        let expected = std::fs::read_to_string(dir.join(golden)).unwrap();
+        assert_eq!(report.script.lines().collect::<Vec<_>>(), expected.lines().collect::<Vec<_>>(), "Golden mismatch for {}", golden);
+    }
+
+    #[test] fn test_export_golden_bash() { check_golden(ExportFormat::Bash, "bash.sh"); }
+    #[test] fn test_export_golden_zsh() { check_golden(ExportFormat::Zsh, "zsh.zsh"); }
+    #[test] fn test_export_golden_fish() { check_golden(ExportFormat::Fish, "fish.fish"); }
+    #[test] fn test_export_golden_powershell() { check_golden(ExportFormat::PowerShell, "powershell.ps1"); }
+    #[test] fn test_export_golden_clink() { check_golden(ExportFormat::Clink, "clink.doskey"); }
+
+    #[test]
+    fn test_export_reports_untranslatable() {
+        let pairs = parse_macro_file(&Path::new(GOLDEN_DIR).join("aliases.doskey"), &Verbosity::mute()).unwrap();
+        let report = export_aliases(&pairs, ExportFormat::Bash);
+        let skipped: Vec<&str> = report.skipped.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(skipped, vec!["tmp", "bad"]);
+        assert_eq!(report.exported, pairs.len() - 2);
+        assert!(translate_macro("x", "echo $", ExportFormat::Fish).is_err(), "Dangling $ must not be emitted");
+        assert!(translate_macro("x", "sort $L in.txt", ExportFormat::PowerShell).is_err());
+        assert_eq!("PWSH".parse::<ExportFormat>(), Ok(ExportFormat::PowerShell));
+        assert!("tcsh".parse::<ExportFormat>().is_err());
+    }
+}
+
+
+// =========================================================
+// SECTION 9: IMPORT (Shell files into the alias file)
+// =========================================================
+#[cfg(test)]
+mod import_tests {
+    use alias_lib::{import_aliases, parse_import, AliasAction, AliasProvider, ImportFormat, ImportOptions, ImportPolicy, MemoryProvider, Verbosity};
+    use alias_lib::import::translate_body;
+    use super::apply_tests::FlakyProvider;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn pairs(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_import_bash_aliases_and_functions() {
+        let src = "# comment\nalias ll='ls -la'\nalias gs=\"git status\"\nmkcd() {\n  mkdir -p \"$1\"\n  cd \"$1\"\n}\nexport PATH=/x\n";
+        let report = parse_import(src, ImportFormat::Bash);
+        assert_eq!(report.aliases, pairs(&[
+            ("ll", "ls -la"),
+            ("gs", "git status"),
+            ("mkcd", "mkdir -p \"$1\"$T cd \"$1\""),
+        ]));
+    }
+
+    #[test]
+    fn test_import_powershell_profile() {
+        let src = "Set-Alias -Name np -Value notepad\nfunction gco { git checkout $args[0] }\nfunction gl { git log @args | more }\n";
+        let report = parse_import(src, ImportFormat::PowerShell);
+        assert_eq!(report.aliases, pairs(&[
+            ("np", "notepad $*"),
+            ("gco", "git checkout $1"),
+            ("gl", "git log $* $B more"),
+        ]));
+    }
+
+    #[test]
+    fn test_import_doskey_dump() {
+        let src = "[cmd.exe]\nll=dir /w $*\n\n; note\ngs=git status\n";
+        let report = parse_import(src, ImportFormat::DoskeyMacros);
+        assert_eq!(report.aliases, pairs(&[("ll", "dir /w $*"), ("gs", "git status")]));
+    }
+
+    #[test]
+    fn test_translate_body_positional_args() {
+        assert_eq!(translate_body("grep -r \"$@\" .", false).unwrap(), "grep -r $* .");
+        assert_eq!(translate_body("cp ${1} $2 > log", false).unwrap(), "cp $1 $2 $G log");
+        assert_eq!(translate_body("echo \\$HOME", false).unwrap(), "echo $$HOME");
+        assert!(translate_body("echo $HOME", false).is_err(), "Shell variables have no doskey meaning");
+        assert!(translate_body("echo 'open", false).is_err());
+    }
+
+    #[test]
+    fn test_import_merge_respects_prefer_policy() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("aliases.doskey");
+        let source = dir.path().join("team.sh");
+        fs::write(&target, "; keep me\ngs=git status -sb\n").unwrap();
+        fs::write(&source, "alias gs='git status'\nalias ll='ls -la'\nalias bad.name='x'\n").unwrap();
+        let args = |extra: &[&str]| {
+            let mut v: Vec<String> = vec!["alias".into(), "--quiet".into(), "--file".into(), target.to_str().unwrap().into(), "--import".into(), source.to_str().unwrap().into()];
+            v.extend(extra.iter().map(|s| s.to_string()));
+            v
+        };
+
+        // Default policy keeps what the file already has
+        let mem = MemoryProvider::new();
+        alias_lib::run_with(&mem, args(&[])).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "; keep me\ngs=git status -sb\nll=ls -la\n");
+
+        alias_lib::run_with(&mem, args(&["--prefer", "incoming"])).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "; keep me\ngs=git status\nll=ls -la\n");
+
+        let (queue, _) = alias_lib::parse_arguments(&args(&["--from", "bash", "--prefer", "incoming"]));
+        assert!(queue.tasks.iter().any(|t| matches!(&t.action,
+            AliasAction::Import(o) if o.from == Some(ImportFormat::Bash) && o.prefer == ImportPolicy::Incoming)));
+    }
+
+    #[test]
+    fn test_import_matches_names_case_insensitively() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("aliases.doskey");
+        let source = dir.path().join("team.sh");
+        fs::write(&target, "GS=git status -sb
+").unwrap();
+        fs::write(&source, "alias gs='git status'\n").unwrap();
+        let mem = MemoryProvider::new();
+        let opts = |prefer| ImportOptions { source: source.clone(), from: None, prefer };
+
+        import_aliases(&mem, &Verbosity::mute(), &opts(ImportPolicy::Existing), &target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "GS=git status -sb\n", "An existing 'GS' is a conflict, and --prefer existing keeps it");
+
+        import_aliases(&mem, &Verbosity::mute(), &opts(ImportPolicy::Incoming), &target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "GS=git status\n", "Replaced in place, not appended as a second entry");
+    }
+
+    #[test]
+    fn test_import_skips_values_doskey_would_mangle() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("aliases.doskey");
+        let source = dir.path().join("macros.txt");
+        fs::write(&source, "x=echo $HOME\nll=dir /w $*\n").unwrap();
+        let mem = MemoryProvider::new();
+        let opts = ImportOptions { source, from: Some(ImportFormat::DoskeyMacros), prefer: ImportPolicy::default() };
+
+        import_aliases(&mem, &Verbosity::mute(), &opts, &target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "ll=dir /w $*");
+        assert_eq!(mem.get_all_aliases(&Verbosity::mute()).unwrap(), vec![("ll".to_string(), "dir /w $*".to_string())]);
+    }
+
+    #[test]
+    fn test_import_is_all_or_nothing() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("aliases.doskey");
+        let source = dir.path().join("team.sh");
+        fs::write(&target, "gs=git status -sb\n").unwrap();
+        fs::write(&source, "alias ll='ls -la'\nalias t='cargo test'\n").unwrap();
+        let opts = ImportOptions { source, from: None, prefer: ImportPolicy::default() };
+
+        for declines in [false, true] {
+            let flaky = FlakyProvider { inner: MemoryProvider::new(), refuse: "t", declines };
+            assert!(import_aliases(&flaky, &Verbosity::mute(), &opts, &target).is_err());
+            assert_eq!(fs::read_to_string(&target).unwrap(), "gs=git status -sb\n", "declines={}: the file is put back", declines);
+            assert!(flaky.inner.get_all_aliases(&Verbosity::mute()).unwrap().is_empty(), "declines={}: RAM is put back", declines);
+        }
+    }
+}
+
+// =========================================================
+// SECTION 10: DOSKEY MACRO LANGUAGE (Tokenizer & validator)
+// =========================================================
+#[cfg(test)]
+mod macro_lang_tests {
+    use alias_lib::{macro_equivalent, tokenize_macro, validate_macro, AliasProvider, MacroIssue, MacroToken, MemoryProvider, SetOptions, ShowIcons, ShowTips, Verbosity, VerbosityLevel};
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_tokenize_every_doskey_code() {
+        let parse = tokenize_macro("dir $1 $g$G out.txt $t type $L in $b more $* $$");
+        assert!(parse.is_clean());
+        assert_eq!(parse.tokens, vec![
+            MacroToken::Text("dir ".into()), MacroToken::Arg(1), MacroToken::Text(" ".into()),
+            MacroToken::Append, MacroToken::Text(" out.txt ".into()), MacroToken::Separator,
+            MacroToken::Text(" type ".into()), MacroToken::RedirectIn, MacroToken::Text(" in ".into()),
+            MacroToken::Pipe, MacroToken::Text(" more ".into()), MacroToken::AllArgs,
+            MacroToken::Text(" ".into()), MacroToken::Dollar,
+        ]);
+        let canonical: String = parse.tokens.iter().map(|t| t.to_string()).collect();
+        assert_eq!(canonical, "dir $1 $G$G out.txt $T type $L in $B more $* $$");
+    }
+
+    #[test]
+    fn test_validator_reports_broken_macros() {
+        assert_eq!(validate_macro("echo $0"), vec![MacroIssue::ZeroArg { pos: 5 }]);
+        assert_eq!(validate_macro("echo $10"), vec![MacroIssue::ArgOutOfRange { pos: 5, text: "$10".into() }]);
+        assert_eq!(validate_macro("echo $"), vec![MacroIssue::DanglingDollar { pos: 5 }]);
+        assert_eq!(validate_macro("echo $Q"), vec![MacroIssue::UnknownToken { pos: 5, text: "$Q".into() }]);
+        // The XCD shape: an odd quote eats the $T and everything after it
+        assert_eq!(validate_macro("cd /d \"%i $T dir"), vec![MacroIssue::UnbalancedQuote { pos: 6 }]);
+        assert!(validate_macro("cd /d \"%i\" $T dir").is_empty());
+        assert!(!MacroIssue::UnbalancedQuote { pos: 0 }.is_fatal());
+        assert!(tokenize_macro("echo $Q").first_fatal().is_some());
+    }
+
+    #[test]
+    fn test_macro_equivalence_is_semantic() {
+        assert!(macro_equivalent("dir $t cls", "dir $T cls"));
+        assert!(macro_equivalent("\"git status\"", "git status"));
+        assert!(macro_equivalent("git status ", "git status"));
+        assert!(!macro_equivalent("dir $1", "dir $2"));
+        assert!(!macro_equivalent("echo $$T", "echo $T"), "An escaped dollar is not a separator");
+    }
+
+    #[test]
+    fn test_set_alias_warns_before_ram() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let spy = Arc::clone(&buffer);
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        {
+            let v = Verbosity {
+                level: VerbosityLevel::Normal,
+                show_icons: ShowIcons::Off,
+                show_tips: ShowTips::Off,
+                display_tip: None,
+                in_startup: false,
+                in_setup: false,
+                writer: Some(buffer),
+                format: alias_lib::OutputFormat::Text,
+                group: None,
+                filter: Default::default(),
+            };
+            let mem = MemoryProvider::new();
+            let opts = SetOptions { name: "bad".into(), value: "echo $0".into(), volatile: false, force_case: false };
+            mem.set_alias(opts, &path, &v).unwrap();
+            // Warnings never block: the macro still lands
+            assert_eq!(mem.query_alias("bad", &Verbosity::mute()), vec!["bad=echo $0".to_string()]);
+        }
+        let out = String::from_utf8(spy.lock().unwrap().clone()).unwrap();
+        assert!(out.contains("Macro 'bad': unsupported doskey token '$0'"), "got: {}", out);
+    }
+}
+
+// =========================================================
+// SECTION 11: EXPANSION SIMULATOR (What cmd.exe will run)
+// =========================================================
+#[cfg(test)]
+mod expand_tests {
+    use alias_lib::{expand_macro, lookup_macro_value, split_doskey_args, AliasAction, MacroIssue, MemoryProvider};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_expand_readme_ncd_macro() {
+        let cd = r#"FOR /F "tokens=*" %i IN ('"ncd.exe" $*') DO @(set "OLDPWD=%CD%" & chdir /d "%i")"#;
+        let expansion = expand_macro(cd, "  projects\\alias");
+        assert!(expansion.issues.is_empty());
+        assert_eq!(expansion.commands, vec![
+            r#"FOR /F "tokens=*" %i IN ('"ncd.exe" projects\alias') DO @(set "OLDPWD=%CD%" & chdir /d "%i")"#.to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_expand_positional_and_separators() {
+        let expansion = expand_macro("mkdir $1 $T cd $1 $T dir $G$G $2.log", "src out");
+        assert_eq!(expansion.commands, vec!["mkdir src ", " cd src ", " dir >> out.log"]);
+        // Missing arguments expand to nothing, just like doskey
+        assert_eq!(expand_macro("echo [$3]", "a b").commands, vec!["echo []"]);
+        assert_eq!(expand_macro("echo $$HOME $B more", "").commands, vec!["echo $HOME | more"]);
+        assert_eq!(expand_macro("echo $0", "x").issues, vec![MacroIssue::ZeroArg { pos: 5 }]);
+    }
+
+    #[test]
+    fn test_doskey_quotes_do_not_group() {
+        assert_eq!(split_doskey_args("\"a b\"\tc"), vec!["\"a", "b\"", "c"]);
+        assert_eq!(expand_macro("echo $2", "\"a b\"").commands, vec!["echo b\""]);
+    }
+
+    #[test]
+    fn test_expand_lookup_prefers_ram_then_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        fs::write(&path, "gs=git status\nll=dir /w\n").unwrap();
+        let mem = MemoryProvider::seeded(&[("gs", "git status -sb")]);
+
+        assert_eq!(lookup_macro_value(&mem, "gs", &path).unwrap().as_deref(), Some("git status -sb"));
+        assert_eq!(lookup_macro_value(&mem, "ll", &path).unwrap().as_deref(), Some("dir /w"));
+        assert_eq!(lookup_macro_value(&mem, "nope", &path).unwrap(), None);
+
+        let args: Vec<String> = ["alias", "--expand", "ll", "--sort", "x"].iter().map(|s| s.to_string()).collect();
+        let (queue, _) = alias_lib::parse_arguments(&args);
+        assert!(matches!(&queue.tasks[0].action, AliasAction::Expand(n, a) if n == "ll" && a == &["--sort", "x"]));
+        alias_lib::run_with(&mem, vec!["alias".into(), "--quiet".into(), "--file".into(), path.to_str().unwrap().into(), "--expand".into(), "ll".into()]).unwrap();
+        let missing = alias_lib::Task { action: AliasAction::Expand("nope".into(), vec![]), path: path.clone(), args: Vec::new() };
+        assert!(alias_lib::dispatch_with(&mem, missing, &alias_lib::Verbosity::mute()).is_err());
+    }
+}
+
+// =========================================================
+// SECTION 12: MACHINE-READABLE OUTPUT (--format json|tsv)
+// =========================================================
+#[cfg(test)]
+mod output_format_tests {
+    use alias_lib::output::{audit_record, diagnostics_record, query_record, Record};
+    use alias_lib::{parse_arguments, render_diagnostics, render_records, AliasEntryMesh, AliasMeta, AuditEntry, AuditStatus, DiagnosticReport, OutputFormat, ProviderType, RegistryStatus, ShowIcons, ShowTips, Verbosity, VerbosityLevel};
+    use std::sync::{Arc, Mutex};
+
+    fn entry(name: &str, os: Option<&str>, file: Option<&str>) -> AliasEntryMesh {
+        AliasEntryMesh { name: name.into(), os_value: os.map(Into::into), file_value: file.map(Into::into), meta: None, origin: None }
+    }
+
+    fn capture(format: OutputFormat, body: impl FnOnce(&Verbosity)) -> String {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let spy = Arc::clone(&buffer);
+        {
+            let v = Verbosity {
+                level: VerbosityLevel::Normal,
+                show_icons: ShowIcons::Off,
+                show_tips: ShowTips::Off,
+                display_tip: None,
+                in_startup: false,
+                in_setup: false,
+                writer: Some(buffer),
+                format,
+                group: None,
+                filter: Default::default(),
+            };
+            body(&v);
+        }
+        String::from_utf8(spy.lock().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_mesh_status_classification() {
+        assert_eq!(entry("gs", Some("git status"), Some("git status")).status(), AuditStatus::Synced);
+        assert_eq!(entry("gs", Some("git status"), Some("git st")).status(), AuditStatus::Desync);
+        assert_eq!(entry("x", Some("dir $T cls"), Some("dir $t cls")).status(), AuditStatus::Synced, "Doskey codes are case-insensitive");
+        assert_eq!(entry("gs", Some("git status"), None).status(), AuditStatus::Phantom);
+        assert_eq!(entry("gs", None, Some("git status")).status(), AuditStatus::Pending);
+        assert_eq!(entry("bad name", Some("x"), Some("x")).status(), AuditStatus::Corrupt);
+    }
+
+    #[test]
+    fn test_alias_record_json_schema() {
+        let mut e = entry("gs", Some("git \"status\"\t-sb"), None);
+        e.meta = Some(AliasMeta { desc: Some("status".into()), tags: vec!["git".into()], created: None });
+        assert_eq!(audit_record(&AuditEntry::from_mesh(&e, &ProviderType::Win32)).to_json(),
+            r#"{"kind":"alias","name":"gs","status":"phantom","win32":"git \"status\"\t-sb","doskey":null,"file":null,"desc":"status","tags":["git"],"scope":null,"shadows":[],"defined":null,"group":null}"#);
+        // The wrapper reads doskey, not the kernel
+        let wrapped = audit_record(&AuditEntry::from_mesh(&entry("gs", Some("x"), Some("x")), &ProviderType::Wrapper));
+        assert!(wrapped.to_json().contains(r#""win32":null,"doskey":"x","file":"x""#));
+    }
+
+    #[test]
+    fn test_counts_and_ids_keep_every_bit() {
+        let record = Record::new("history").with("id", 5_000_000_001u64).with("entries", 7usize);
+        assert_eq!(record.to_json(), r#"{"kind":"history","id":5000000001,"entries":7}"#);
+    }
+
+    #[test]
+    fn test_tsv_headers_per_kind() {
+        let records = vec![query_record("gs", "git\tstatus"), query_record("ll", "dir")];
+        assert_eq!(render_records(&records, OutputFormat::Tsv), vec![
+            "kind\tname\tvalue".to_string(),
+            "query\tgs\tgit\\tstatus".to_string(),
+            "query\tll\tdir".to_string(),
+        ]);
+        assert!(render_records(&records, OutputFormat::Text).is_empty());
+    }
+
+    #[test]
+    fn test_diagnostics_render_as_one_record() {
+        let report = DiagnosticReport {
+            resolved_path: "C:\\aliases.doskey".into(),
+            registry_status: RegistryStatus::Mismatch("other.exe".into()),
+            ..Default::default()
+        };
+        assert!(diagnostics_record(&report).to_json().contains(r#""registry_status":"mismatch","registry_value":"other.exe""#));
+
+        let out = capture(OutputFormat::Json, |v| render_diagnostics(report.clone(), v));
+        assert_eq!(out.lines().filter(|l| !l.is_empty()).count(), 1, "No banner or footer in json mode: {}", out);
+        assert!(out.starts_with(r#"{"kind":"diagnostics","binary_path":null,"resolved_path":"C:\\aliases.doskey""#));
+        // Chatter is dropped in machine formats
+        assert!(capture(OutputFormat::Json, |v| v.say("hello")).is_empty());
+    }
+
+    #[test]
+    fn test_format_flag_parsing() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let (_, voice) = parse_arguments(&args(&["alias", "--format", "json", "--show-all"]));
+        assert_eq!(voice.format, OutputFormat::Json);
+        let (_, voice) = parse_arguments(&args(&["alias", "--format=tsv", "gs"]));
+        assert_eq!(voice.format, OutputFormat::Tsv);
+        let (_, voice) = parse_arguments(&args(&["alias", "--version", "--format", "json"]));
+        assert_eq!(voice.format, OutputFormat::Json, "--version short-circuits but still honours --format");
+        let (queue, _) = parse_arguments(&args(&["alias", "--format", "xml", "--show-all"]));
+        assert!(queue.tasks.iter().any(|t| t.action == alias_lib::AliasAction::Fail));
+    }
+}
+
+// =========================================================
+// SECTION 13: STRUCTURED AUDIT (Pure triple_audit & exit verdict)
+// =========================================================
+#[cfg(test)]
+mod audit_report_tests {
+    use alias_lib::{triple_audit, AliasError, AuditStatus, ErrorCode, MemoryProvider, ENV_ALIAS_FILE};
+    use serial_test::serial;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn pairs(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_triple_audit_classifies_every_layer() {
+        let report = triple_audit(
+            pairs(&[("gs", "git status"), ("ls", "dir $t cls"), ("cd", "cd /d $1"), ("bad name", "x")]),
+            pairs(&[("gs", "git status"), ("ls", "dir $T cls"), ("ghost", "echo boo")]),
+            pairs(&[("gs", "\"git status\""), ("cd", "chdir $1"), ("new", "echo hi")]),
+        );
+        let statuses: Vec<(&str, AuditStatus)> = report.entries.iter().map(|e| (e.name.as_str(), e.status)).collect();
+        assert_eq!(statuses, vec![
+            ("gs", AuditStatus::Synced),
+            ("ls", AuditStatus::Synced),
+            ("cd", AuditStatus::Desync),
+            ("bad name", AuditStatus::Corrupt),
+            ("ghost", AuditStatus::Phantom),
+            ("new", AuditStatus::Pending),
+        ]);
+        assert_eq!((report.synced, report.desync, report.corrupt, report.phantom, report.pending), (2, 1, 1, 1, 1));
+        assert!(report.desync_detected());
+        assert!(report.is_broken());
+        assert_eq!(report.entries[2].file.as_deref(), Some("chdir $1"));
+
+        let clean = triple_audit(pairs(&[("gs", "git status")]), pairs(&[("gs", "git status")]), pairs(&[("gs", "git status")]));
+        assert!(!clean.desync_detected());
+
+        // A session-only alias and a not-yet-loaded one want a reload, but nothing is broken
+        let settling = triple_audit(pairs(&[("gs", "git status"), ("tmp", "echo temp")]), Vec::new(), pairs(&[("gs", "git status"), ("new", "echo hi")]));
+        assert!(settling.desync_detected());
+        assert!(!settling.is_broken());
+    }
+
+    #[test]
+    #[serial]
+    fn test_run_returns_desync_exit_code() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        fs::write(&path, "gs=git status\nll=dir /w\n").unwrap();
+        unsafe { std::env::set_var(ENV_ALIAS_FILE, &path); }
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let stale = MemoryProvider::seeded(&[("gs", "git stat")]);
+        let err = alias_lib::run_with(&stale, args(&["alias", "--quiet", "--show-all"])).expect_err("gs drifted");
+        let alias_err = err.downcast_ref::<AliasError>().expect("AliasError carries the exit code");
+        assert_eq!(alias_err.code, ErrorCode::Desync as u8);
+        assert!(alias_err.message.contains("1 desync") && alias_err.message.contains("1 pending"), "got: {}", alias_err.message);
+        for filter in [&["--list", "ll*"][..], &["--pending-only"], &["--grep", "dir"]] {
+            let cli = [&["alias", "--quiet", "--show-all"][..], filter].concat();
+            assert!(alias_lib::run_with(&stale, args(&cli)).is_err(), "{:?} hides gs, but the exit code still counts it", filter);
+        }
+
+        let settling = MemoryProvider::seeded(&[("gs", "git status"), ("tmp", "echo session only")]);
+        let outcome = alias_lib::run_with(&settling, args(&["alias", "--quiet", "--show-all"]));
+        assert!(outcome.is_ok(), "Pending and phantom (--temp) entries do not fail the audit: {:?}", outcome.err().map(|e| e.to_string()));
+
+        let fresh = MemoryProvider::seeded(&[("gs", "git status"), ("ll", "dir /w")]);
+        let outcome = alias_lib::run_with(&fresh, args(&["alias", "--quiet", "--show-all"]));
+        unsafe { std::env::remove_var(ENV_ALIAS_FILE); }
+        assert!(outcome.is_ok(), "In sync means exit 0");
+    }
+}
+
+// =========================================================
+// SECTION 14: FILE LOCKING (Concurrent read-modify-write)
+// =========================================================
+#[cfg(test)]
+mod file_lock_tests {
+    use alias_lib::{lock_alias_file, update_disk_file, AliasDocument, AliasError, ErrorCode, Verbosity};
+    use std::sync::Arc;
+    use std::thread;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_concurrent_updates_lose_nothing() {
+        let dir = tempdir().unwrap();
+        let path = Arc::new(dir.path().join("aliases.doskey"));
+
+        let workers: Vec<_> = (0..8).map(|w| {
+            let path = Arc::clone(&path);
+            thread::spawn(move || {
+                let verbosity = Verbosity::silent();
+                for i in 0..10 {
+                    // A busy neighbour can exceed one bounded wait; retry like a user would
+                    let mut attempts = 0;
+                    while let Err(e) = update_disk_file(&verbosity, &format!("w{}_{}", w, i), &format!("echo {} {}", w, i), &path) {
+                        attempts += 1;
+                        assert!(attempts < 20, "Worker {} kept failing: {}", w, e);
+                    }
+                }
+            })
+        }).collect();
+        for worker in workers { worker.join().expect("worker panicked"); }
+
+        let doc = AliasDocument::parse(&std::fs::read_to_string(&*path).unwrap());
+        assert_eq!(doc.entries().len(), 80, "Every update must survive the race");
+        assert_eq!(doc.get("w7_9"), Some("echo 7 9"));
+    }
+
+    #[test]
+    fn test_held_lock_times_out_with_code() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        let verbosity = Verbosity::silent();
+
+        let held = lock_alias_file(&verbosity, &path).expect("first lock is free");
+        let started = std::time::Instant::now();
+        let err = update_disk_file(&verbosity, "gs", "git status", &path).expect_err("lock is held");
+        assert!(started.elapsed() < std::time::Duration::from_secs(5), "The wait must be bounded");
+        assert_eq!(err.downcast_ref::<AliasError>().unwrap().code, ErrorCode::LockTimeout as u8);
+        assert!(!path.exists(), "Nothing may be written without the lock");
+
+        drop(held);
+        update_disk_file(&verbosity, "gs", "git status", &path).expect("lock released on drop");
+    }
+}
+
+// =========================================================
+// SECTION 15: BACKUPS (Rotating snapshots & --restore)
+// =========================================================
+#[cfg(test)]
+mod backup_tests {
+    use alias_lib::backup::backup_dir;
+    use alias_lib::{find_backup, list_backups, restore_backup, snapshot_alias_file, update_disk_file, AliasProvider, BackupDiff, MemoryProvider, Verbosity};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_snapshots_rotate_and_skip_duplicates() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        assert_eq!(snapshot_alias_file(&path, 3).unwrap(), None, "Nothing to back up yet");
+
+        for i in 0..5 {
+            fs::write(&path, format!("gs=git status\nv{}=echo {}\n", i, i)).unwrap();
+            assert!(snapshot_alias_file(&path, 3).unwrap().is_some());
+        }
+        assert_eq!(snapshot_alias_file(&path, 3).unwrap(), None, "Unchanged file is not copied twice");
+        assert_eq!(snapshot_alias_file(&path, 0).unwrap(), None, "Retention 0 disables snapshots");
+
+        let backups = list_backups(&path).unwrap();
+        assert_eq!(backups.len(), 3, "Only the newest three survive");
+        assert_eq!(fs::read_to_string(&backups[0].path).unwrap(), "gs=git status\nv4=echo 4\n");
+        assert_eq!(backups[2].diff, BackupDiff { added: 1, removed: 1, changed: 0 });
+        assert!(backups.iter().all(|b| b.path.starts_with(backup_dir(&path))));
+    }
+
+    #[test]
+    fn test_update_snapshots_and_restore_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        let verbosity = Verbosity::silent();
+        fs::write(&path, "gs=git status\n").unwrap();
+
+        update_disk_file(&verbosity, "gs", "git status -sb", &path).unwrap();
+        update_disk_file(&verbosity, "ll", "dir /w", &path).unwrap();
+        let backups = list_backups(&path).unwrap();
+        assert_eq!(backups.len(), 2, "One snapshot per transactional swap");
+        assert_eq!(backups[1].diff, BackupDiff { added: 0, removed: 1, changed: 1 });
+
+        let original = find_backup(&backups, "2").unwrap();
+        assert_eq!(find_backup(&backups, &original.stamp[..10]).map(|b| b.index), Some(1), "Stamp prefix picks the newest match");
+        assert!(find_backup(&backups, "9").is_none());
+
+        restore_backup(&verbosity, original, &path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "gs=git status\n");
+        assert_eq!(list_backups(&path).unwrap().len(), 3, "The restore itself is undoable");
+    }
+
+    #[test]
+    fn test_restore_action_reloads_ram() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        let verbosity = Verbosity::silent();
+        fs::write(&path, "gs=git status\n").unwrap();
+        update_disk_file(&verbosity, "gs", "broken", &path).unwrap();
+
+        let provider = MemoryProvider::seeded(&[("gs", "broken")]);
+        let task = alias_lib::Task { action: alias_lib::AliasAction::Restore("1".into()), path: path.clone(), args: Vec::new() };
+        alias_lib::dispatch_with(&provider, task, &verbosity).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "gs=git status\n");
+        assert_eq!(provider.query_alias("gs", &verbosity), vec!["gs=git status".to_string()]);
+    }
+}
+
+// =========================================================
+// SECTION 16: JOURNAL (Undo / redo / history)
+// =========================================================
+#[cfg(test)]
+mod journal_tests {
+    use alias_lib::journal::{journal_path, parse_journal};
+    use alias_lib::{dispatch_with, load_journal, undo_stacks, AliasAction, AliasProvider, JournalLayer, JournalOp, MemoryProvider, SetOptions, Task, Verbosity};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn run(provider: &MemoryProvider, action: AliasAction, path: &Path) {
+        dispatch_with(provider, Task { action, path: path.to_path_buf(), args: Vec::new() }, &Verbosity::silent()).unwrap();
+    }
+
+    fn set(name: &str, value: &str) -> AliasAction {
+        AliasAction::Set(SetOptions { name: name.to_string(), value: value.to_string(), volatile: false, force_case: false })
+    }
+
+    fn ram(provider: &MemoryProvider, name: &str) -> Option<String> {
+        provider.get_all_aliases(&Verbosity::silent()).unwrap().into_iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    #[test]
+    fn test_mutations_are_journaled_with_prev_and_new() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        let provider = MemoryProvider::default();
+
+        run(&provider, set("gs", "git status"), &path);
+        run(&provider, set("gs", "git status -sb"), &path);
+        run(&provider, AliasAction::Query("gs".into()), &path); // read-only: not journaled
+        run(&provider, AliasAction::Unalias(SetOptions::volatile("gs".into(), false)), &path);
+
+        let txns = load_journal(&path).unwrap();
+        assert_eq!(txns.len(), 3);
+        assert_eq!(txns[1].action, "gs=git status -sb");
+        let file_change = txns[1].changes.iter().find(|c| c.layer == JournalLayer::File).unwrap();
+        assert_eq!((file_change.prev.as_deref(), file_change.new.as_deref()), (Some("git status"), Some("git status -sb")));
+        assert_eq!(txns[2].changes.len(), 1, "Unalias only touches RAM");
+        assert_eq!(txns[2].changes[0].new, None);
+    }
+
+    #[test]
+    fn test_undo_redo_round_trip_file_and_ram() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        let provider = MemoryProvider::default();
+
+        run(&provider, set("gs", "git status"), &path);
+        run(&provider, set("gs", "git status -sb"), &path);
+        run(&provider, set("ll", "dir /w"), &path);
+
+        run(&provider, AliasAction::Undo(2), &path);
+        assert_eq!(fs::read_to_string(&path).unwrap().trim_end(), "gs=git status");
+        assert_eq!(ram(&provider, "gs").as_deref(), Some("git status"));
+        assert_eq!(ram(&provider, "ll"), None);
+
+        run(&provider, AliasAction::Redo, &path);
+        assert_eq!(ram(&provider, "gs").as_deref(), Some("git status -sb"));
+        assert!(fs::read_to_string(&path).unwrap().contains("gs=git status -sb"));
+
+        // A fresh change drops whatever was left to redo
+        run(&provider, set("gd", "git diff"), &path);
+        let (done, undone) = undo_stacks(&load_journal(&path).unwrap());
+        assert_eq!(done, vec![1, 2, 7]); // 4 and 5 undid 3 and 2, 6 redid 2
+        assert!(undone.is_empty());
+    }
+
+    #[test]
+    fn test_journal_format_escapes_and_skips_torn_lines() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        let provider = MemoryProvider::default();
+        run(&provider, set("t", "echo a\\b"), &path);
+
+        let mut content = fs::read_to_string(journal_path(&path)).unwrap();
+        assert!(content.contains("\\N"), "Missing previous values are written as \\N");
+        content.push_str("7\t2025-01-01T00:00:00Z\tdo\ttorn");
+        let txns = parse_journal(&content);
+        assert_eq!(txns.len(), 1);
+        assert_eq!(txns[0].op, JournalOp::Do);
+        assert_eq!(txns[0].changes[0].new.as_deref(), Some("echo a\\b"));
+    }
+}
+
+// =========================================================
+// SECTION 17: LAYERS (team < user < project < session)
+// =========================================================
+#[cfg(test)]
+mod layer_tests {
+    use alias_lib::layers::origin_note;
+    use alias_lib::{alias_layers, attach_layer_origins, dispatch_with, merge_layers, mesh_layers, parse_arguments, triple_audit, AliasAction,
+        AliasLayer, AliasProvider, AliasScope, MemoryProvider, Task, Verbosity, ENV_ALIAS_FILE, ENV_ALIAS_TEAM_FILE};
+    use serial_test::serial;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn pairs(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    fn layer(scope: AliasScope, path: &str) -> AliasLayer {
+        AliasLayer { scope, path: PathBuf::from(path) }
+    }
+
+    #[test]
+    fn test_merge_layers_higher_scope_wins_and_records_shadows() {
+        let merged = merge_layers(vec![
+            (layer(AliasScope::Team, "team.doskey"), pairs(&[("gs", "git status"), ("build", "make")])),
+            (layer(AliasScope::User, "user.doskey"), pairs(&[("GS", "git status -sb"), ("ll", "dir /w"), ("ll", "dir /w /o")])),
+            (layer(AliasScope::Project, ".aliases.doskey"), pairs(&[("build", "cargo build")])),
+        ]);
+        let view: Vec<(&str, &str, AliasScope)> = merged.iter().map(|e| (e.name.as_str(), e.value.as_str(), e.origin.scope)).collect();
+        assert_eq!(view, vec![
+            ("GS", "git status -sb", AliasScope::User),
+            ("build", "cargo build", AliasScope::Project),
+            ("ll", "dir /w /o", AliasScope::User),
+        ]);
+        assert_eq!(merged[0].origin.shadowed, vec![(AliasScope::Team, "git status".to_string())]);
+        assert!(merged[2].origin.shadowed.is_empty(), "A duplicate in one file is not a shadow");
+        assert_eq!(origin_note(&merged[1].origin).as_deref(), Some("[project] shadows team:'make'"));
+        assert_eq!(origin_note(&merged[2].origin), None, "Plain user entries print as before");
+    }
+
+    #[test]
+    fn test_mesh_layers_keeps_origin_per_entry() {
+        let merged = merge_layers(vec![
+            (layer(AliasScope::Team, "team.doskey"), pairs(&[("gs", "git status")])),
+            (layer(AliasScope::User, "user.doskey"), pairs(&[("ll", "dir")])),
+        ]);
+        let mesh = mesh_layers(pairs(&[("ghost", "boo"), ("gs", "git status")]), merged);
+        assert_eq!(mesh.len(), 3);
+        assert_eq!(mesh[0].origin.as_ref().map(|o| o.scope), Some(AliasScope::Team));
+        assert_eq!(mesh[1].origin.as_ref().map(|o| o.scope), Some(AliasScope::User));
+        assert!(mesh[2].origin.is_none(), "RAM-only entries have no file layer");
+    }
+
+    #[test]
+    #[serial]
+    fn test_triple_audit_origins_ignore_case() {
+        let dir = tempdir().unwrap();
+        let team = dir.path().join("team.doskey");
+        let user = dir.path().join("user.doskey");
+        fs::write(&team, "GS=git status\n").unwrap();
+        fs::write(&user, "ll=dir\n").unwrap();
+        unsafe {
+            std::env::set_var(ENV_ALIAS_TEAM_FILE, &team);
+            std::env::set_var(ENV_ALIAS_FILE, &user);
+        }
+        let mut report = triple_audit(pairs(&[("gs", "git status")]), Vec::new(), pairs(&[("gs", "git status"), ("ll", "dir")]));
+        attach_layer_origins(&mut report, &Verbosity::mute());
+        unsafe {
+            std::env::remove_var(ENV_ALIAS_TEAM_FILE);
+            std::env::remove_var(ENV_ALIAS_FILE);
+        }
+        let scopes: Vec<(&str, Option<AliasScope>)> = report.entries.iter().map(|e| (e.name.as_str(), e.origin.as_ref().map(|o| o.scope))).collect();
+        assert_eq!(scopes, vec![("gs", Some(AliasScope::Team)), ("ll", Some(AliasScope::User))], "RAM's 'gs' is the team file's 'GS'");
+    }
+
+    #[test]
+    #[serial]
+    fn test_reload_loads_team_below_user() {
+        let dir = tempdir().unwrap();
+        let team = dir.path().join("team.doskey");
+        let user = dir.path().join("user.doskey");
+        fs::write(&team, "gs=git status\nbuild=make\n").unwrap();
+        fs::write(&user, "gs=git status -sb\n").unwrap();
+        unsafe { std::env::set_var(ENV_ALIAS_TEAM_FILE, &team); }
+
+        let scopes: Vec<AliasScope> = alias_layers(&user).iter().map(|l| l.scope).collect();
+        let provider = MemoryProvider::seeded(&[("stale", "x")]);
+        let outcome = dispatch_with(&provider, Task { action: AliasAction::Reload, path: user.clone(), args: Vec::new() }, &Verbosity::silent());
+        unsafe { std::env::remove_var(ENV_ALIAS_TEAM_FILE); }
+
+        assert_eq!(scopes, vec![AliasScope::Team, AliasScope::User]);
+        outcome.unwrap();
+        let mut ram = provider.get_all_aliases(&Verbosity::silent()).unwrap();
+        ram.sort();
+        assert_eq!(ram, pairs(&[("build", "make"), ("gs", "git status -sb")]));
+    }
+
+    #[test]
+    #[serial]
+    fn test_scope_flag_retargets_writes() {
+        let dir = tempdir().unwrap();
+        let team = dir.path().join("team.doskey");
+        unsafe { std::env::set_var(ENV_ALIAS_TEAM_FILE, &team); }
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let (team_queue, _) = parse_arguments(&args(&["alias", "--scope", "team", "gs=git status"]));
+        let (session_queue, _) = parse_arguments(&args(&["alias", "--scope=session", "gs=git status"]));
+        unsafe { std::env::remove_var(ENV_ALIAS_TEAM_FILE); }
+        let (bad_queue, _) = parse_arguments(&args(&["alias", "--quiet", "--scope", "galaxy"]));
+
+        let set = team_queue.tasks.iter().find(|t| matches!(t.action, AliasAction::Set(_))).unwrap();
+        assert_eq!(set.path, team);
+        assert!(matches!(&session_queue.tasks[0].action, AliasAction::Set(opts) if opts.volatile));
+        assert!(bad_queue.tasks.iter().any(|t| t.action == AliasAction::Fail));
+    }
+}
+
+// =========================================================
+// SECTION 18: #include DIRECTIVES
+// =========================================================
+#[cfg(test)]
+mod include_tests {
+    use alias_lib::include::include_directive;
+    use alias_lib::layers::origin_note;
+    use alias_lib::{expand_includes, flatten_macro_file, load_layers, parse_macro_file, AliasProvider, FlatMacroFile, MemoryProvider,
+        Verbosity, INCLUDE_DEPTH_LIMIT};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_include_directive_spellings() {
+        assert_eq!(include_directive("#include git.doskey"), Some("git.doskey"));
+        assert_eq!(include_directive("  ;INCLUDE \"C:\\Shared Aliases\\team.doskey\" "), Some("C:\\Shared Aliases\\team.doskey"));
+        assert_eq!(include_directive("#include <sub/git.doskey>"), Some("sub/git.doskey"));
+        assert_eq!(include_directive("#include"), None);
+        assert_eq!(include_directive("#includes are neat"), None);
+        assert_eq!(include_directive(";; @desc include this"), None);
+        assert_eq!(include_directive("include=echo not a directive"), None);
+    }
+
+    #[test]
+    fn test_expand_splices_in_place_with_sources() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("git.doskey"), ";; git pack\ngs=git status\ngl=git log\n").unwrap();
+        let root = dir.path().join("aliases.doskey");
+        fs::write(&root, "ll=dir\n#include sub/git.doskey\ngs=git status -sb\n").unwrap();
+
+        let pairs = expand_includes(&root, &Verbosity::mute()).unwrap();
+        let view: Vec<(String, String)> = pairs.iter().map(|p| (p.name.clone(), p.source.to_string())).collect();
+        assert_eq!(view, vec![
+            ("ll".to_string(), "aliases.doskey:1".to_string()),
+            ("gs".to_string(), "git.doskey:2".to_string()),
+            ("gl".to_string(), "git.doskey:3".to_string()),
+            ("gs".to_string(), "aliases.doskey:3".to_string()),
+        ]);
+
+        // The expanded stream loads like one flat file: the later root line wins
+        let provider = MemoryProvider::new();
+        provider.raw_reload_from_file(&Verbosity::mute(), &root).unwrap();
+        assert_eq!(provider.query_alias("gs", &Verbosity::mute()), vec!["gs=git status -sb".to_string()]);
+        assert_eq!(provider.query_alias("gl", &Verbosity::mute()), vec!["gl=git log".to_string()]);
+        assert_eq!(parse_macro_file(&root, &Verbosity::mute()).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_include_cycle_missing_and_depth_are_errors() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.doskey");
+        fs::write(&a, "x=1\n#include b.doskey\n").unwrap();
+        fs::write(dir.path().join("b.doskey"), ";include a.doskey\n").unwrap();
+        let err = expand_includes(&a, &Verbosity::mute()).unwrap_err().to_string();
+        assert!(err.contains("cycle") && err.contains("b.doskey:1"), "{}", err);
+
+        let missing = dir.path().join("missing.doskey");
+        fs::write(&missing, "x=1\n#include nowhere.doskey\n").unwrap();
+        let err = expand_includes(&missing, &Verbosity::mute()).unwrap_err().to_string();
+        assert!(err.contains("nowhere.doskey") && err.contains("missing.doskey:2"), "{}", err);
+
+        // A chain of distinct files, one longer than the limit
+        for i in 0..=INCLUDE_DEPTH_LIMIT + 1 {
+            fs::write(dir.path().join(format!("d{}.doskey", i)), format!("#include d{}.doskey\n", i + 1)).unwrap();
+        }
+        let err = expand_includes(&dir.path().join("d0.doskey"), &Verbosity::mute()).unwrap_err().to_string();
+        assert!(err.contains("deeper than"), "{}", err);
+    }
+
+    #[test]
+    fn test_flatten_only_when_needed_and_cleans_up() {
+        let dir = tempdir().unwrap();
+        let plain = dir.path().join("plain.doskey");
+        fs::write(&plain, "ll=dir\n").unwrap();
+        assert!(matches!(&flatten_macro_file(&Verbosity::mute(), &plain).unwrap(), FlatMacroFile::Borrowed(p) if *p == plain));
+
+        fs::write(dir.path().join("git.doskey"), "gs=git status\nbad name=x\n").unwrap();
+        let root = dir.path().join("aliases.doskey");
+        fs::write(&root, "#include git.doskey\nll=dir\n").unwrap();
+        let flat = flatten_macro_file(&Verbosity::mute(), &root).unwrap();
+        let temp = flat.path().to_path_buf();
+        assert_ne!(temp, root);
+        assert_eq!(fs::read_to_string(&temp).unwrap(), "gs=git status\r\nll=dir\r\n");
+        drop(flat);
+        assert!(!temp.exists(), "The flattened copy must not outlive the reload");
+    }
+
+    #[test]
+    fn test_layers_report_where_included_entries_are_defined() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("git.doskey"), ";; pack\ngs=git status\n").unwrap();
+        let user = dir.path().join("user.doskey");
+        fs::write(&user, "#include git.doskey\nll=dir\n").unwrap();
+
+        let merged = load_layers(&Verbosity::mute(), &user).unwrap();
+        let gs = merged.iter().find(|e| e.name == "gs").unwrap();
+        let ll = merged.iter().find(|e| e.name == "ll").unwrap();
+        assert_eq!(origin_note(&gs.origin).as_deref(), Some("[user] defined in git.doskey:2"));
+        assert_eq!(origin_note(&ll.origin), None, "Entries in the layer file itself stay quiet");
+    }
+}
+
+// =========================================================
+// SECTION 19: PROJECT ALIASES (discovery, --allow, --enter/--leave)
+// =========================================================
+#[cfg(test)]
+mod project_tests {
+    use alias_lib::project::{load_entered, retrust_after_write};
+    use super::apply_tests::FlakyProvider;
+    use alias_lib::{allow_project, enter_project, find_project_file, leave_project, parse_arguments, trust_state, AliasAction, AliasProvider,
+        MemoryProvider, ProjectState, TrustState, Verbosity, PROJECT_ALIAS_FILENAME};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn ram(provider: &MemoryProvider) -> Vec<(String, String)> {
+        let mut list = provider.get_all_aliases(&Verbosity::mute()).unwrap();
+        list.sort();
+        list
+    }
+
+    #[test]
+    fn test_find_project_file_walks_up() {
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("repo").join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_project_file(&nested), None);
+
+        let project = dir.path().join("repo").join(PROJECT_ALIAS_FILENAME);
+        fs::write(&project, "b=cargo build\n").unwrap();
+        assert_eq!(find_project_file(&nested), Some(project.clone()));
+        assert_eq!(find_project_file(&dir.path().join("repo")), Some(project));
+    }
+
+    #[test]
+    fn test_trust_follows_content_including_includes() {
+        let dir = tempdir().unwrap();
+        let state = ProjectState::for_user(&dir.path().join("aliases.doskey"));
+        let project = dir.path().join(PROJECT_ALIAS_FILENAME);
+        fs::write(dir.path().join("extra.doskey"), "x=1\n").unwrap();
+        fs::write(&project, "b=cargo build\n#include extra.doskey\n").unwrap();
+        assert_eq!(trust_state(&state, &project), TrustState::Unknown);
+
+        let hash = allow_project(&Verbosity::mute(), &state, &project).unwrap();
+        assert_eq!(hash.len(), 64);
+        assert_eq!(trust_state(&state, &project), TrustState::Trusted);
+
+        fs::write(dir.path().join("extra.doskey"), "x=format c:\n").unwrap();
+        assert_eq!(trust_state(&state, &project), TrustState::Changed, "An included file is part of what was allowed");
+
+        // Re-allowing replaces the old record rather than adding a second one
+        allow_project(&Verbosity::mute(), &state, &project).unwrap();
+        assert_eq!(fs::read_to_string(&state.trust).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn test_retrust_only_keeps_trust_that_existed() {
+        let dir = tempdir().unwrap();
+        let state = ProjectState::for_user(&dir.path().join("aliases.doskey"));
+        let project = dir.path().join(PROJECT_ALIAS_FILENAME);
+
+        // A file this tool created is ours
+        fs::write(&project, "b=cargo build\n").unwrap();
+        retrust_after_write(&Verbosity::mute(), &state, &project, None);
+        assert_eq!(trust_state(&state, &project), TrustState::Trusted);
+
+        // A trusted file edited by this tool stays trusted
+        let before = alias_lib::project::project_hash(&project).unwrap();
+        fs::write(&project, "b=cargo build --release\n").unwrap();
+        retrust_after_write(&Verbosity::mute(), &state, &project, Some(&before));
+        assert_eq!(trust_state(&state, &project), TrustState::Trusted);
+
+        // But a write on top of a foreign edit does not launder it
+        fs::write(&project, "b=evil\n").unwrap();
+        let before = alias_lib::project::project_hash(&project).unwrap();
+        fs::write(&project, "b=evil\nc=more\n").unwrap();
+        retrust_after_write(&Verbosity::mute(), &state, &project, Some(&before));
+        assert_eq!(trust_state(&state, &project), TrustState::Changed);
+    }
+
+    #[test]
+    fn test_enter_and_leave_restore_shadowed_values() {
+        let dir = tempdir().unwrap();
+        let state = ProjectState::for_user(&dir.path().join("aliases.doskey"));
+        let project = dir.path().join(PROJECT_ALIAS_FILENAME);
+        fs::write(&project, "gs=git status --short\nb=cargo build\n").unwrap();
+        let provider = MemoryProvider::seeded(&[("gs", "git status"), ("ll", "dir")]);
+        let before = ram(&provider);
+
+        let err = enter_project(&provider, &Verbosity::mute(), &state, &project).unwrap_err();
+        assert!(err.to_string().contains("not trusted"), "{}", err);
+        assert_eq!(ram(&provider), before, "An untrusted project must not touch RAM");
+
+        allow_project(&Verbosity::mute(), &state, &project).unwrap();
+        assert_eq!(enter_project(&provider, &Verbosity::mute(), &state, &project).unwrap(), 2);
+        assert_eq!(enter_project(&provider, &Verbosity::mute(), &state, &project).unwrap(), 0, "Entering twice is a no-op");
+        assert_eq!(ram(&provider), vec![
+            ("b".to_string(), "cargo build".to_string()),
+            ("gs".to_string(), "git status --short".to_string()),
+            ("ll".to_string(), "dir".to_string()),
+        ]);
+        assert_eq!(load_entered(&state).unwrap().len(), 2);
+
+        assert!(leave_project(&provider, &Verbosity::mute(), &state).unwrap().is_some());
+        assert_eq!(ram(&provider), before);
+        assert!(!state.entered.exists(), "The enter stack is gone once empty");
+        assert_eq!(leave_project(&provider, &Verbosity::mute(), &state).unwrap(), None);
+    }
+
+    #[test]
+    fn test_allow_only_takes_an_existing_path() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join(PROJECT_ALIAS_FILENAME);
+        fs::write(&project, "b=cargo build\n").unwrap();
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let (queue, _) = parse_arguments(&args(&["alias", "--allow", project.to_str().unwrap()]));
+        assert_eq!(queue.tasks[0].action, AliasAction::Allow(project.to_string_lossy().to_string()));
+
+        let (queue, _) = parse_arguments(&args(&["alias", "--allow", "gs=git status"]));
+        let actions: Vec<&AliasAction> = queue.tasks.iter().map(|t| &t.action).collect();
+        assert_eq!(actions[0], &AliasAction::Allow(String::new()));
+        assert!(matches!(actions[1], AliasAction::Set(opts) if opts.name == "gs"));
+    }
+
+    #[test]
+    fn test_enter_is_all_or_nothing() {
+        let dir = tempdir().unwrap();
+        let state = ProjectState::for_user(&dir.path().join("aliases.doskey"));
+        let project = dir.path().join(PROJECT_ALIAS_FILENAME);
+        fs::write(&project, "gs=git status --short\nb=cargo build\nt=cargo test\n").unwrap();
+        allow_project(&Verbosity::mute(), &state, &project).unwrap();
+        let flaky = FlakyProvider { inner: MemoryProvider::seeded(&[("gs", "git status")]), refuse: "t", declines: false };
+
+        assert!(enter_project(&flaky, &Verbosity::mute(), &state, &project).is_err());
+        assert_eq!(flaky.inner.get_all_aliases(&Verbosity::mute()).unwrap(), vec![("gs".to_string(), "git status".to_string())],
+            "Macros applied before the failure are put back");
+        assert!(load_entered(&state).unwrap().is_empty());
+
+        let declining = FlakyProvider { inner: MemoryProvider::seeded(&[("gs", "git status")]), refuse: "t", declines: true };
+        assert!(enter_project(&declining, &Verbosity::mute(), &state, &project).is_err(), "A declined set is a failure too");
+        assert_eq!(declining.inner.get_all_aliases(&Verbosity::mute()).unwrap(), vec![("gs".to_string(), "git status".to_string())]);
+        assert!(load_entered(&state).unwrap().is_empty(), "Nothing the console declined is recorded as entered");
+    }
+
+    #[test]
+    fn test_enter_and_leave_are_refused_in_setup() {
+        for flag in ["--enter", "--leave"] {
+            let (queue, voice) = parse_arguments(&[String::from("alias"), "--setup".into(), flag.into()]);
+            assert!(voice.in_setup);
+            assert_eq!(queue.tasks.iter().map(|t| t.action.clone()).collect::<Vec<_>>(), vec![AliasAction::Invalid], "{}", flag);
+        }
+    }
+}
+
+// =========================================================
+// SECTION 20: TEMPLATES (${...} placeholders, [if] guards)
+// =========================================================
+#[cfg(test)]
+mod template_tests {
+    use alias_lib::{expand_includes, expand_placeholders, flatten_macro_file, parse_arguments, parse_guard, AliasAction, FlatMacroFile, Guard,
+        GuardLine, GuardTest, Verbosity, ENV_ALIAS_CONFIG, ENV_ALIAS_EXPAND};
+    use serial_test::serial;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn values(path: &Path) -> Vec<String> {
+        expand_includes(path, &Verbosity::mute()).unwrap().into_iter().map(|p| format!("{}={}", p.name, p.value)).collect()
+    }
+
+    #[test]
+    #[serial]
+    fn test_placeholders_resolve_and_leave_doskey_tokens_alone() {
+        unsafe { std::env::set_var("ALIAS_TEST_TOOLS", "C:\\tools"); }
+        let dir = Path::new("C:\\shared");
+        let (out, unresolved) = expand_placeholders("${env:ALIAS_TEST_TOOLS}\\ncd.exe $1 $* $T echo $$ $${home} ${alias_dir}", dir);
+        unsafe { std::env::remove_var("ALIAS_TEST_TOOLS"); }
+        assert_eq!(out, "C:\\tools\\ncd.exe $1 $* $T echo $$ $${home} C:\\shared");
+        assert!(unresolved.is_empty());
+
+        let (out, unresolved) = expand_placeholders("${env:ALIAS_TEST_SURELY_UNSET} ${nope} ${env:OPEN", dir);
+        assert_eq!(out, "${env:ALIAS_TEST_SURELY_UNSET} ${nope} ${env:OPEN", "Unknown placeholders stay as written");
+        assert_eq!(unresolved, vec!["${env:ALIAS_TEST_SURELY_UNSET}".to_string(), "${nope}".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_guard_forms() {
+        let guard = |test, arg: &str, negated| Some(GuardLine::If(Guard { test, arg: arg.to_string(), negated }));
+        assert_eq!(parse_guard("[if host=BUILD01]"), guard(GuardTest::Host, "BUILD01", false));
+        assert_eq!(parse_guard("  [IF exists=\"C:\\tools\\ncd.exe\"] "), guard(GuardTest::Exists, "C:\\tools\\ncd.exe", false));
+        assert_eq!(parse_guard("[if host != BUILD01]"), guard(GuardTest::Host, "BUILD01", true));
+        assert_eq!(parse_guard("[end]"), Some(GuardLine::End));
+        assert!(matches!(parse_guard("[if os=windows]"), Some(GuardLine::Malformed(_))));
+        assert_eq!(parse_guard("[git]"), None, "Other bracket headers are not guards");
+        assert_eq!(parse_guard("[iffy=1]"), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_guards_select_lines_and_nest() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("ncd.exe"), "").unwrap();
+        let file = dir.path().join("aliases.doskey");
+        fs::write(&file, concat!(
+            "all=yes\n",
+            "[if exists=${alias_dir}/ncd.exe]\n",
+            "ncd=${alias_dir}/ncd.exe $*\n",
+            "[if env=ALIAS_TEST_GUARD]\n",
+            "ci=only on ci\n",
+            "[end]\n",
+            "[end]\n",
+            "[if exists=${alias_dir}/missing.exe]\n",
+            "missing=never\n",
+            "[end]\n",
+            "[if host!=no-such-host-anywhere]\n",
+            "anyhost=yes\n",
+            "[end]\n",
+        )).unwrap();
+        let ncd = format!("ncd={}/ncd.exe $*", dir.path().display());
+
+        unsafe { std::env::set_var(ENV_ALIAS_EXPAND, "on"); }
+        let plain = values(&file);
+        unsafe { std::env::set_var("ALIAS_TEST_GUARD", "1"); }
+        let with_ci = values(&file);
+        unsafe { std::env::remove_var("ALIAS_TEST_GUARD"); }
+        unsafe { std::env::remove_var(ENV_ALIAS_EXPAND); }
+        assert_eq!(plain, vec!["all=yes".to_string(), ncd.clone(), "anyhost=yes".to_string()]);
+        assert_eq!(with_ci, vec!["all=yes".to_string(), ncd, "ci=only on ci".to_string(), "anyhost=yes".to_string()]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_expand_off_keeps_values_verbatim_but_honours_guards() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("aliases.doskey");
+        fs::write(&file, "home=cd ${home}\n[if env=ALIAS_TEST_SURELY_UNSET]\nx=1\n[end]\n").unwrap();
+        unsafe { std::env::set_var(ENV_ALIAS_EXPAND, "off"); }
+        let loaded = values(&file);
+        unsafe { std::env::remove_var(ENV_ALIAS_EXPAND); }
+        assert_eq!(loaded, vec!["home=cd ${home}".to_string()]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_expand_is_opt_in() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("aliases.doskey");
+        fs::write(&file, "pp=powershell -c \"echo ${env:PATH}\"\n").unwrap();
+        unsafe {
+            std::env::remove_var(ENV_ALIAS_EXPAND);
+            std::env::set_var(ENV_ALIAS_CONFIG, dir.path().join("no-such-alias.toml"));
+        }
+        let loaded = values(&file);
+        unsafe { std::env::remove_var(ENV_ALIAS_CONFIG); }
+        assert_eq!(loaded, vec!["pp=powershell -c \"echo ${env:PATH}\"".to_string()], "Unset means verbatim");
+    }
+
+    #[test]
+    fn test_bad_guard_is_an_error_and_guards_force_flattening() {
+        let dir = tempdir().unwrap();
+        let bad = dir.path().join("bad.doskey");
+        fs::write(&bad, "x=1\n[if os=windows]\n").unwrap();
+        let err = expand_includes(&bad, &Verbosity::mute()).unwrap_err().to_string();
+        assert!(err.contains("bad.doskey:2"), "{}", err);
+
+        // doskey.exe would load "[if host" as a macro, so guarded files are flattened
+        let guarded = dir.path().join("guarded.doskey");
+        fs::write(&guarded, "[if env=ALIAS_TEST_SURELY_UNSET]\nx=1\n[end]\ny=2\n").unwrap();
+        let flat = flatten_macro_file(&Verbosity::mute(), &guarded).unwrap();
+        assert!(matches!(flat, FlatMacroFile::Temp(_)));
+        assert_eq!(fs::read_to_string(flat.path()).unwrap(), "y=2\r\n");
+    }
+
+    #[test]
+    fn test_expand_file_takes_an_optional_existing_path() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("shared.doskey");
+        fs::write(&file, "x=1\n").unwrap();
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let (queue, _) = parse_arguments(&args(&["alias", "--expand-file", file.to_str().unwrap()]));
+        assert_eq!(queue.tasks[0].action, AliasAction::ExpandFile(file.to_string_lossy().to_string()));
+        let (queue, _) = parse_arguments(&args(&["alias", "--expand-file"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::ExpandFile(String::new()));
+    }
+}
+
+// =========================================================
+// SECTION 21: GROUPS ([name] sections, --enable-group/--disable-group)
+// =========================================================
+#[cfg(test)]
+mod group_tests {
+    use alias_lib::groups::group_state_path;
+    use alias_lib::{disabled_groups, expand_includes, flatten_macro_file, group_header, parse_arguments, parse_macro_file, set_group_enabled,
+        AliasAction, AliasProvider, FlatMacroFile, MemoryProvider, Verbosity, ENV_ALIAS_TEAM_FILE};
+    use serial_test::serial;
+    use std::fs;
+    use tempfile::tempdir;
+    use super::apply_tests::FlakyProvider;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn ram(provider: &MemoryProvider) -> Vec<(String, String)> {
+        let mut list = provider.get_all_aliases(&Verbosity::mute()).unwrap();
+        list.sort();
+        list
+    }
+
+    #[test]
+    fn test_group_header_forms() {
+        assert_eq!(group_header("[git]"), Some("git".to_string()));
+        assert_eq!(group_header("  [ Docker ] "), Some("docker".to_string()));
+        assert_eq!(group_header("[]"), Some(String::new()), "[] returns to ungrouped");
+        assert_eq!(group_header("[if env=CI]"), None, "Guards are not sections");
+        assert_eq!(group_header("[end]"), None);
+        assert_eq!(group_header("[not a group]"), None);
+        assert_eq!(group_header("gs=[git]"), None);
+    }
+
+    #[test]
+    fn test_sections_tag_entries_and_includes_inherit() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("extra.doskey"), "gl=git log\n").unwrap();
+        let file = dir.path().join("aliases.doskey");
+        fs::write(&file, "ll=dir\n[git]\ngs=git status\n#include extra.doskey\n[docker]\ndps=docker ps\n[]\nx=1\n").unwrap();
+
+        let tagged: Vec<(String, Option<String>)> = expand_includes(&file, &Verbosity::mute()).unwrap()
+            .into_iter().map(|p| (p.name, p.source.group)).collect();
+        assert_eq!(tagged, vec![
+            ("ll".to_string(), None),
+            ("gs".to_string(), Some("git".to_string())),
+            ("gl".to_string(), Some("git".to_string())),
+            ("dps".to_string(), Some("docker".to_string())),
+            ("x".to_string(), None),
+        ]);
+    }
+
+    #[test]
+    fn test_disable_and_enable_group_touch_ram_only() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("aliases.doskey");
+        let content = "ll=dir\n[git]\ngs=git status\ngl=git log\n[docker]\ndps=docker ps\n";
+        fs::write(&file, content).unwrap();
+        let mem = MemoryProvider::seeded(&[("ll", "dir"), ("gs", "git status"), ("gl", "git log"), ("dps", "docker ps")]);
+
+        assert_eq!(set_group_enabled(&mem, &Verbosity::mute(), &file, "GIT", false).unwrap(), 2);
+        assert_eq!(ram(&mem), vec![("dps".to_string(), "docker ps".to_string()), ("ll".to_string(), "dir".to_string())]);
+        assert_eq!(fs::read_to_string(&file).unwrap(), content, "The file is never edited");
+        assert_eq!(disabled_groups(&file), vec!["git".to_string()]);
+
+        // Remembered for the next --startup / reload
+        let names: Vec<String> = parse_macro_file(&file, &Verbosity::mute()).unwrap().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["ll".to_string(), "dps".to_string()]);
+
+        assert_eq!(set_group_enabled(&mem, &Verbosity::mute(), &file, "git", true).unwrap(), 2);
+        assert_eq!(ram(&mem).len(), 4);
+        assert!(disabled_groups(&file).is_empty());
+        assert!(!group_state_path(&file).exists(), "Nothing disabled, no state file");
+    }
+
+    #[test]
+    fn test_group_state_is_saved_only_once_ram_took_it() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("aliases.doskey");
+        fs::write(&file, "ll=dir\n[git]\ngs=git status\ngl=git log\n").unwrap();
+        let seeded = [("gl", "git log"), ("gs", "git status"), ("ll", "dir")];
+
+        for declines in [false, true] {
+            let flaky = FlakyProvider { inner: MemoryProvider::seeded(&seeded), refuse: "gl", declines };
+            assert!(set_group_enabled(&flaky, &Verbosity::mute(), &file, "git", false).is_err());
+            assert_eq!(ram(&flaky.inner).len(), 3, "declines={}: 'gs' is put back", declines);
+            assert!(disabled_groups(&file).is_empty(), "declines={}: the group is not remembered as disabled", declines);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_disable_group_falls_back_to_the_next_winner() {
+        let dir = tempdir().unwrap();
+        let team = dir.path().join("team.doskey");
+        let file = dir.path().join("aliases.doskey");
+        fs::write(&team, "gl=git log --team\n").unwrap();
+        fs::write(&file, "gs=git st\n[git]\nGS=git status\ngl=git log\n").unwrap();
+        unsafe { std::env::set_var(ENV_ALIAS_TEAM_FILE, &team); }
+        let mem = MemoryProvider::seeded(&[("gs", "git status"), ("gl", "git log")]);
+
+        set_group_enabled(&mem, &Verbosity::mute(), &file, "git", false).unwrap();
+        let disabled = ram(&mem);
+        set_group_enabled(&mem, &Verbosity::mute(), &file, "git", true).unwrap();
+        let enabled = ram(&mem);
+        unsafe { std::env::remove_var(ENV_ALIAS_TEAM_FILE); }
+
+        assert_eq!(disabled, vec![("gl".to_string(), "git log --team".to_string()), ("gs".to_string(), "git st".to_string())],
+            "The ungrouped and the team definitions take over again");
+        assert_eq!(enabled, vec![("GS".to_string(), "git status".to_string()), ("gl".to_string(), "git log".to_string())], "The winner keeps its spelling");
+    }
+
+    #[test]
+    fn test_unknown_group_names_the_known_ones() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("aliases.doskey");
+        fs::write(&file, "[git]\ngs=git status\n").unwrap();
+        let mem = MemoryProvider::new();
+        let err = set_group_enabled(&mem, &Verbosity::mute(), &file, "docker", false).unwrap_err().to_string();
+        assert!(err.contains("[docker]") && err.contains("git"), "{}", err);
+        assert!(disabled_groups(&file).is_empty());
+    }
+
+    #[test]
+    fn test_disabled_group_forces_flattening() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("aliases.doskey");
+        fs::write(&file, "ll=dir\n[git]\ngs=git status\n").unwrap();
+        assert!(matches!(flatten_macro_file(&Verbosity::mute(), &file).unwrap(), FlatMacroFile::Borrowed(_)));
+
+        set_group_enabled(&MemoryProvider::new(), &Verbosity::mute(), &file, "git", false).unwrap();
+        let flat = flatten_macro_file(&Verbosity::mute(), &file).unwrap();
+        assert!(matches!(flat, FlatMacroFile::Temp(_)));
+        assert_eq!(fs::read_to_string(flat.path()).unwrap(), "ll=dir\r\n");
+    }
+
+    #[test]
+    fn test_parse_group_switches() {
+        let (queue, voice) = parse_arguments(&args(&["alias", "--show-all", "--group", "Git"]));
+        assert_eq!(voice.group.as_deref(), Some("git"));
+        assert!(queue.tasks.iter().all(|t| !matches!(t.action, AliasAction::Group(_))));
+
+        let (queue, _) = parse_arguments(&args(&["alias", "--disable-group", "docker"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::DisableGroup("docker".into()));
+        let (queue, _) = parse_arguments(&args(&["alias", "--enable-group=git"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::EnableGroup("git".into()));
+        let (queue, _) = parse_arguments(&args(&["alias", "--enable-group"]));
+        assert!(queue.tasks.iter().any(|t| t.action == AliasAction::Fail));
+    }
+}
+
+// =========================================================
+// SECTION 22: BATCH APPLY (--apply, all-or-nothing)
+// =========================================================
+#[cfg(test)]
+mod apply_tests {
+    use alias_lib::{apply_batch, dispatch_with, list_backups, load_journal, parse_arguments, parse_batch, AliasAction, AliasProvider, AuditReport, BatchOp,
+        BatchSummary, MemoryProvider, PurgeReport, SetOptions, Task, Verbosity, Versioning};
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn op(line: usize, name: &str, value: Option<&str>) -> BatchOp {
+        BatchOp { line, name: name.to_string(), value: value.map(str::to_string) }
+    }
+
+    fn ram(provider: &dyn AliasProvider) -> Vec<(String, String)> {
+        let mut list = provider.get_all_aliases(&Verbosity::mute()).unwrap();
+        list.sort();
+        list
+    }
+
+    /// A session whose RAM refuses one alias, to drive the rollback path. With `declines` it
+    /// answers Ok(false) the way doskey.exe's non-zero exit does, rather than an error.
+    pub(super) struct FlakyProvider {
+        pub(super) inner: MemoryProvider,
+        pub(super) refuse: &'static str,
+        pub(super) declines: bool,
+    }
+
+    impl AliasProvider for FlakyProvider {
+        fn raw_set_macro(&self, name: &str, value: Option<&str>) -> io::Result<bool> {
+            if name == self.refuse && self.declines { return Ok(false); }
+            if name == self.refuse { return Err(io::Error::other("console refused the macro")); }
+            self.inner.raw_set_macro(name, value)
+        }
+        fn raw_reload_from_file(&self, v: &Verbosity, path: &Path) -> io::Result<()> { self.inner.raw_reload_from_file(v, path) }
+        fn get_all_aliases(&self, v: &Verbosity) -> io::Result<Vec<(String, String)>> { self.inner.get_all_aliases(v) }
+        fn write_autorun_registry(&self, cmd: &str, v: &Verbosity) -> io::Result<()> { self.inner.write_autorun_registry(cmd, v) }
+        fn read_autorun_registry(&self) -> String { self.inner.read_autorun_registry() }
+        fn purge_ram_macros(&self, v: &Verbosity) -> io::Result<PurgeReport> { self.inner.purge_ram_macros(v) }
+        fn query_alias(&self, name: &str, v: &Verbosity) -> Vec<String> { self.inner.query_alias(name, v) }
+        fn set_alias(&self, opts: SetOptions, path: &Path, v: &Verbosity) -> io::Result<()> { self.inner.set_alias(opts, path, v) }
+        fn run_diagnostics(&self, path: &Path, v: &Verbosity) -> Result<(), Box<dyn std::error::Error>> { self.inner.run_diagnostics(path, v) }
+        fn alias_show_all(&self, v: &Verbosity) -> Result<AuditReport, Box<dyn std::error::Error>> { self.inner.alias_show_all(v) }
+        fn get_version(&self) -> &'static Versioning { self.inner.get_version() }
+    }
+
+    #[test]
+    fn test_parse_batch_forms() {
+        let ops = parse_batch("# team defaults\ngs=git status\n\nGL=git log  \nll=\n--remove dps\n; done\n").unwrap();
+        assert_eq!(ops, vec![
+            op(2, "gs", Some("git status")),
+            op(4, "gl", Some("git log  ")),
+            op(5, "ll", None),
+            op(6, "dps", None),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_batch_reports_every_bad_line() {
+        let errors = parse_batch("gs=git status\nnot an op\n1bad=x\n--remove\ngs=git status -sb\n").unwrap_err();
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(errors[0].starts_with("line 2:"));
+        assert!(errors[1].contains("'1bad'"));
+        assert!(errors[2].contains("--remove needs a name"));
+        assert!(errors[3].contains("already appears on line 1"));
+    }
+
+    #[test]
+    fn test_apply_batch_one_write_and_summary() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        fs::write(&path, "; mine\ngs=git status\nll=dir\nx=exit\n").unwrap();
+        let mem = MemoryProvider::seeded(&[("gs", "git status"), ("ll", "dir"), ("x", "exit")]);
+
+        let ops = parse_batch("gs=git status -sb\nll=\ngd=git diff\nx=exit\n--remove nothere\n").unwrap();
+        let summary = apply_batch(&mem, &Verbosity::mute(), &ops, &path).unwrap();
+        assert_eq!(summary, BatchSummary { added: 1, changed: 1, removed: 1, unchanged: 2 });
+        assert_eq!(fs::read_to_string(&path).unwrap(), "; mine\ngs=git status -sb\nx=exit\ngd=git diff\n");
+        assert_eq!(ram(&mem), vec![
+            ("gd".to_string(), "git diff".to_string()),
+            ("gs".to_string(), "git status -sb".to_string()),
+            ("x".to_string(), "exit".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_batch_refuses_values_doskey_would_mangle() {
+        let errors = parse_batch("gs=git status\nx=echo $0\nq=echo \"open\n").unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].starts_with("line 2: 'x':") && errors[0].contains("$0"), "{}", errors[0]);
+        assert!(errors[1].starts_with("line 3: 'q':") && errors[1].contains("unbalanced quote"), "{}", errors[1]);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        let mem = MemoryProvider::new();
+        assert!(apply_batch(&mem, &Verbosity::mute(), &[op(1, "gs", Some("git status")), op(2, "x", Some("echo $0"))], &path).is_err());
+        assert!(!path.exists() && ram(&mem).is_empty(), "A hand-built batch is checked before anything is touched");
+    }
+
+    #[test]
+    fn test_apply_batch_injects_what_ram_is_missing() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        fs::write(&path, "gs=git status\nll=dir\n").unwrap();
+        let mem = MemoryProvider::seeded(&[("ll", "dir"), ("x", "exit")]);
+
+        let ops = parse_batch("gs=git status\nll=dir\nx=\n").unwrap();
+        let summary = apply_batch(&mem, &Verbosity::mute(), &ops, &path).unwrap();
+        assert_eq!(summary, BatchSummary { added: 0, changed: 1, removed: 1, unchanged: 1 });
+        assert_eq!(fs::read_to_string(&path).unwrap(), "gs=git status\nll=dir\n", "The file already agreed");
+        assert_eq!(ram(&mem), vec![("gs".to_string(), "git status".to_string()), ("ll".to_string(), "dir".to_string())]);
+    }
+
+    #[test]
+    fn test_apply_batch_rolls_back_on_ram_failure() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        let content = "gs=git status\nll=dir\n";
+        fs::write(&path, content).unwrap();
+        let flaky = FlakyProvider { inner: MemoryProvider::seeded(&[("gs", "git status"), ("ll", "dir")]), refuse: "zz", declines: false };
+        let before = ram(&flaky);
+
+        let ops = vec![op(1, "gs", Some("git status -sb")), op(2, "ll", None), op(3, "zz", Some("boom"))];
+        let err = apply_batch(&flaky, &Verbosity::mute(), &ops, &path).unwrap_err().to_string();
+        assert!(err.contains("'zz'") && err.contains("line 3"), "{}", err);
+        assert_eq!(fs::read_to_string(&path).unwrap(), content, "The file is put back");
+        assert_eq!(ram(&flaky), before, "Macros injected before the failure are put back");
+
+        // Ok(false) is a refusal too
+        let declining = FlakyProvider { inner: MemoryProvider::seeded(&[("gs", "git status"), ("ll", "dir")]), refuse: "zz", declines: true };
+        let err = apply_batch(&declining, &Verbosity::mute(), &ops, &path).unwrap_err().to_string();
+        assert!(err.contains("'zz'"), "{}", err);
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+        assert_eq!(ram(&declining), before);
+
+        // A batch against a file that did not exist leaves no file behind
+        let fresh = dir.path().join("fresh.doskey");
+        assert!(apply_batch(&flaky, &Verbosity::mute(), &ops, &fresh).is_err());
+        assert!(!fresh.exists());
+    }
+
+    #[test]
+    fn test_apply_dispatch_is_one_journal_entry_and_rejects_bad_batches() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        let changes = dir.path().join("changes.txt");
+        let mem = MemoryProvider::new();
+        let run = |action| dispatch_with(&mem, Task { action, path: path.to_path_buf(), args: Vec::new() }, &Verbosity::mute());
+
+        fs::write(&changes, "gs=git status\nbad name=x\n").unwrap();
+        assert!(run(AliasAction::Apply(changes.to_string_lossy().to_string())).is_err());
+        assert!(!path.exists(), "Validation failed, nothing applied");
+
+        fs::write(&changes, "gs=git status\ngl=git log\n").unwrap();
+        run(AliasAction::Apply(changes.to_string_lossy().to_string())).unwrap();
+        let txns = load_journal(&path).unwrap();
+        assert_eq!(txns.len(), 1);
+        assert_eq!(txns[0].changes.len(), 4, "Two file and two RAM changes in one transaction");
+
+        run(AliasAction::Undo(1)).unwrap();
+        assert!(ram(&mem).is_empty());
+    }
+
+    #[test]
+    fn test_undo_of_a_batch_is_one_write_and_all_or_nothing() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        let changes = dir.path().join("changes.txt");
+        fs::write(&path, "keep=1\n").unwrap();
+        fs::write(&changes, "a=1\nb=2\nc=3\nd=4\ne=5\n").unwrap();
+        let mem = MemoryProvider::seeded(&[("keep", "1")]);
+        let task = |action| Task { action, path: path.to_path_buf(), args: Vec::new() };
+
+        dispatch_with(&mem, task(AliasAction::Apply(changes.to_string_lossy().to_string())), &Verbosity::mute()).unwrap();
+        let snapshots = list_backups(&path).unwrap().len();
+        dispatch_with(&mem, task(AliasAction::Undo(1)), &Verbosity::mute()).unwrap();
+        assert_eq!(list_backups(&path).unwrap().len(), snapshots + 1, "Five removals, one rewrite, one snapshot");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "keep=1\n");
+
+        // Redo where RAM refuses one macro: neither layer moves and the journal records nothing
+        let flaky = FlakyProvider { inner: MemoryProvider::seeded(&[("keep", "1")]), refuse: "c", declines: false };
+        let journal = load_journal(&path).unwrap().len();
+        assert!(dispatch_with(&flaky, task(AliasAction::Redo), &Verbosity::mute()).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "keep=1\n");
+        assert_eq!(ram(&flaky), vec![("keep".to_string(), "1".to_string())]);
+        assert_eq!(load_journal(&path).unwrap().len(), journal);
+
+        // A console that declines (Ok(false)) is refused the same way
+        let declining = FlakyProvider { inner: MemoryProvider::seeded(&[("keep", "1")]), refuse: "c", declines: true };
+        assert!(dispatch_with(&declining, task(AliasAction::Redo), &Verbosity::mute()).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "keep=1\n");
+        assert_eq!(ram(&declining), vec![("keep".to_string(), "1".to_string())]);
+        assert_eq!(load_journal(&path).unwrap().len(), journal);
+    }
+
+    #[test]
+    fn test_apply_takes_an_optional_source() {
+        let dir = tempdir().unwrap();
+        let changes = dir.path().join("changes.txt");
+        fs::write(&changes, "x=1\n").unwrap();
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let (queue, _) = parse_arguments(&args(&["alias", "--apply", changes.to_str().unwrap()]));
+        assert_eq!(queue.tasks[0].action, AliasAction::Apply(changes.to_string_lossy().to_string()));
+        let (queue, _) = parse_arguments(&args(&["alias", "--apply", "-"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::Apply("-".into()));
+        let (queue, _) = parse_arguments(&args(&["alias", "--apply"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::Apply(String::new()));
+    }
+}
+
+// =========================================================
+// SECTION 23: ENCODINGS (BOM, UTF-16, CP437, newlines)
+// =========================================================
+#[cfg(test)]
+mod encoding_tests {
+    use alias_lib::{dispatch_with, flatten_macro_file, parse_arguments, parse_macro_file, read_alias_file, update_disk_file, AliasAction,
+        AliasDocument, AliasEncoding, FlatMacroFile, MemoryProvider, Task, Verbosity};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn utf16le(text: &str, bom: bool) -> Vec<u8> {
+        let body = text.encode_utf16().flat_map(u16::to_le_bytes);
+        if bom { [0xFF, 0xFE].into_iter().chain(body).collect() } else { body.collect() }
+    }
+
+    /// Writes `bytes`, sets one alias through the normal path and returns what is on disk after.
+    fn edit_round_trip(bytes: &[u8]) -> (Vec<u8>, String, AliasEncoding) {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        fs::write(&path, bytes).unwrap();
+        update_disk_file(&Verbosity::mute(), "gd", "git diff", &path).unwrap();
+        let after = fs::read(&path).unwrap();
+        let (text, encoding) = read_alias_file(&path).unwrap();
+        (after, text, encoding)
+    }
+
+    #[test]
+    fn test_detect_each_variant() {
+        assert_eq!(AliasEncoding::detect(b"gs=git status\r\n"), AliasEncoding::Utf8);
+        assert_eq!(AliasEncoding::detect("gs=git status ✓\n".as_bytes()), AliasEncoding::Utf8);
+        assert_eq!(AliasEncoding::detect(b"\xEF\xBB\xBFgs=git status\n"), AliasEncoding::Utf8Bom);
+        assert_eq!(AliasEncoding::detect(&utf16le("gs=git status\r\n", true)), AliasEncoding::Utf16Le);
+        assert_eq!(AliasEncoding::detect(&utf16le("gs=git status\r\n", false)), AliasEncoding::Utf16Le);
+        let be: Vec<u8> = [0xFE, 0xFF].into_iter().chain("gs=git status\n".encode_utf16().flat_map(u16::to_be_bytes)).collect();
+        assert_eq!(AliasEncoding::detect(&be), AliasEncoding::Utf16Be);
+        assert_eq!(AliasEncoding::detect(b"cafe=echo caf\x82\r\n"), AliasEncoding::Cp437, "Not valid UTF-8: read as CP437");
+        assert_eq!(AliasEncoding::detect(b""), AliasEncoding::Utf8);
+    }
+
+    #[test]
+    fn test_decode_encode_round_trip_every_variant() {
+        let text = "; caf\u{e9}\r\ngs=git status\r\n";
+        for encoding in AliasEncoding::ALL {
+            let (bytes, lost) = encoding.encode(text);
+            assert_eq!(lost, 0, "{}", encoding);
+            assert_eq!(AliasEncoding::detect(&bytes), encoding);
+            assert_eq!(encoding.decode(&bytes), text, "{}", encoding);
+            assert_eq!(encoding.to_string().parse::<AliasEncoding>(), Ok(encoding));
+        }
+        let (bytes, lost) = AliasEncoding::Cp437.encode("x=echo \u{2713}");
+        assert_eq!((bytes, lost), (b"x=echo ?".to_vec(), 1), "Characters CP437 cannot hold are counted");
+        assert!("oem".parse::<AliasEncoding>().is_err(), "The console code page varies; only 437 is spelled out");
+    }
+
+    #[test]
+    fn test_edit_keeps_utf8_bom_and_crlf() {
+        let (after, text, encoding) = edit_round_trip(b"\xEF\xBB\xBFgs=git status\r\n");
+        assert_eq!(encoding, AliasEncoding::Utf8Bom);
+        assert!(after.starts_with(&[0xEF, 0xBB, 0xBF]));
+        assert_eq!(text, "gs=git status\r\ngd=git diff\r\n");
+    }
+
+    #[test]
+    fn test_edit_keeps_utf16le_from_notepad() {
+        let (after, text, encoding) = edit_round_trip(&utf16le("; tools\r\ngs=git status\r\n", true));
+        assert_eq!(encoding, AliasEncoding::Utf16Le);
+        assert_eq!(after, utf16le("; tools\r\ngs=git status\r\ngd=git diff\r\n", true));
+        assert_eq!(text, "; tools\r\ngs=git status\r\ngd=git diff\r\n");
+    }
+
+    #[test]
+    fn test_edit_keeps_utf16be_and_missing_trailing_newline() {
+        let be = |t: &str| -> Vec<u8> { [0xFE, 0xFF].into_iter().chain(t.encode_utf16().flat_map(u16::to_be_bytes)).collect() };
+        let (after, _, encoding) = edit_round_trip(&be("gs=git status"));
+        assert_eq!(encoding, AliasEncoding::Utf16Be);
+        assert_eq!(after, be("gs=git status\ngd=git diff"));
+    }
+
+    #[test]
+    fn test_edit_keeps_cp437_bytes_and_lf() {
+        let (after, text, encoding) = edit_round_trip(b"caf=echo caf\x82\n");
+        assert_eq!(encoding, AliasEncoding::Cp437);
+        assert_eq!(after, b"caf=echo caf\x82\ngd=git diff\n".to_vec());
+        assert_eq!(text, "caf=echo caf\u{e9}\ngd=git diff\n");
+    }
+
+    #[test]
+    fn test_loading_utf16_file_yields_macros() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        fs::write(&path, utf16le("gs=git status\r\nll=dir\r\n", true)).unwrap();
+        assert_eq!(parse_macro_file(&path, &Verbosity::mute()).unwrap(), vec![
+            ("gs".to_string(), "git status".to_string()),
+            ("ll".to_string(), "dir".to_string()),
+        ]);
+        assert_eq!(AliasDocument::load(&path, &Verbosity::mute()).unwrap().encoding, AliasEncoding::Utf16Le);
+
+        // doskey.exe gets a plain copy
+        let flat = flatten_macro_file(&Verbosity::mute(), &path).unwrap();
+        assert!(matches!(flat, FlatMacroFile::Temp(_)));
+        assert_eq!(fs::read(flat.path()).unwrap(), b"gs=git status\r\nll=dir\r\n".to_vec());
+    }
+
+    #[test]
+    fn test_encoding_action_converts_explicitly() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        fs::write(&path, b"gs=git status\r\n").unwrap();
+        let run = |action| dispatch_with(&MemoryProvider::new(), Task { action, path: path.to_path_buf(), args: Vec::new() }, &Verbosity::mute());
+
+        run(AliasAction::Encoding(String::new())).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"gs=git status\r\n".to_vec(), "A bare --encoding only reports");
+        run(AliasAction::Encoding("utf16le".into())).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), utf16le("gs=git status\r\n", true));
+        assert!(run(AliasAction::Encoding("latin9".into())).is_err());
+
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let (queue, _) = parse_arguments(&args(&["alias", "--encoding", "UTF8-BOM"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::Encoding("utf8-bom".into()));
+        let (queue, _) = parse_arguments(&args(&["alias", "--encoding"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::Encoding(String::new()));
+    }
+
+    #[test]
+    fn test_utf16_without_bom_is_not_mistaken_for_text() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        fs::write(&path, utf16le("gs=git status\r\n", false)).unwrap();
+        let (text, encoding) = read_alias_file(&path).unwrap();
+        assert_eq!((text.as_str(), encoding), ("gs=git status\r\n", AliasEncoding::Utf16Le));
+    }
+}
+
+// =========================================================
+// SECTION 24: CONFIG FILE (alias.toml, precedence, --config)
+// =========================================================
+#[cfg(test)]
+mod config_tests {
+    use alias_lib::backup::backup_retention;
+    use alias_lib::config::{option_args, set_config_value};
+    use alias_lib::template::templates_enabled;
+    use alias_lib::{effective_settings, parse_arguments, AliasAction, AliasConfig, SettingSource, Verbosity, ENV_ALIAS_BACKUPS,
+        ENV_ALIAS_CONFIG, ENV_ALIAS_EXPAND, ENV_ALIAS_OPTS};
+    use serial_test::serial;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn value_of(config: &AliasConfig, cli: &[&str], key: &str) -> (String, SettingSource) {
+        let found = effective_settings(config, &args(cli)).into_iter().find(|s| s.setting.key == key).unwrap();
+        (found.value, found.source)
+    }
+
+    #[test]
+    fn test_parse_reads_toml_and_ini_spellings() {
+        let config = AliasConfig::parse(concat!(
+            "# defaults\n",
+            "[alias]\n",
+            "icons = off\n",
+            "format=\"JSON\"\n",
+            "editor = \"code --wait # not a comment\"\n",
+            "backups = 25   # keep more\n",
+            "icons = true\n",
+        ), None);
+        assert!(config.problems.is_empty(), "{:?}", config.problems);
+        assert_eq!(config.get("icons"), Some("true"), "The last line wins");
+        assert_eq!(config.get("format"), Some("json"));
+        assert_eq!(config.get("editor"), Some("code --wait # not a comment"));
+        assert_eq!(config.get("backups"), Some("25"));
+        assert_eq!(config.to_args(), args(&["--icons", "--format=json"]), "Only flag settings become switches, in table order");
+    }
+
+    #[test]
+    fn test_parse_reports_bad_lines() {
+        let config = AliasConfig::parse("colour = red\nicons = maybe\nbackups = -1\njunk\nscope = project\n", None);
+        assert_eq!(config.problems.len(), 4, "{:?}", config.problems);
+        assert!(config.problems[0].contains("unknown setting 'colour'"));
+        assert!(config.problems[1].starts_with("line 2:"));
+        assert_eq!(config.get("scope"), Some("project"));
+    }
+
+    #[test]
+    fn test_alias_opts_keeps_every_flag_setting_and_nothing_else() {
+        assert_eq!(option_args("--quiet --no-icons --format json --scope=project --remove gs --file x.doskey"),
+            args(&["--quiet", "--no-icons", "--format", "json", "--scope=project", "--file", "x.doskey"]));
+        assert_eq!(option_args("--format \"tsv\""), args(&["--format", "tsv"]));
+    }
+
+    #[test]
+    #[serial]
+    fn test_precedence_cli_env_config_default() {
+        let config = AliasConfig::parse("icons = false\nformat = tsv\n", Some(PathBuf::from("alias.toml")));
+        unsafe { std::env::set_var(ENV_ALIAS_OPTS, "--icons"); }
+        let cli = value_of(&config, &["--no-icons", "gs"], "icons");
+        let env = value_of(&config, &["gs"], "icons");
+        unsafe { std::env::remove_var(ENV_ALIAS_OPTS); }
+        let file = value_of(&config, &["gs"], "icons");
+        let default = value_of(&AliasConfig::default(), &["gs"], "icons");
+
+        assert_eq!(cli, ("false".to_string(), SettingSource::CommandLine));
+        assert_eq!(env, ("true".to_string(), SettingSource::Env(ENV_ALIAS_OPTS)));
+        assert_eq!(file, ("false".to_string(), SettingSource::Config(PathBuf::from("alias.toml"), 1)));
+        assert_eq!(default, ("true".to_string(), SettingSource::Default));
+        assert_eq!(value_of(&config, &["--format", "json"], "format").0, "json");
+        assert_eq!(value_of(&config, &["--", "--format=json"], "format").1, SettingSource::Config(PathBuf::from("alias.toml"), 2),
+            "Nothing after -- is a switch");
+    }
+
+    #[test]
+    fn test_modifiers_are_settings_too() {
+        let config = AliasConfig::parse("group = git\nsort = NAME\nfile = \"C:\\\\My Aliases\\\\work.doskey\"\n", None);
+        assert!(config.problems.is_empty(), "{:?}", config.problems);
+        assert_eq!(config.to_args(), args(&["--file=C:\\My Aliases\\work.doskey", "--group=git", "--sort=name"]));
+        assert_eq!(value_of(&config, &["--group", "docker", "--show-all"], "group"), ("docker".to_string(), SettingSource::CommandLine));
+        assert_eq!(value_of(&config, &["--sort=length"], "sort").0, "length");
+        assert_eq!(value_of(&AliasConfig::default(), &["--file", "x.doskey"], "file"), ("x.doskey".to_string(), SettingSource::CommandLine));
+        assert!(AliasConfig::parse("sort = random\n", None).problems[0].contains("sort expects"));
+    }
+
+    #[test]
+    fn test_file_takes_an_attached_path() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("work.doskey");
+        fs::write(&file, "gs=git status\n").unwrap();
+        let (queue, _) = parse_arguments(&args(&["alias", &format!("--file={}", file.display()), "--show-all"]));
+        assert!(queue.tasks.iter().all(|t| t.action != AliasAction::Fail && t.action != AliasAction::Invalid));
+        assert_eq!(queue.tasks.iter().find(|t| t.action == AliasAction::ShowAll).map(|t| t.path.clone()), Some(file),
+            "--file=path is what alias.toml hands over");
+    }
+
+    #[test]
+    #[serial]
+    fn test_settings_read_at_point_of_use_follow_the_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("alias.toml");
+        fs::write(&file, "backups = 3\nexpand = no\n").unwrap();
+        unsafe {
+            std::env::set_var(ENV_ALIAS_CONFIG, &file);
+            std::env::remove_var(ENV_ALIAS_BACKUPS);
+            std::env::remove_var(ENV_ALIAS_EXPAND);
+        }
+        let from_file = (backup_retention(), templates_enabled());
+        unsafe { std::env::set_var(ENV_ALIAS_BACKUPS, "7"); }
+        let env_wins = backup_retention();
+        unsafe {
+            std::env::remove_var(ENV_ALIAS_BACKUPS);
+            std::env::remove_var(ENV_ALIAS_CONFIG);
+        }
+        assert_eq!(from_file, (3, false));
+        assert_eq!(env_wins, 7);
+    }
+
+    #[test]
+    fn test_config_set_edits_in_place() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("alias.toml");
+        let v = Verbosity::mute();
+
+        assert_eq!(set_config_value(&v, &file, "Icons", "off").unwrap(), "false");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "icons = false\n");
+
+        fs::write(&file, "# mine\r\nicons = false\r\nformat = \"tsv\"\r\n").unwrap();
+        set_config_value(&v, &file, "icons", "yes").unwrap();
+        set_config_value(&v, &file, "editor", "code --wait").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "# mine\r\nicons = true\r\nformat = \"tsv\"\r\neditor = \"code --wait\"\r\n");
+        assert_eq!(AliasConfig::load(&file).get("editor"), Some("code --wait"));
+
+        assert!(set_config_value(&v, &file, "colour", "red").is_err());
+        assert!(set_config_value(&v, &file, "format", "xml").is_err());
+    }
+
+    #[test]
+    fn test_parse_config_switches() {
+        let (queue, _) = parse_arguments(&args(&["alias", "--config", "set", "Editor", "code --wait"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::ConfigSet("editor".into(), "code --wait".into()));
+        let (queue, _) = parse_arguments(&args(&["alias", "--config", "show"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::ConfigShow);
+        let (queue, _) = parse_arguments(&args(&["alias", "--config"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::ConfigShow);
+        let (queue, _) = parse_arguments(&args(&["alias", "--config", "set", "editor"]));
+        assert!(queue.tasks.iter().any(|t| t.action == AliasAction::Fail));
+    }
+}
+
+// =========================================================
+// SECTION 25: SHELL COMPLETIONS (--completions)
+// =========================================================
+#[cfg(test)]
+mod completion_tests {
+    use alias_lib::{completion_script, completion_specs, parse_arguments, AliasAction, CompletionArg, ExportFormat};
+    use std::mem::discriminant;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn flags_with(arg: CompletionArg) -> Vec<String> {
+        completion_specs().into_iter().filter(|s| s.arg == arg).map(|s| s.flag).collect()
+    }
+
+    #[test]
+    fn test_every_catalogued_switch_parses_back_to_its_action() {
+        for action in AliasAction::catalog() {
+            let cli = action.to_cli_args();
+            let flag = cli.split_whitespace().next().unwrap();
+            let parsed: AliasAction = flag.parse().unwrap();
+            assert_eq!(discriminant(&parsed), discriminant(&action), "{} does not parse back to {:?}", flag, action);
+        }
+    }
+
+    #[test]
+    fn test_specs_cover_every_spelling_once() {
+        let flags: Vec<String> = completion_specs().into_iter().map(|s| s.flag).collect();
+        for expected in ["--edalias", "--ver", "--version", "--no-quiet", "--config", "--completions", "--format", "--remove"] {
+            assert_eq!(flags.iter().filter(|f| *f == expected).count(), 1, "{} should be offered exactly once", expected);
+        }
+        assert!(flags.iter().all(|f| f.starts_with("--")), "{:?}", flags);
+        for named in ["--remove", "--unalias", "--expand", "--describe", "--history"] {
+            assert!(flags_with(CompletionArg::AliasName).contains(&named.to_string()), "{} should complete alias names", named);
+        }
+        assert!(flags_with(CompletionArg::Path).contains(&"--file".to_string()));
+        let format = completion_specs().into_iter().find(|s| s.flag == "--format").unwrap();
+        assert_eq!(format.arg, CompletionArg::Choice(vec!["text".into(), "json".into(), "tsv".into()]), "Choices come from the settings table");
+    }
+
+    #[test]
+    fn test_scripts_offer_flags_and_call_back_for_names() {
+        for shell in ExportFormat::ALL {
+            let script = completion_script(shell, "alias.exe");
+            assert!(script.contains("show-all"), "{} script lacks --show-all", shell);
+            assert!(script.contains("--completions names"), "{} script never asks for alias names", shell);
+        }
+        let bash = completion_script(ExportFormat::Bash, "alias.exe");
+        assert!(bash.contains("complete -F _alias_complete alias alias.exe"), "{}", bash);
+        assert!(completion_script(ExportFormat::Clink, "alias.exe").contains("clink.argmatcher(\"alias\", \"alias.exe\")"));
+        assert!(completion_script(ExportFormat::Fish, "alias").contains("complete -c alias -l remove -x -a"));
+    }
+
+    #[test]
+    fn test_parse_completions_target() {
+        let (queue, _) = parse_arguments(&args(&["alias", "--completions", "PowerShell"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::Completions("powershell".into()));
+        let (queue, _) = parse_arguments(&args(&["alias", "--completions=bash"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::Completions("bash".into()));
+        assert!(!AliasAction::Completions("bash".into()).requires_file(), "Generating a script needs no alias file");
+        assert!(AliasAction::Completions("names".into()).requires_file());
+        let (queue, _) = parse_arguments(&args(&["alias", "--completions", "tcsh"]));
+        assert!(queue.tasks.iter().any(|t| t.action == AliasAction::Fail));
+        let (queue, _) = parse_arguments(&args(&["alias", "--completions"]));
+        assert!(queue.tasks.iter().any(|t| t.action == AliasAction::Fail));
+    }
+}
+
+// =========================================================
+// SECTION 26: SUGGESTIONS AND FUZZY SEARCH (did you mean, --fuzzy)
+// =========================================================
+#[cfg(test)]
+mod suggest_tests {
+    use alias_lib::suggest::{flag_hint, name_hint};
+    use alias_lib::{closest, edit_distance, fuzzy_match, glob_match, known_aliases, parse_arguments, run_with, AliasAction, MemoryProvider, Verbosity};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_edit_distance_counts_swaps_as_one_edit() {
+        assert_eq!(edit_distance("reload", "reload"), 0);
+        assert_eq!(edit_distance("--relaod", "--reload"), 1);
+        assert_eq!(edit_distance("GS", "gs"), 0, "Case is not an edit");
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_closest_ranks_and_limits() {
+        let names = ["gs", "gst", "gc", "ll", "grep"];
+        assert_eq!(closest("gx", names), vec!["gc", "gs"]);
+        assert!(closest("gs", names).iter().all(|n| *n != "gs"), "An exact hit is not a suggestion");
+        assert!(closest("kubectl", names).is_empty(), "Nothing close enough, nothing offered");
+        assert!(closest("g", ["a", "b", "c", "d", "e"]).len() <= 3);
+    }
+
+    #[test]
+    fn test_flag_hints_come_from_the_option_table() {
+        assert_eq!(flag_hint("--relaod"), " Did you mean --reload?");
+        assert_eq!(flag_hint("--formt=json"), " Did you mean --format?");
+        assert!(flag_hint("--no-quite").contains("--no-quiet"), "{}", flag_hint("--no-quite"));
+        assert_eq!(flag_hint("--zzzzzzzzzz"), "");
+    }
+
+    #[test]
+    fn test_name_hints_cover_file_and_ram() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        fs::write(&path, "gst=git status\n").unwrap();
+        let mem = MemoryProvider::seeded(&[("ll", "dir /w")]);
+
+        let known = known_aliases(&mem, &Verbosity::mute(), &path);
+        assert_eq!(known.len(), 2, "{:?}", known);
+        assert_eq!(name_hint("gts", &known), " Did you mean gst?");
+        assert_eq!(name_hint("lll", &known), " Did you mean ll?");
+        assert_eq!(name_hint("unrelated", &known), "");
+    }
+
+    #[test]
+    fn test_glob_and_fuzzy_matching() {
+        assert!(glob_match("g*", "gs"));
+        assert!(glob_match("*status*", "git status -sb"));
+        assert!(glob_match("g?", "GS"));
+        assert!(!glob_match("g?", "gst"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b", "ab-"));
+
+        assert!(fuzzy_match("stat", "gs", "git status"), "Substring of the value");
+        assert!(fuzzy_match("GS", "gs", "x"), "Substring of the name, any case");
+        assert!(fuzzy_match("d*", "dl", "x"));
+        assert!(!fuzzy_match("d*", "ll", "x"), "A glob must match the whole name or value");
+    }
+
+    #[test]
+    fn test_parse_fuzzy() {
+        let (queue, _) = parse_arguments(&args(&["alias", "--fuzzy", "*Git*"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::Fuzzy("*Git*".into()));
+        let (queue, _) = parse_arguments(&args(&["alias", "--fuzzy=status"]));
+        assert_eq!(queue.tasks[0].action, AliasAction::Fuzzy("status".into()));
+        assert!(AliasAction::Fuzzy("x".into()).requires_file());
+        let (queue, _) = parse_arguments(&args(&["alias", "--fuzzy"]));
+        assert!(queue.tasks.iter().any(|t| t.action == AliasAction::Fail));
+    }
+
+    #[test]
+    fn test_typos_stay_harmless() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.doskey");
+        fs::write(&path, "gst=git status\n").unwrap();
+        let mem = MemoryProvider::seeded(&[("gst", "git status")]);
+        let file = path.to_str().unwrap();
+
+        run_with(&mem, args(&["alias", "--quiet", "--file", file, "gts"])).unwrap();
+        run_with(&mem, args(&["alias", "--quiet", "--file", file, "--fuzzy", "*stat*"])).unwrap();
+        run_with(&mem, args(&["alias", "--quiet", "--file", file, "--remove", "gts"])).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("gst=git status"), "Removing a typo touches nothing");
+    }
+}
+
+// =========================================================
+// SECTION 27: LISTING FILTERS (--list, --grep, --<status>-only, --sort)
+// =========================================================
+#[cfg(test)]
+mod listing_filter_tests {
+    use alias_lib::{parse_arguments, perform_triple_audit, triple_audit, AliasAction, AuditFilter, AuditSort, AuditStatus, OutputFormat, ProviderType, ShowIcons, ShowTips, Verbosity, VerbosityLevel};
+    use regex::Regex;
+    use std::sync::{Arc, Mutex};
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn pairs(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    // gs synced, gl desync, gp pending, ll phantom, "bad name" corrupt
+    fn sample() -> Vec<alias_lib::AuditEntry> {
+        triple_audit(
+            pairs(&[("gs", "git status"), ("gl", "git log --oneline"), ("bad name", "x")]),
+            pairs(&[("ll", "dir /w")]),
+            pairs(&[("gs", "git status"), ("gl", "git log"), ("gp", "git push --follow-tags origin")]),
+        ).entries
+    }
+
+    fn names(filter: &AuditFilter) -> Vec<String> {
+        filter.apply(sample()).into_iter().map(|e| e.name).collect()
+    }
+
+    #[test]
+    fn test_filter_flags_parse_into_the_voice() {
+        let (queue, voice) = parse_arguments(&args(&["alias", "--list", "g*", "--grep=STATUS", "--pending-only", "--desync-only", "--sort", "Name"]));
+        assert_eq!(queue.tasks.iter().map(|t| t.action.clone()).collect::<Vec<_>>(), vec![AliasAction::ShowAll], "Filters alone still list");
+        assert_eq!(voice.filter.names.as_deref(), Some("g*"));
+        assert!(voice.filter.values.as_ref().is_some_and(|re| re.is_match("git status")), "--grep ignores case");
+        assert_eq!(voice.filter.statuses, vec![AuditStatus::Pending, AuditStatus::Desync]);
+        assert_eq!(voice.filter.sort, AuditSort::Name);
+
+        for bad in [&["alias", "--grep", "("][..], &["alias", "--sort", "size"], &["alias", "--list"], &["alias", "--grep", "--show-all"]] {
+            let (queue, _) = parse_arguments(&args(bad));
+            assert!(queue.tasks.iter().any(|t| t.action == AliasAction::Fail), "{:?} should fail", bad);
+        }
+    }
+
+    #[test]
+    fn test_filters_narrow_and_combine() {
+        assert_eq!(names(&AuditFilter::default()), ["gs", "gl", "bad name", "ll", "gp"], "Empty filter keeps file order");
+        assert_eq!(names(&AuditFilter { names: Some("g?".into()), ..Default::default() }), ["gs", "gl", "gp"]);
+        assert_eq!(names(&AuditFilter { values: Some(Regex::new("^git (log|push)").unwrap()), ..Default::default() }), ["gl", "gp"]);
+        assert_eq!(names(&AuditFilter { values: Some(Regex::new("oneline").unwrap()), ..Default::default() }), ["gl"], "Any layer's value counts");
+        assert_eq!(names(&AuditFilter { statuses: vec![AuditStatus::Pending], ..Default::default() }), ["gp"]);
+        assert_eq!(names(&AuditFilter { statuses: vec![AuditStatus::Phantom, AuditStatus::Corrupt], ..Default::default() }), ["bad name", "ll"]);
+        assert_eq!(names(&AuditFilter { names: Some("g*".into()), statuses: vec![AuditStatus::Synced], ..Default::default() }), ["gs"]);
+    }
+
+    #[test]
+    fn test_sort_orders() {
+        assert_eq!(names(&AuditFilter { sort: AuditSort::Name, ..Default::default() }), ["bad name", "gl", "gp", "gs", "ll"]);
+        assert_eq!(names(&AuditFilter { sort: AuditSort::Length, ..Default::default() }), ["gp", "gl", "gs", "ll", "bad name"]);
+        assert_eq!("len".parse::<AuditSort>(), Ok(AuditSort::Length));
+    }
+
+    #[test]
+    fn test_triple_audit_renders_only_the_kept_rows() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let spy = Arc::clone(&buffer);
+        let report = {
+            let v = Verbosity {
+                level: VerbosityLevel::Normal,
+                show_icons: ShowIcons::Off,
+                show_tips: ShowTips::Off,
+                display_tip: None,
+                in_startup: false,
+                in_setup: false,
+                writer: Some(buffer),
+                format: OutputFormat::Json,
+                group: None,
+                filter: AuditFilter { statuses: vec![AuditStatus::Pending, AuditStatus::Desync], sort: AuditSort::Name, ..Default::default() },
+            };
+            perform_triple_audit(&v,
+                pairs(&[("gs", "git status"), ("gl", "git log --oneline")]),
+                Vec::new(),
+                pairs(&[("gs", "git status"), ("gl", "git log"), ("gp", "git push")]),
+                &ProviderType::Hybrid)
+        };
+        assert_eq!((report.synced, report.desync, report.pending), (1, 1, 1), "The verdict counts every row, printed or not");
+        let out = String::from_utf8(spy.lock().unwrap().clone()).unwrap();
+        let rows: Vec<&str> = out.lines().filter(|l| !l.is_empty()).collect();
+        assert_eq!(rows.len(), 2, "{}", out);
+        assert!(rows[0].contains(r#""name":"gl""#) && rows[1].contains(r#""name":"gp""#), "{}", out);
+    }
+}
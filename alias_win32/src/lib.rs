@@ -6,9 +6,8 @@ use std::path::Path;
 use windows_sys::Win32::Foundation::GetLastError;
 use windows_sys::Win32::System::Console::{
     GetConsoleAliasesLengthW, GetConsoleAliasesW, AddConsoleAliasW,
-    GetConsoleAliasesLengthA // Still used for api_responsive check
 };
-use alias_lib::{is_drive_responsive, parse_alias_line, parse_macro_file, perform_audit, render_diagnostics, say, shout, text, timeout_guard, update_disk_file, whisper, AccessResult, AliasIcon, AliasProvider, DiagnosticReport, ProviderType, PurgeReport, RegistryStatus, SetOptions, Verbosity, VerbosityLevel, ENV_ALIAS_FILE, ENV_ALIAS_OPTS, IO_RESPONSIVENESS_THRESHOLD};
+use alias_lib::{is_drive_responsive, parse_alias_line, parse_macro_file, perform_audit, render_diagnostics, AuditReport, say, shout, text, timeout_guard, update_disk_file, warn_macro_issues, whisper, AccessResult, AliasIcon, AliasProvider, DiagnosticReport, ProviderType, PurgeReport, RegistryStatus, SetOptions, Verbosity, VerbosityLevel, ENV_ALIAS_FILE, ENV_ALIAS_OPTS, IO_RESPONSIVENESS_THRESHOLD};
 use std::os::windows::ffi::OsStrExt;
 use std::time::Duration;
 use winreg::RegKey;
@@ -27,25 +26,26 @@ fn get_test_silo_name() -> String {
     }
 }
 
-fn get_target_exe_wide() -> *const u16 {
-    use std::cell::RefCell;
-    thread_local! {
-        static WIDE_BUCKET: RefCell<Vec<u16>> = RefCell::new(Vec::new());
+pub struct Win32LibraryInterface {
+    // Null-terminated wide name of the console exe whose alias table we own.
+    exe_wide: Vec<u16>,
+}
+
+impl Win32LibraryInterface {
+    /// Targets the alias table of an arbitrary console exe (e.g. "cmd.exe").
+    pub fn for_exe(exe_name: &str) -> Self {
+        Self { exe_wide: std::ffi::OsStr::new(exe_name).encode_wide().chain(Some(0)).collect() }
     }
-    WIDE_BUCKET.with(|b| {
-        let mut bucket = b.borrow_mut();
-        if bucket.is_empty() {
-            let name = get_test_silo_name();
-            *bucket = std::ffi::OsStr::new(&name).encode_wide().chain(Some(0)).collect();
-        }
-        bucket.as_ptr()
-    })
 }
 
-pub struct Win32LibraryInterface;
+impl Default for Win32LibraryInterface {
+    fn default() -> Self {
+        Self::for_exe(&get_test_silo_name())
+    }
+}
 
 impl AliasProvider for Win32LibraryInterface {
-    fn raw_set_macro(name: &str, value: Option<&str>) -> io::Result<bool> {
+    fn raw_set_macro(&self, name: &str, value: Option<&str>) -> io::Result<bool> {
         // 1. NO MORE TRIMMING. Pass the name EXACTLY as it is.
         let n_wide: Vec<u16> = std::ffi::OsStr::new(name)
             .encode_wide().chain(Some(0)).collect();
@@ -58,7 +58,7 @@ impl AliasProvider for Win32LibraryInterface {
         });
 
         unsafe {
-            let exe_ptr = get_target_exe_wide();
+            let exe_ptr = self.exe_wide.as_ptr();
             let success = AddConsoleAliasW(
                 n_wide.as_ptr(),
                 v_wide.as_ref().map_or(std::ptr::null(), |v| v.as_ptr()),
@@ -76,18 +76,18 @@ impl AliasProvider for Win32LibraryInterface {
             Ok(true)
         }
     }
-    fn raw_reload_from_file(_verbosity: &Verbosity, path: &Path) -> io::Result<()> {
+    fn raw_reload_from_file(&self, _verbosity: &Verbosity, path: &Path) -> io::Result<()> {
         // We pass Verbosity::silent() to satisfy the signature
         let macros = parse_macro_file(path, &Verbosity::silent())
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
 
         for (n, v) in macros {
-            Self::raw_set_macro(&n, Some(&v))?;
+            self.raw_set_macro(&n, Some(&v))?;
         }
         Ok(())
     }
-    fn get_all_aliases(_verbosity: &Verbosity) -> io::Result<Vec<(String, String)>> {
-        let exe_name = get_target_exe_wide();
+    fn get_all_aliases(&self, _verbosity: &Verbosity) -> io::Result<Vec<(String, String)>> {
+        let exe_name = self.exe_wide.as_ptr();
 
         unsafe {
             let mut len_bytes = GetConsoleAliasesLengthW(exe_name);
@@ -143,7 +143,7 @@ impl AliasProvider for Win32LibraryInterface {
             Ok(list)
         }
     }
-    fn write_autorun_registry(cmd: &str, verbosity: &Verbosity) -> io::Result<()> {
+    fn write_autorun_registry(&self, cmd: &str, verbosity: &Verbosity) -> io::Result<()> {
         let hkcu = RegKey::predef(HKEY_CURRENT_USER);
         let (key, _) = hkcu.create_subkey(REG_SUBKEY)?;
 
@@ -200,18 +200,18 @@ impl AliasProvider for Win32LibraryInterface {
         shout!(verbosity, AliasIcon::Success, "AutoRun synchronized (Deduplicated & Position Preserved).");
         Ok(())
     }
-    fn read_autorun_registry() -> String {
+    fn read_autorun_registry(&self) -> String {
         let hkcu = RegKey::predef(HKEY_CURRENT_USER);
         // We open the key and get the value, defaulting to empty string on any error
         hkcu.open_subkey(REG_SUBKEY)
             .and_then(|key| key.get_value(REG_AUTORUN_KEY))
             .unwrap_or_default()
     }
-    fn purge_ram_macros(verbosity: &Verbosity) -> io::Result<PurgeReport> {
+    fn purge_ram_macros(&self, verbosity: &Verbosity) -> io::Result<PurgeReport> {
         let mut report = PurgeReport { cleared: Vec::new(), failed: Vec::new() };
         // Now using ? on the getter
-        for (name, _) in Self::get_all_aliases(verbosity)? {
-            if Self::raw_set_macro(&name, None)? {
+        for (name, _) in self.get_all_aliases(verbosity)? {
+            if self.raw_set_macro(&name, None)? {
                 report.cleared.push(name);
             } else {
                 report.failed.push((name, unsafe { GetLastError() }));
@@ -219,8 +219,8 @@ impl AliasProvider for Win32LibraryInterface {
         }
         Ok(report)
     }
-    fn reload_full( verbosity: &Verbosity, path: &Path, clear: bool) -> Result<(), Box<dyn std::error::Error>> {
-        if clear { Self::purge_ram_macros(verbosity)?; }
+    fn reload_full(&self, verbosity: &Verbosity, path: &Path, clear: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if clear { self.purge_ram_macros(verbosity)?; }
         // 1. Add '?' to percolate the error and get the Vec
         // 2. Pass verbosity to match the new signature
         let macros = parse_macro_file(path, verbosity)
@@ -229,17 +229,17 @@ impl AliasProvider for Win32LibraryInterface {
         let mut count = 0;
         for (n, v) in macros {
             // Use '?' here too to ensure we stop on a kernel failure
-            Self::raw_set_macro(&n, Some(&v))?;
+            self.raw_set_macro(&n, Some(&v))?;
             count += 1;
         }
 
         whisper!(verbosity, AliasIcon::Success, "API Reload: {} macros injected.", count);
         Ok(())
     }
-    fn query_alias(name: &str, verbosity: &Verbosity) -> Vec<String> {
+    fn query_alias(&self, name: &str, verbosity: &Verbosity) -> Vec<String> {
         let search_target = name.to_lowercase();
 
-        let os_list = match Self::get_all_aliases(verbosity) {
+        let os_list = match self.get_all_aliases(verbosity) {
             Ok(list) => { list },
             Err(e) => {
                 return vec![text!(verbosity, AliasIcon::Alert, "Kernel Query Failed: {}", e)];
@@ -255,11 +255,12 @@ impl AliasProvider for Win32LibraryInterface {
         // This return MUST match what your test is looking for: "not a known alias"
         vec![text!(verbosity, AliasIcon::Alert, "'{}' not found in Win32 RAM.", name)]
     }
-    fn set_alias(opts: SetOptions, path: &Path, verbosity: &Verbosity) -> io::Result<()> {
+    fn set_alias(&self, opts: SetOptions, path: &Path, verbosity: &Verbosity) -> io::Result<()> {
         let name = if opts.force_case { opts.name.clone() } else { opts.name.to_lowercase() };
         let val_opt = if opts.value.is_empty() { None } else { Some(opts.value.as_str()) };
+        warn_macro_issues(verbosity, &name, &opts.value);
 
-        if !Self::raw_set_macro(&name, val_opt)? {
+        if !self.raw_set_macro(&name, val_opt)? {
             shout!(verbosity, AliasIcon::Alert, "Kernel strike failed (Code {}).", unsafe { GetLastError() });
         }
 
@@ -273,7 +274,7 @@ impl AliasProvider for Win32LibraryInterface {
         whisper!(verbosity, AliasIcon::Success, "{} alias: {}", if opts.value.is_empty() { "Deleted" } else { "Set" }, name);
         Ok(())
     }
-    fn run_diagnostics(path: &Path, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
+    fn run_diagnostics(&self, path: &Path, verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
         let report = DiagnosticReport {
             binary_path: env::current_exe().ok(),
             resolved_path: path.to_path_buf(),
@@ -283,34 +284,35 @@ impl AliasProvider for Win32LibraryInterface {
             is_readonly: path.metadata().map(|m| m.permissions().readonly()).unwrap_or(false),
             drive_responsive: matches!(is_drive_responsive(path, IO_RESPONSIVENESS_THRESHOLD), AccessResult::Ready),
             registry_status: check_registry_native(),
-            api_status: Some(if Self::is_api_responsive(IO_RESPONSIVENESS_THRESHOLD) { "CONNECTED (Win32 API)".to_string() } else { "FAILED".to_string() }),
+            api_status: Some(if self.is_api_responsive(IO_RESPONSIVENESS_THRESHOLD) { "CONNECTED (Win32 API)".to_string() } else { "FAILED".to_string() }),
         };
         render_diagnostics(report, verbosity);
         Ok(())
     }
-    fn alias_show_all(verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
-        if verbosity.level == VerbosityLevel::Mute { return Ok(()); }
+    fn alias_show_all(&self, verbosity: &Verbosity) -> Result<AuditReport, Box<dyn std::error::Error>> {
+        if verbosity.level == VerbosityLevel::Mute { return Ok(AuditReport::default()); }
 
-        let os_pairs = Self::get_all_aliases(verbosity)?;
-        perform_audit(os_pairs, verbosity, &Self::provider_type())
+        let os_pairs = self.get_all_aliases(verbosity)?;
+        perform_audit(os_pairs, verbosity, &self.provider_type())
     }
-    fn provider_type() -> ProviderType {
+    fn provider_type(&self) -> ProviderType {
         ProviderType::Win32
     }
-    fn is_api_responsive(timeout: Duration) -> bool {
-        timeout_guard(timeout, || {
-            let name = get_test_silo_name() + "\0";
-            unsafe { GetConsoleAliasesLengthA(name.as_ptr()) };
+    fn is_api_responsive(&self, timeout: Duration) -> bool {
+        // Probe the table this instance owns (for_exe), not the default one
+        let exe = self.exe_wide.clone();
+        timeout_guard(timeout, move || {
+            unsafe { GetConsoleAliasesLengthW(exe.as_ptr()) };
             true // If it didn't hang, it's responsive
         }).unwrap_or(false)
     }
-    fn get_version() -> &'static Versioning {
+    fn get_version(&self) -> &'static Versioning {
         &VERSION
     }
-    fn get_versions() -> Vec<&'static Versioning> {
+    fn get_versions(&self) -> Vec<&'static Versioning> {
         vec![
             alias_lib::Versioning::current(),
-            Self::get_version(),
+            self.get_version(),
         ]
     }
 
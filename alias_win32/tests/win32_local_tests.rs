@@ -53,26 +53,26 @@ fn test_win32_api_roundtrip() {
     let val = "echo hello";
 
     // Call via the Interface
-    Win32LibraryInterface::raw_set_macro(name, Some(val)).unwrap();
-    let all = Win32LibraryInterface::get_all_aliases(&voice!(Silent, Off, Off)).expect("RAM fetch failed");
+    Win32LibraryInterface::default().raw_set_macro(name, Some(val)).unwrap();
+    let all = Win32LibraryInterface::default().get_all_aliases(&voice!(Silent, Off, Off)).expect("RAM fetch failed");
     let found = all.iter().find(|(n, _)| n == name);
 
     assert!(found.is_some());
-    Win32LibraryInterface::raw_set_macro(name, None).unwrap();
+    Win32LibraryInterface::default().raw_set_macro(name, None).unwrap();
 }
 
 #[test]
 #[serial]
 fn test_routine_clear_ram() {
     let name = "purge_me";
-    Win32LibraryInterface::raw_set_macro(name, Some("temporary")).unwrap();
+    Win32LibraryInterface::default().raw_set_macro(name, Some("temporary")).unwrap();
 
-    let report = Win32LibraryInterface::purge_ram_macros(&voice!(Silent, Off, Off)).expect("Purge failed");
+    let report = Win32LibraryInterface::default().purge_ram_macros(&voice!(Silent, Off, Off)).expect("Purge failed");
 
     assert!(report.cleared.iter().any(|n| n.to_lowercase() == name.to_lowercase()),
             "Purge did not report clearing our test key");
 
-    let results = Win32LibraryInterface::query_alias(name, &Verbosity::normal());
+    let results = Win32LibraryInterface::default().query_alias(name, &Verbosity::normal());
     // Since query_alias returns Vec<String>, check for content or lack thereof
     assert!(results.iter().all(|s| !s.contains("temporary")));
 }
@@ -80,10 +80,10 @@ fn test_routine_clear_ram() {
 #[test]
 #[serial]
 fn test_routine_purge_ram() {
-    Win32LibraryInterface::raw_set_macro("purge_target", Some("alive")).unwrap();
-    let _ = Win32LibraryInterface::purge_ram_macros(&voice!(Silent, Off, Off)).expect("Purge failed");
+    Win32LibraryInterface::default().raw_set_macro("purge_target", Some("alive")).unwrap();
+    let _ = Win32LibraryInterface::default().purge_ram_macros(&voice!(Silent, Off, Off)).expect("Purge failed");
 
-    let query = Win32LibraryInterface::query_alias("purge_target", &Verbosity::normal());
+    let query = Win32LibraryInterface::default().query_alias("purge_target", &Verbosity::normal());
 
     // Use a more flexible check that matches your text! output
     assert!(query.get(0).map_or(false, |s| s.contains("not a known alias") || s.contains("not found")));
@@ -102,10 +102,10 @@ fn test_win32_rapid_fire_sync() {
             volatile: false,
             force_case: false,
         };
-        Win32LibraryInterface::set_alias(opts, &path, &Verbosity::normal()).expect("Rapid fire set failed");
+        Win32LibraryInterface::default().set_alias(opts, &path, &Verbosity::normal()).expect("Rapid fire set failed");
     }
 
-    let all = Win32LibraryInterface::get_all_aliases(&voice!(Silent, Off, Off)).expect("RAM fetch failed");
+    let all = Win32LibraryInterface::default().get_all_aliases(&voice!(Silent, Off, Off)).expect("RAM fetch failed");
     for i in 0..20 {
         let name = format!("stress_test_{}", i);
         assert!(all.iter().any(|(n, _)| n == &name), "Missing alias {}", name);
@@ -120,15 +120,15 @@ fn test_win32_international_roundtrip() {
     let name = "λ_alias";
     let val = "echo lambda_power";
 
-    assert!(Win32LibraryInterface::raw_set_macro(name, Some(val)).unwrap(), "Failed to set international alias");
+    assert!(Win32LibraryInterface::default().raw_set_macro(name, Some(val)).unwrap(), "Failed to set international alias");
 
-    let all = Win32LibraryInterface::get_all_aliases(&voice!(Silent, Off, Off)).expect("RAM fetch failed");
+    let all = Win32LibraryInterface::default().get_all_aliases(&voice!(Silent, Off, Off)).expect("RAM fetch failed");
     let found = all.iter().find(|(n, _)| n == name);
 
     assert!(found.is_some(), "International alias 'λ' was mangled or lost");
     assert_eq!(found.unwrap().1, val);
 
-    Win32LibraryInterface::raw_set_macro(name, None).unwrap();
+    Win32LibraryInterface::default().raw_set_macro(name, None).unwrap();
 }
 
 #[test]
@@ -143,7 +143,7 @@ fn test_registry_append_logic_library() {
     let original_cmd = "echo 'Old Command'";
     key.set_value(REG_AUTORUN_KEY, &original_cmd.to_string()).unwrap();
 
-    Win32LibraryInterface::write_autorun_registry(&format!("{} & alias --reload", original_cmd), &Verbosity::normal()).expect("Install failed");
+    Win32LibraryInterface::default().write_autorun_registry(&format!("{} & alias --reload", original_cmd), &Verbosity::normal()).expect("Install failed");
 
     let result: String = key.get_value(REG_AUTORUN_KEY).unwrap();
     assert!(result.contains(original_cmd));
@@ -155,16 +155,16 @@ fn test_thread_silo_isolation_local() {
     let name_a = "unique_silo_test_a";
     let name_b = "unique_silo_test_b";
 
-    Win32LibraryInterface::raw_set_macro(name_a, Some("val_a")).unwrap();
-    Win32LibraryInterface::raw_set_macro(name_b, Some("val_b")).unwrap();
+    Win32LibraryInterface::default().raw_set_macro(name_a, Some("val_a")).unwrap();
+    Win32LibraryInterface::default().raw_set_macro(name_b, Some("val_b")).unwrap();
 
-    let all = Win32LibraryInterface::get_all_aliases(&voice!(Silent, Off, Off)).expect("RAM fetch failed");
+    let all = Win32LibraryInterface::default().get_all_aliases(&voice!(Silent, Off, Off)).expect("RAM fetch failed");
 
     assert!(all.iter().any(|(n, _)| n == name_a));
     assert!(all.iter().any(|(n, _)| n == name_b));
 
-    Win32LibraryInterface::raw_set_macro(name_a, None).unwrap();
-    Win32LibraryInterface::raw_set_macro(name_b, None).unwrap();
+    Win32LibraryInterface::default().raw_set_macro(name_a, None).unwrap();
+    Win32LibraryInterface::default().raw_set_macro(name_b, None).unwrap();
 }
 
 type P = Win32LibraryInterface; // Define P for the template
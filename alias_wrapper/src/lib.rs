@@ -14,14 +14,30 @@ include!(concat!(env!("OUT_DIR"), "/version_data.rs"));
 
 extern crate alias_lib;
 
-pub struct WrapperLibraryInterface;
+pub struct WrapperLibraryInterface {
+    // The console exe whose doskey table we drive ("/exename=", "/macros:").
+    exe_name: String,
+}
+
+impl WrapperLibraryInterface {
+    /// Targets the doskey table of an arbitrary console exe (e.g. "cmd.exe").
+    pub fn for_exe(exe_name: &str) -> Self {
+        Self { exe_name: exe_name.to_string() }
+    }
+}
+
+impl Default for WrapperLibraryInterface {
+    fn default() -> Self {
+        Self::for_exe("cmd.exe")
+    }
+}
 
 // ... (imports remain the same)
 
 impl alias_lib::AliasProvider for WrapperLibraryInterface {
     /*
         // SYNCED: No more trimming. Let the command pass through raw.
-        fn raw_set_macro(name: &str, value: Option<&str>) -> io::Result<bool> {
+        fn raw_set_macro(&self, name: &str, value: Option<&str>) -> io::Result<bool> {
             let val = value.unwrap_or("");
             // Remove the .trim_matches('"') calls to match Win32's "Raw" philosophy
             let status = Command::new("doskey")
@@ -33,7 +49,7 @@ impl alias_lib::AliasProvider for WrapperLibraryInterface {
         }
     */
     // SYNCED: Use the same Highlander deduplication logic
-    fn write_autorun_registry(cmd: &str, verbosity: &Verbosity) -> io::Result<()> {
+    fn write_autorun_registry(&self, cmd: &str, verbosity: &Verbosity) -> io::Result<()> {
         let hkcu = winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER);
         let (key, _) = hkcu.create_subkey(REG_SUBKEY)?;
         let raw_existing: String = key.get_value(REG_AUTORUN_KEY).unwrap_or_default();
@@ -76,10 +92,10 @@ impl alias_lib::AliasProvider for WrapperLibraryInterface {
     }
 
     // ADDED: Missing trait method to match Win32
-    fn purge_ram_macros(verbosity: &Verbosity) -> io::Result<PurgeReport> {
+    fn purge_ram_macros(&self, verbosity: &Verbosity) -> io::Result<PurgeReport> {
         let mut report = PurgeReport { cleared: Vec::new(), failed: Vec::new() };
-        for (name, _) in Self::get_all_aliases(verbosity)? {
-            if Self::raw_set_macro(&name, None)? {
+        for (name, _) in self.get_all_aliases(verbosity)? {
+            if self.raw_set_macro(&name, None)? {
                 report.cleared.push(name);
             } else {
                 report.failed.push((name, 0)); // No GetLastError for wrapper
@@ -89,16 +105,16 @@ impl alias_lib::AliasProvider for WrapperLibraryInterface {
     }
 
     // ADDED: Missing trait method to match Win32
-    fn reload_full(verbosity: &Verbosity, path: &Path, clear: bool) -> Result<(), Box<dyn std::error::Error>> {
-        if clear { Self::purge_ram_macros(verbosity)?; }
-        Self::raw_reload_from_file(verbosity, path)?;
+    fn reload_full(&self, verbosity: &Verbosity, path: &Path, clear: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if clear { self.purge_ram_macros(verbosity)?; }
+        self.raw_reload_from_file(verbosity, path)?;
         whisper!(verbosity, AliasIcon::Success, "Doskey Wrapper: Reloaded from {}", path.display());
         Ok(())
     }
 
-    fn get_all_aliases(_verbosity: &Verbosity) -> io::Result<Vec<(String, String)>> {
+    fn get_all_aliases(&self, _verbosity: &Verbosity) -> io::Result<Vec<(String, String)>> {
         let output = Command::new("doskey")
-            .arg("/macros:cmd.exe")
+            .arg(format!("/macros:{}", self.exe_name))
             .output()
             .map_err(|e| {
                 // If we can't even spawn doskey, that's a system error
@@ -128,11 +144,11 @@ impl alias_lib::AliasProvider for WrapperLibraryInterface {
         Ok(list)
     }
 
-    fn query_alias(name: &str, verbosity: &Verbosity) -> Vec<String> {
+    fn query_alias(&self, name: &str, verbosity: &Verbosity) -> Vec<String> {
         let search_target = name.to_lowercase();
 
         // FIX: Handle the Result from get_all_aliases()
-        let os_list = match Self::get_all_aliases(verbosity) {
+        let os_list = match self.get_all_aliases(verbosity) {
             Ok(list) => list,
             Err(e) => {
                 if verbosity.level == VerbosityLevel::Normal {
@@ -155,13 +171,13 @@ impl alias_lib::AliasProvider for WrapperLibraryInterface {
         vec![]
     }
     // old
-    fn raw_set_macro(name: &str, value: Option<&str>) -> io::Result<bool> {
+    fn raw_set_macro(&self, name: &str, value: Option<&str>) -> io::Result<bool> {
         let val = value.unwrap_or("");
         let clean_name = name.trim_matches('"');
         let clean_val = val.trim_matches('"');
 
         let status = Command::new("doskey")
-            .args(["/exename=cmd.exe", &format!("{}={}", clean_name, clean_val)])
+            .args([&format!("/exename={}", self.exe_name), &format!("{}={}", clean_name, clean_val)])
             .status()
             .map_err(|e| {
                 let err_box = failure!(Verbosity::loud(), e);
@@ -175,7 +191,7 @@ impl alias_lib::AliasProvider for WrapperLibraryInterface {
         Ok(true)
     }
 
-    fn set_alias(opts: SetOptions, path: &Path, verbosity: &Verbosity) -> io::Result<()> {
+    fn set_alias(&self, opts: SetOptions, path: &Path, verbosity: &Verbosity) -> io::Result<()> {
         let name = if opts.force_case { opts.name.clone() } else { opts.name.to_lowercase() };
 
         if name.is_empty() {
@@ -189,16 +205,20 @@ impl alias_lib::AliasProvider for WrapperLibraryInterface {
         }
 
         // Percolate RAM/Doskey errors
-        Self::raw_set_macro(&name, Some(&opts.value))?;
+        warn_macro_issues(verbosity, &name, &opts.value);
+        self.raw_set_macro(&name, Some(&opts.value))?;
 
         let tag = if opts.volatile { "(volatile)" } else { "(saved)" };
         whisper!(verbosity, AliasIcon::Success, "Wrapper set {}: {}={}", tag, name, opts.value);
         Ok(())
     }
 
-    fn raw_reload_from_file(_verbosity: &Verbosity, path: &Path) -> io::Result<()> {
+    fn raw_reload_from_file(&self, verbosity: &Verbosity, path: &Path) -> io::Result<()> {
+        // doskey cannot follow #include, so it gets the expanded stream (removed on drop)
+        let flat = alias_lib::flatten_macro_file(verbosity, path)
+            .map_err(|e| io::Error::other(e.to_string()))?;
         let status = Command::new("doskey")
-            .arg(format!("/macrofile={}", path.display()))
+            .arg(format!("/macrofile={}", flat.path().display()))
             .status()
             .map_err(|e| {
                 let err_box = failure!(Verbosity::loud(), e);
@@ -212,14 +232,14 @@ impl alias_lib::AliasProvider for WrapperLibraryInterface {
         Ok(())
     }
 
-    fn read_autorun_registry() -> String {
+    fn read_autorun_registry(&self) -> String {
         let hkcu = winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER);
         hkcu.open_subkey(REG_SUBKEY)
             .and_then(|key| key.get_value(REG_AUTORUN_KEY))
             .unwrap_or_default()
     }
 
-    fn run_diagnostics(path: &Path, verbosity: &Verbosity) -> Result<(), Box<dyn Error>> {
+    fn run_diagnostics(&self, path: &Path, verbosity: &Verbosity) -> Result<(), Box<dyn Error>> {
         let report = DiagnosticReport {
             binary_path: env::current_exe().ok(),
             resolved_path: path.to_path_buf(),
@@ -228,40 +248,40 @@ impl alias_lib::AliasProvider for WrapperLibraryInterface {
             file_exists: path.exists(),
             is_readonly: path.metadata().map(|m| m.permissions().readonly()).unwrap_or(false),
             drive_responsive: matches!( is_drive_responsive(path, IO_RESPONSIVENESS_THRESHOLD), AccessResult::Ready | AccessResult::Empty ),
-            registry_status: check_registry_wrapper(),
+            registry_status: check_registry_wrapper(self),
             api_status: Some("SPAWNER (doskey.exe)".into()),
         };
         alias_lib::render_diagnostics(report, verbosity);
         Ok(())
     }
 
-    fn alias_show_all(verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
-        if verbosity.level == VerbosityLevel::Mute { return Ok(()); }
+    fn alias_show_all(&self, verbosity: &Verbosity) -> Result<AuditReport, Box<dyn std::error::Error>> {
+        if verbosity.level == VerbosityLevel::Mute { return Ok(AuditReport::default()); }
 
         // FIX: Extract the Vec from the Result using '?'
-        let os_aliases = Self::get_all_aliases(verbosity)?;
+        let os_aliases = self.get_all_aliases(verbosity)?;
 
         // Perform the audit and percolate any error immediately
-        alias_lib::perform_audit(os_aliases, verbosity, &Self::provider_type())
+        alias_lib::perform_audit(os_aliases, verbosity, &self.provider_type())
     }
-    fn provider_type() -> ProviderType { ProviderType::Wrapper }
-    fn is_api_responsive(_timeout: Duration) -> bool {
+    fn provider_type(&self) -> ProviderType { ProviderType::Wrapper }
+    fn is_api_responsive(&self, _timeout: Duration) -> bool {
         true
     }
 
-    fn get_version() -> &'static Versioning {
+    fn get_version(&self) -> &'static Versioning {
         &VERSION
     }
-    fn get_versions() -> Vec<&'static Versioning> {
+    fn get_versions(&self) -> Vec<&'static Versioning> {
         vec![
             alias_lib::Versioning::current(),
-            Self::get_version(),
+            self.get_version(),
         ]
     }
 }
 
-fn check_registry_wrapper() -> RegistryStatus {
-    let raw = WrapperLibraryInterface::read_autorun_registry();
+fn check_registry_wrapper(provider: &WrapperLibraryInterface) -> RegistryStatus {
+    let raw = provider.read_autorun_registry();
     if raw.is_empty() {
         RegistryStatus::NotFound
     } else if raw.contains("--reload") || raw.contains("alias") {
@@ -20,6 +20,7 @@ use test_suite_shared::{MockProvider, MOCK_RAM, LAST_CALL, global_test_setup};
 // shared code end
 
 #[path = "state_restoration.rs"]
+#[cfg(windows)]
 mod stateful;
 #[cfg(test)]
 #[ctor::ctor]
@@ -27,19 +28,23 @@ fn local_library_tests_init() {
     eprintln!("[PRE-FLIGHT] Warning: System state is starting.");
     // FORCE LINKAGE: This prevents the linker from tree-shaking the module
     // and silences the "unused" warnings by actually "using" them.
-    let _ = stateful::has_backup();
-    if stateful::is_stale() {
-        // This path probably won't be hit, but the compiler doesn't know that.
-        eprintln!("[PRE-FLIGHT] Warning: System state is stale.");
+    #[cfg(windows)]
+    {
+        let _ = stateful::has_backup();
+        if stateful::is_stale() {
+            // This path probably won't be hit, but the compiler doesn't know that.
+            eprintln!("[PRE-FLIGHT] Warning: System state is stale.");
+        }
+        let _ = stateful::has_backup();
+        stateful::pre_flight_inc();
     }
-    let _ = stateful::has_backup();
-    stateful::pre_flight_inc();
     global_test_setup();
 }
 #[cfg(test)]
 #[ctor::dtor]
 fn alias_lib_tests_end() {
     eprintln!("[POST-FLIGHT] Warning: System state is finished.");
+    #[cfg(windows)]
     stateful::post_flight_dec();
 }
 
@@ -784,6 +789,8 @@ mod battery_4 {
             name: "test".to_string(),
             os_value: None,
             file_value: None,
+            meta: None,
+            origin: None,
         };
         assert!(empty_mesh.is_empty_definition());
 
@@ -792,6 +799,8 @@ mod battery_4 {
             name: "ghost".to_string(),
             os_value: Some("dir".to_string()),
             file_value: None,
+            meta: None,
+            origin: None,
         };
         assert!(!ghost.is_empty_definition());
     }
@@ -853,7 +862,7 @@ mod alias_provider_logic_tests {
         }
 
         // 2. Run the Trait's default purge logic
-        let report = MockProvider::purge_ram_macros(&v).unwrap();
+        let report = MockProvider.purge_ram_macros(&v).unwrap();
 
         // 3. Verify
         assert_eq!(report.cleared.len(), 2);
@@ -900,8 +909,10 @@ mod battery_5 {
 
 #[cfg(test)]
 mod directory_logic_tests {
+    #[cfg_attr(not(windows), allow(unused_imports))]
     use alias_lib::get_alias_exe;
 
+    #[cfg(windows)]
     #[test]
     fn test_alias_binary_and_directory_resolution() {
         // 1. Test the EXE resolution (The Anchor)
@@ -928,6 +939,7 @@ mod directory_logic_tests {
             assert!(exe_path.starts_with(dir_path), "The binary must reside within the resolved directory");
         }
     }
+    #[cfg(windows)]
     #[test]
     fn test_parent_fallback_logic() {
         // Simulating the 'unwrap_or(&p)' logic
@@ -1046,6 +1058,7 @@ mod path_resolution_tests {
     use alias_lib::{get_alias_path, DEFAULT_ALIAS_FILENAME, ENV_ALIAS_FILE};
     use serial_test::serial;
 
+    #[cfg(windows)]
     #[test]
     #[serial]
     fn test_env_override_panic() {
@@ -1369,6 +1382,9 @@ mod battery_8 {
                 in_startup: false,
                 in_setup: false,
                 writer: Some(buffer), // buffer is moved into v here
+                format: alias_lib::OutputFormat::Text,
+                group: None,
+                filter: Default::default(),
             };
 
             let report = alias_lib::DiagnosticReport {
@@ -1505,7 +1521,7 @@ mod battery_10 {
         let file = vec![("bad name".into(), "val".into())];
 
         // This should fall all the way to the Pending loop and still flag "CORRUPT"
-        perform_triple_audit(&Verbosity::silent(), win32, doskey, file, &MockProvider::provider_type());
+        perform_triple_audit(&Verbosity::silent(), win32, doskey, file, &MockProvider.provider_type());
     }
 }
 #[cfg(test)]
@@ -1564,6 +1580,8 @@ mod battery_12 {
             name: "g".into(),
             os_value: Some("git status".into()),
             file_value: Some("git status".into()),
+            meta: None,
+            origin: None,
         }];
 
         // Scenario 2: Value Desync
@@ -1571,6 +1589,8 @@ mod battery_12 {
             name: "g".into(),
             os_value: Some("git status".into()),
             file_value: Some("git st".into()), // Different
+            meta: None,
+            origin: None,
         }];
 
         // Scenario 3: Corrupt Name
@@ -1578,14 +1598,16 @@ mod battery_12 {
             name: "bad name".into(), // Spaces are illegal
             os_value: Some("val".into()),
             file_value: Some("val".into()),
+            meta: None,
+            origin: None,
         }];
 
         // In your test, you can run these through display_audit.
         // Since it's a 'void' return, you are verifying it doesn't panic
         // and handles the Option types correctly.
-        display_audit(&mesh_ok, &Verbosity::silent(), &MockProvider::provider_type());
-        display_audit(&mesh_desync, &Verbosity::silent(), &MockProvider::provider_type());
-        display_audit(&mesh_corrupt, &Verbosity::silent(), &MockProvider::provider_type());
+        display_audit(&mesh_ok, &Verbosity::silent(), &MockProvider.provider_type());
+        display_audit(&mesh_desync, &Verbosity::silent(), &MockProvider.provider_type());
+        display_audit(&mesh_corrupt, &Verbosity::silent(), &MockProvider.provider_type());
     }
 }
 
@@ -1755,6 +1777,7 @@ mod battery_17 {
         let task = Task {
             action,
             path: file_path, // Task owns this path now
+            args: Vec::new(),
         };
 
         // 2. Dispatch now takes exactly TWO arguments: (Task, &Verbosity)
@@ -1777,6 +1800,7 @@ mod battery_17 {
         let task = Task {
             action,
             path: file_path,
+            args: Vec::new(),
         };
 
         // 2. Pass Task and &Verbosity
@@ -2026,6 +2050,7 @@ mod intelligence_tests {
 mod intent_tests {
     //        use super::*;
     use std::env;
+    #[cfg_attr(not(windows), allow(unused_imports))]
     use alias_lib::{find_executable, get_editor_preference, Verbosity};
 
     #[test]
@@ -2062,6 +2087,7 @@ mod intent_tests {
         assert_eq!(profile_2.args[0], "nano");
     }
 
+    #[cfg(windows)]
     #[test]
     fn test_find_executable_path_logic() {
         // This test assumes 'notepad.exe' exists in C:\Windows\System32
@@ -2076,8 +2102,10 @@ mod intent_tests {
 mod integration_tests {
     //        use super::*;
     use std::path::PathBuf;
+    #[cfg_attr(not(windows), allow(unused_imports))]
     use alias_lib::{open_editor, BinaryProfile, BinarySubsystem, Verbosity};
 
+    #[cfg(windows)]
     #[test]
     fn test_open_editor_inaccessible_target() {
         let verbosity = Verbosity::mute();
@@ -2331,6 +2359,7 @@ mod integrity_tests {
         assert!(is_file_accessible(&path));
     }
 
+    #[cfg(windows)]
     #[test]
     fn test_integrity_locked_file() {
         use std::os::windows::fs::OpenOptionsExt; // Needed for .share_mode()
@@ -35,7 +35,7 @@ mod stateful;
 fn local_library_tests_init() {
     eprintln!("[PRE-FLIGHT] Warning: System state is starting.");
     eprintln!("\n--- TEST ENVIRONMENT INITIALIZED ---");
-    eprintln!("Provider Identity: {:?}", <P as alias_lib::AliasProvider>::provider_type());
+    eprintln!("Provider Identity: {:?}", P::default().provider_type());
     eprintln!("-----------------------------------\n");
     // FORCE LINKAGE: This prevents the linker from tree-shaking the module
     // and silences the "unused" warnings by actually "using" them.
@@ -58,7 +58,7 @@ fn alias_lib_tests_end() {
 
 macro_rules! skip_if_wrapper {
     () => {
-        if P::provider_type() == ProviderType::Wrapper {
+        if P::default().provider_type() == ProviderType::Wrapper {
             println!("Skipping: Known 'Spawn SNAFU' in Wrapper mode.");
             return;
         }
@@ -86,7 +86,7 @@ fn test_alias_deletion_persistence() {
     };
 
     // Note: set_alias now takes 3 args: (SetOptions, &Path, bool)
-    let _ = P::set_alias(opts, &test_path, &voice!(Silent, ShowFeature::Off, ShowTips::Off));
+    let _ = P::default().set_alias(opts, &test_path, &voice!(Silent, ShowFeature::Off, ShowTips::Off));
 
     let content = fs::read_to_string(&test_path).unwrap();
     assert!(!content.contains("cdx="), "The ghost of cdx is still in the file!");
@@ -100,7 +100,7 @@ fn test_alias_deletion_persistence() {
 fn test_routine_diagnostics_safety() {
     let path = get_test_path("diag");
     // Ensure it doesn't panic even if file doesn't exist
-    P::run_diagnostics(&path, &voice!(Silent, ShowFeature::Off, ShowTips::Off)).expect("Diagnostics failed");
+    P::default().run_diagnostics(&path, &voice!(Silent, ShowFeature::Off, ShowTips::Off)).expect("Diagnostics failed");
 }
 
 #[test]
@@ -108,7 +108,7 @@ fn test_routine_diagnostics_safety() {
 fn test_routine_setup_registration() {
     // We test that the command executes. Result may be Err if no Admin,
     // but the logic path is exercised.
-    let _ = P::install_autorun(&voice!(Silent, ShowFeature::Off, ShowTips::Off), "alias --startup");
+    let _ = P::default().install_autorun(&voice!(Silent, ShowFeature::Off, ShowTips::Off), "alias --startup");
 }
 
 #[test]
@@ -119,7 +119,7 @@ fn test_routine_set_persistence() {
     let val = "echo hello";
 
     // Execute dual strike
-    P::set_alias(SetOptions {
+    P::default().set_alias(SetOptions {
         name: name.into(),
         value: val.into(),
         volatile: false,
@@ -129,7 +129,7 @@ fn test_routine_set_persistence() {
     // RAM Check: Retry loop to handle Win32 kernel latency
     let mut success = false;
     for _ in 0..5 {
-        let query = P::query_alias(name, &voice!(Silent, ShowFeature::Off, ShowTips::Off));
+        let query = P::default().query_alias(name, &voice!(Silent, ShowFeature::Off, ShowTips::Off));
         if !query.is_empty() && query.iter().any(|s: &String| s.contains(val)) {
             success = true;
             break;
@@ -145,7 +145,7 @@ fn test_routine_set_persistence() {
 #[test]
 #[serial]
 fn test_routine_show_all() {
-    P::alias_show_all(&voice!(Normal, ShowFeature::Off, ShowTips::Off)).expect("Audit should pass");
+    P::default().alias_show_all(&voice!(Normal, ShowFeature::Off, ShowTips::Off)).expect("Audit should pass");
 }
 
 // -------------------------------
@@ -169,7 +169,7 @@ fn test_real_file_deletion() {
     };
 
     // FIXED: Passed test_v() instead of 'true'
-    P::set_alias(opts, &path, &test_v()).unwrap();
+    P::default().set_alias(opts, &path, &test_v()).unwrap();
 
     let content = fs::read_to_string(&path).unwrap();
     assert!(!content.contains("cdx="));
@@ -183,10 +183,10 @@ fn test_routine_reload_full() {
     fs::write(&path, "reload_key=reload_val\n").unwrap();
 
     // FIXED: Signature now requires Verbosity
-    P::reload_full(&test_v(), &path, true).expect("Reload failed");
+    P::default().reload_full(&test_v(), &path, true).expect("Reload failed");
 
     // FIXED: query_alias now requires Verbosity
-    let results = P::query_alias("reload_key", &test_v());
+    let results = P::default().query_alias("reload_key", &test_v());
     assert!(results.iter().any(|s: &String| s.contains("reload_val")));
 
     let _ = fs::remove_file(path);
@@ -197,14 +197,14 @@ fn test_routine_reload_full() {
 fn test_routine_diagnostics() {
     let path = PathBuf::from("diag_test.doskey");
     // FIXED: Signature changed from (path) to (path, verbosity)
-    P::run_diagnostics(&path, &voice!(Silent, ShowFeature::Off, ShowTips::Off)).expect("Diagnostics failed");
+    P::default().run_diagnostics(&path, &voice!(Silent, ShowFeature::Off, ShowTips::Off)).expect("Diagnostics failed");
 }
 
 #[test]
 #[serial]
 fn test_routine_install_autorun() {
     // FIXED: Passed test_v() instead of 'true'
-    let _ = P::install_autorun(&test_v(), "alias --startup");
+    let _ = P::default().install_autorun(&test_v(), "alias --startup");
 }
 
 #[test]
@@ -218,9 +218,9 @@ fn test_routine_volatile_strike() {
         force_case: false,
     };
 
-    P::set_alias(opts, &path, &test_v()).unwrap();
+    P::default().set_alias(opts, &path, &test_v()).unwrap();
 
-    let query = P::query_alias("temp_macro", &test_v());
+    let query = P::default().query_alias("temp_macro", &test_v());
     assert!(query.iter().any(|s: &String| s.contains("echo tmp")));
 
     if path.exists() {
@@ -242,9 +242,9 @@ fn test_routine_force_case() {
         force_case: true,
     };
 
-    P::set_alias(opts, &path, &test_v()).expect("Forced set failed");
+    P::default().set_alias(opts, &path, &test_v()).expect("Forced set failed");
 
-    let query = P::query_alias(name, &test_v());
+    let query = P::default().query_alias(name, &test_v());
     assert!(query.iter().any(|s: &String| s.to_lowercase().contains("forced")));
 
     let _ = fs::remove_file(path);
@@ -262,7 +262,7 @@ pub mod testing {
         PathBuf::from(format!("test_{}_{:?}.doskey", suffix, std::thread::current().id()))
     }
 
-    pub fn run_generic_set_and_query<P: AliasProvider>() {
+    pub fn run_generic_set_and_query<P: AliasProvider + Default>() {
         let name = "gauntlet_test";
         let val = "echo gauntlet";
         let path = get_test_path("gauntlet");
@@ -275,19 +275,19 @@ pub mod testing {
             force_case: false,
         };
 
-        P::set_alias(opts, &path, &v).expect("Failed to set alias");
-        let results = P::query_alias(name, &v);
+        P::default().set_alias(opts, &path, &v).expect("Failed to set alias");
+        let results = P::default().query_alias(name, &v);
         assert!(results.iter().any(|s: &String| s.contains(val)));
 
         let _ = std::fs::remove_file(path);
     }
 
-    pub fn run_generic_registry_test<P: AliasProvider>() {
+    pub fn run_generic_registry_test<P: AliasProvider + Default>() {
         let test_cmd = "echo alias_test_hook";
         let verbosity = Verbosity::silent();
-        let res = P::write_autorun_registry(test_cmd, &verbosity);
+        let res = P::default().write_autorun_registry(test_cmd, &verbosity);
         assert!(res.is_ok());
-        let current = P::read_autorun_registry();
+        let current = P::default().read_autorun_registry();
         assert!(current.contains(test_cmd));
     }
 }
@@ -310,13 +310,13 @@ fn test_routine_set_and_query() {
     };
 
     // FIX: Call via the Interface P
-    P::set_alias(opts, &path, &voice!(Silent, Off, Off)).expect("Failed to set alias");
+    P::default().set_alias(opts, &path, &voice!(Silent, Off, Off)).expect("Failed to set alias");
 
     let content = std::fs::read_to_string(&path).unwrap();
     assert!(content.contains("gauntlet_test=echo gauntlet"));
 
     // FIX: Call via P and add closure type annotation
-    let results = P::query_alias(name, &voice!(Silent, Off, Off));
+    let results = P::default().query_alias(name, &voice!(Silent, Off, Off));
     if !results.is_empty() {
         assert!(results.iter().any(|s: &String| s.contains(val))); // Added : &String
     }
@@ -331,13 +331,13 @@ fn test_routine_reload_sync() {
     fs::write(&path, "k1=v1\nk2=v2\n").unwrap();
 
     // FIX: Use trait method for purging
-    let _ = P::purge_ram_macros(&voice!(Silent, Off, Off));
+    let _ = P::default().purge_ram_macros(&voice!(Silent, Off, Off));
 
     // FIX: Use trait method for reloading
-    P::reload_full(&test_v(), &path, true).expect("Reload failed");
+    P::default().reload_full(&test_v(), &path, true).expect("Reload failed");
 
-    let q1 = P::query_alias("k1", &test_v());
-    let q2 = P::query_alias("k2", &test_v());
+    let q1 = P::default().query_alias("k1", &test_v());
+    let q2 = P::default().query_alias("k2", &test_v());
 
     assert!(!q1.is_empty());
     assert!(!q2.is_empty());
@@ -354,20 +354,20 @@ pub fn get_test_path(suffix: &str) -> PathBuf {
 fn test_win32_api_roundtrip() {
     let name = "test_alias_123";
     let val = "echo hello";
-    P::raw_set_macro(name, Some(val)).unwrap();
-    let all = P::get_all_aliases(&voice!(Silent, Off, Off)).expect("Failed to read RAM macros");
+    P::default().raw_set_macro(name, Some(val)).unwrap();
+    let all = P::default().get_all_aliases(&voice!(Silent, Off, Off)).expect("Failed to read RAM macros");
     let found = all.iter().find(|(n, _)| n == name);
     assert!(found.is_some());
-    P::raw_set_macro(name, None).unwrap();
+    P::default().raw_set_macro(name, None).unwrap();
 }
 
 #[test]
 #[serial]
 fn test_routine_clear_ram() {
     let name = "purge_me";
-    P::raw_set_macro(name, Some("temporary")).unwrap();
-    let _ = P::purge_ram_macros(&voice!(Silent, Off, Off)).expect("Purge failed");
-    let results = P::query_alias(name, &Verbosity::normal());
+    P::default().raw_set_macro(name, Some("temporary")).unwrap();
+    let _ = P::default().purge_ram_macros(&voice!(Silent, Off, Off)).expect("Purge failed");
+    let results = P::default().query_alias(name, &Verbosity::normal());
     assert!(results.iter().all(|s| !s.contains("temporary")));
 }
 
@@ -376,7 +376,7 @@ fn test_routine_clear_ram() {
 fn test_routine_delete_sync() {
     let path = get_test_path("del");
     fs::write(&path, "ghost=gone\n").unwrap();
-    P::raw_set_macro("ghost", Some("gone")).unwrap();
+    P::default().raw_set_macro("ghost", Some("gone")).unwrap();
 
     let opts = SetOptions {
         name: "ghost".into(),
@@ -385,13 +385,13 @@ fn test_routine_delete_sync() {
         force_case: false,
     };
 
-    P::set_alias(opts, &path, &Verbosity::normal()).unwrap();
+    P::default().set_alias(opts, &path, &Verbosity::normal()).unwrap();
 
     // SLEEPER AGENT COUNTER-MEASURE:
     // We poll for up to 500ms to allow the File System and Win32 RAM to sync.
     let mut success = false;
     for _ in 0..10 {
-        let query = P::query_alias("ghost", &Verbosity::normal());
+        let query = P::default().query_alias("ghost", &Verbosity::normal());
         if query.is_empty() || query[0].contains("not found") {
             success = true;
             break;
@@ -412,18 +412,18 @@ fn test_thread_silo_isolation() {
     let name_b = format!("silo_b_{}", pid);
 
     // 1. Set A
-    P::raw_set_macro(&name_a, Some("val_a")).unwrap();
+    P::default().raw_set_macro(&name_a, Some("val_a")).unwrap();
     // 2. Set B
-    P::raw_set_macro(&name_b, Some("val_b")).unwrap();
+    P::default().raw_set_macro(&name_b, Some("val_b")).unwrap();
 
     let mut all = Vec::new();
     for _ in 0..10 {
-        all = P::get_all_aliases(&voice!(Silent, Off, Off)).expect("Failed to read RAM macros");
+        all = P::default().get_all_aliases(&voice!(Silent, Off, Off)).expect("Failed to read RAM macros");
         if all.iter().any(|(n, _)| n == &name_a) && all.iter().any(|(n, _)| n == &name_b) { break; }
         std::thread::sleep(std::time::Duration::from_millis(20));
     }
 
-    let all = P::get_all_aliases(&voice!(Silent, Off, Off)).expect("Failed to read RAM macros");
+    let all = P::default().get_all_aliases(&voice!(Silent, Off, Off)).expect("Failed to read RAM macros");
 
     // Prove both exist independently
     let has_a = all.iter().any(|(n, _)| n == &name_a);
@@ -433,8 +433,8 @@ fn test_thread_silo_isolation() {
     assert!(has_b, "Missing B");
 
     // Cleanup
-    P::raw_set_macro(&name_a, None).unwrap();
-    P::raw_set_macro(&name_b, None).unwrap();
+    P::default().raw_set_macro(&name_a, None).unwrap();
+    P::default().raw_set_macro(&name_b, None).unwrap();
 }
 
 #[test]
@@ -445,14 +445,14 @@ fn test_purge_stress_partial_failure() {
     struct MaliciousMock;
     impl AliasProvider for MaliciousMock {
         // --- 1. The Logic you care about ---
-        fn raw_set_macro(name: &str, value: Option<&str>) -> io::Result<bool> {
+        fn raw_set_macro(&self, name: &str, value: Option<&str>) -> io::Result<bool> {
             if name == "PROTECTED" && value.is_none() {
                 return Ok(false);
             }
             Ok(true)
         }
 
-        fn get_all_aliases(_: &Verbosity) -> io::Result<Vec<(String, String)>> {
+        fn get_all_aliases(&self, _: &Verbosity) -> io::Result<Vec<(String, String)>> {
             Ok(vec![
                 ("ls".into(), "dir".into()),
                 ("PROTECTED".into(), "secret".into()),
@@ -461,34 +461,34 @@ fn test_purge_stress_partial_failure() {
         // --- 2. The Updated Paperwork (Matching lib.rs Trait) ---
 
         // MATCH: &std::path::Path instead of &str
-        fn raw_reload_from_file(_v: &Verbosity,_: &std::path::Path) -> io::Result<()> { Ok(()) }
+        fn raw_reload_from_file(&self, _v: &Verbosity,_: &std::path::Path) -> io::Result<()> { Ok(()) }
 
-        fn write_autorun_registry(_cmd: &str, _v: &Verbosity) -> io::Result<()> { Ok(()) }
+        fn write_autorun_registry(&self, _cmd: &str, _v: &Verbosity) -> io::Result<()> { Ok(()) }
 
         // MATCH: Returns String directly, not Result<Option>
-        fn read_autorun_registry() -> String { String::new() }
+        fn read_autorun_registry(&self) -> String { String::new() }
 
         // MATCH: Returns Vec<String>
-        fn query_alias(_: &str, _: &Verbosity) -> Vec<String> { vec![] }
+        fn query_alias(&self, _: &str, _: &Verbosity) -> Vec<String> { vec![] }
 
         // MATCH: SetOptions and &Path
-        fn set_alias(_: SetOptions, _: &std::path::Path, _: &Verbosity) -> io::Result<()> { Ok(()) }
+        fn set_alias(&self, _: SetOptions, _: &std::path::Path, _: &Verbosity) -> io::Result<()> { Ok(()) }
 
         // MATCH: Result<(), Box<dyn Error>>
-        fn alias_show_all(_: &Verbosity) -> Result<(), Box<dyn std::error::Error>> { Ok(()) }
+        fn alias_show_all(&self, _: &Verbosity) -> Result<alias_lib::AuditReport, Box<dyn std::error::Error>> { Ok(alias_lib::AuditReport::default()) }
 
         // MATCH: &Path and Result<(), Box<dyn Error>>
-        fn run_diagnostics(_path: &std::path::Path, _verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> { Ok(()) }
+        fn run_diagnostics(&self, _path: &std::path::Path, _verbosity: &Verbosity) -> Result<(), Box<dyn std::error::Error>> { Ok(()) }
 
-        fn purge_ram_macros(v: &Verbosity) -> io::Result<PurgeReport> {
+        fn purge_ram_macros(&self, v: &Verbosity) -> io::Result<PurgeReport> {
             let mut report = PurgeReport::default();
 
             // 1. Get the aliases from the Mock's own provider
-            let aliases = Self::get_all_aliases(v)?;
+            let aliases = self.get_all_aliases(v)?;
 
             for (name, _) in aliases {
                 // 2. Try to "delete" via the Mock's own raw_set_macro
-                match Self::raw_set_macro(&name, None) {
+                match self.raw_set_macro(&name, None) {
                     Ok(true) => report.cleared.push(name),
                     _ => report.failed.push((name, 5)),
                 }
@@ -496,7 +496,7 @@ fn test_purge_stress_partial_failure() {
             Ok(report)
         }
 
-        fn get_version() -> &'static Versioning {
+        fn get_version(&self) -> &'static Versioning {
             static MOCK_VER: Versioning = Versioning {
                 lib: "MaliciousMock",
                 major: 6,
@@ -509,7 +509,7 @@ fn test_purge_stress_partial_failure() {
         }
     }
     // 2. Run the purge
-    let report = MaliciousMock::purge_ram_macros(&v).unwrap();
+    let report = MaliciousMock.purge_ram_macros(&v).unwrap();
 
     // 3. Validation
     assert!(report.cleared.contains(&"ls".to_string()));
@@ -523,10 +523,10 @@ fn test_set_and_clear_poisoned_alias() {
     let val = "echo boo\"";
 
     // 1. Set it (should include quotes in RAM)
-    P::raw_set_macro(name, Some(val)).expect("Should set poisoned alias");
+    P::default().raw_set_macro(name, Some(val)).expect("Should set poisoned alias");
 
     // 2. Clear it (The critical fix: passing the same quoted name should delete it)
-    let result = P::raw_set_macro(name, None).expect("Should delete poisoned alias");
+    let result = P::default().raw_set_macro(name, None).expect("Should delete poisoned alias");
     assert!(result, "Windows should report success for deletion of quoted name");
 }
 
@@ -541,11 +541,11 @@ fn test_xcd_trailing_quote_integrity() {
     let val = r#"for /f "delims=" %i in ('dir') do cd /d "%i""#;
 
     // 2. The Action: Direct API call via Provider (P)
-    P::raw_set_macro(name, Some(val))
+    P::default().raw_set_macro(name, Some(val))
         .expect("Win32 Kernel rejected the alias syntax");
 
     // 3. The Forensic Check: Did the API store it correctly?
-    let ram = P::get_all_aliases(&Verbosity::loud()).expect("Failed to read back from RAM");
+    let ram = P::default().get_all_aliases(&Verbosity::loud()).expect("Failed to read back from RAM");
 
     let (_, stored_val) = ram.iter()
         .find(|(n, _)| n == name)
@@ -559,15 +559,15 @@ fn test_xcd_trailing_quote_integrity() {
     );
 
     // Cleanup
-    let _ = P::raw_set_macro(name, None);
+    let _ = P::default().raw_set_macro(name, None);
 }
 
 
 #[test]
 fn test_alphanumeric_alias() {
     // Standard case should still work perfectly
-    P::raw_set_macro("standard", Some("echo hello")).expect("Should set standard alias");
-    P::raw_set_macro("standard", None).expect("Should clear standard alias");
+    P::default().raw_set_macro("standard", Some("echo hello")).expect("Should set standard alias");
+    P::default().raw_set_macro("standard", None).expect("Should clear standard alias");
 }
 
 
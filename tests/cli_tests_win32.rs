@@ -32,7 +32,7 @@ fn test_registry_append_logic() {
     let val_to_set: String = original_cmd.to_string();
     key.set_value(REG_AUTORUN_KEY, &val_to_set).unwrap();
 
-    P::write_autorun_registry(
+    P::default().write_autorun_registry(
         &format!("{} & alias --reload", original_cmd),
         &Verbosity::normal()
     ).expect("Install failed");
@@ -46,7 +46,7 @@ fn test_registry_append_logic() {
 #[serial]
 fn test_routine_setup_registration() {
     // Verifies the install_autorun branch in the provider
-    let _ = P::install_autorun(&Verbosity::silent(), "alias --startup");
+    let _ = P::default().install_autorun(&Verbosity::silent(), "alias --startup");
 }
 
 #[test]
@@ -54,10 +54,10 @@ fn test_routine_setup_registration() {
 fn test_win32_international_roundtrip_repeat() {
     let name = "λ_alias";
     let val = "echo lambda_power";
-    assert!(P::raw_set_macro(name, Some(val)).unwrap());
-    let all = P::get_all_aliases(&voice!(Silent, Off, Off)).unwrap(); // Add .unwrap()
+    assert!(P::default().raw_set_macro(name, Some(val)).unwrap());
+    let all = P::default().get_all_aliases(&voice!(Silent, Off, Off)).unwrap(); // Add .unwrap()
     let found = all.iter().find(|(n, _)| n == name);
     assert!(found.is_some());
     assert_eq!(found.unwrap().1, val);
-    P::raw_set_macro(name, None).unwrap();
+    P::default().raw_set_macro(name, None).unwrap();
 }
@@ -33,6 +33,11 @@ pub fn global_test_setup() {
         std::env::set_var("ALIAS_PATH", tmp.to_str().unwrap());
         std::env::set_var("ALIAS_FILE", "test.doskey");
     }
+    // Off Windows there is no APPDATA, so hand get_alias_path a fallback home.
+    #[cfg(not(windows))]
+    if std::env::var("APPDATA").is_err() {
+        unsafe { std::env::set_var("APPDATA", tmp.to_str().unwrap()); }
+    }
 }
 
 // 3. SHARED MOCK PROVIDER
@@ -45,6 +50,7 @@ pub fn get_captured_set() -> SetOptions {
 }
 
 #[allow(dead_code)]
+#[derive(Default)]
 pub struct MockProvider;
 // Create an alias to the actual provider being tested
 // This allows the test file to just refer to "P"
@@ -64,7 +70,7 @@ pub use alias_hybrid::WrapperLibraryInterface as P;
 #[allow(dead_code)]
 impl AliasProvider for MockProvider {
     // 1. ATOMIC HANDS
-    fn raw_set_macro(name: &str, value: Option<&str>) -> io::Result<bool> {
+    fn raw_set_macro(&self, name: &str, value: Option<&str>) -> io::Result<bool> {
         let mut ram = MOCK_RAM.lock().unwrap();
         if value.is_none() {
             ram.retain(|(k, _)| k != name);
@@ -75,53 +81,53 @@ impl AliasProvider for MockProvider {
     }
 
     // This now returns the ACTUAL state of your fake system
-    fn get_all_aliases(_: &Verbosity) -> io::Result<Vec<(String, String)>> {
+    fn get_all_aliases(&self, _: &Verbosity) -> io::Result<Vec<(String, String)>> {
         let ram = MOCK_RAM.lock().unwrap();
         Ok(ram.clone())
     }
     // MATCH: Path instead of str
-    fn raw_reload_from_file(_: &Verbosity, _: &std::path::Path) -> io::Result<()> { Ok(()) }
-    fn reload_full(_verbosity: &Verbosity, _file_path: &Path, _force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    fn raw_reload_from_file(&self, _: &Verbosity, _: &std::path::Path) -> io::Result<()> { Ok(()) }
+    fn reload_full(&self, _verbosity: &Verbosity, _file_path: &Path, _force: bool) -> Result<(), Box<dyn std::error::Error>> {
         Ok(())
     }
-    fn write_autorun_registry(_: &str, _: &Verbosity) -> io::Result<()> { Ok(()) }
-    fn purge_ram_macros(v: &Verbosity) -> Result<PurgeReport, std::io::Error> {
+    fn write_autorun_registry(&self, _: &str, _: &Verbosity) -> io::Result<()> { Ok(()) }
+    fn purge_ram_macros(&self, v: &Verbosity) -> Result<PurgeReport, std::io::Error> {
         let mut report = PurgeReport::default();
-        let aliases = Self::get_all_aliases(v)?;
+        let aliases = self.get_all_aliases(v)?;
         for (name, _) in aliases {
-            Self::raw_set_macro(&name, None)?;
+            self.raw_set_macro(&name, None)?;
             report.cleared.push(name);
         }
         Ok(report)
     }
-    fn purge_file_macros(_: &Verbosity, _: &Path) -> Result<PurgeReport, std::io::Error> { Ok(PurgeReport::default()) }
+    fn purge_file_macros(&self, _: &Verbosity, _: &Path) -> Result<PurgeReport, std::io::Error> { Ok(PurgeReport::default()) }
     // MATCH: Returns String directly, not Result
-    fn read_autorun_registry() -> String { String::new() }
+    fn read_autorun_registry(&self) -> String { String::new() }
 
     // 2. REQUIRED TRAIT METHODS
     // MATCH: Returns Vec<String>, not Result
-    fn query_alias(_: &str, _: &Verbosity) -> Vec<String> { vec![] }
+    fn query_alias(&self, _: &str, _: &Verbosity) -> Vec<String> { vec![] }
 
     // MATCH: Param 1 is SetOptions, Param 2 is &Path
-    fn set_alias(opts: SetOptions, _path: &Path, _v: &Verbosity) -> io::Result<()> {
+    fn set_alias(&self, opts: SetOptions, _path: &Path, _v: &Verbosity) -> io::Result<()> {
         let mut call = LAST_CALL.lock().unwrap();
         *call = Some(opts); // This records the work dispatch did
         Ok(())
     }
 
     // MATCH: &Path and Result<(), Box<dyn Error>>
-    fn run_diagnostics(_: &std::path::Path, v: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
+    fn run_diagnostics(&self, _: &std::path::Path, v: &Verbosity) -> Result<(), Box<dyn std::error::Error>> {
         // If the test expects to see "WRITABLE", the provider MUST write it!
         v.say("✅ WRITABLE");
         Ok(())
     }
 
     // MATCH: Result<(), Box<dyn Error>>
-    fn alias_show_all(_: &Verbosity) -> Result<(), Box<dyn std::error::Error>> { Ok(()) }
+    fn alias_show_all(&self, _: &Verbosity) -> Result<alias_lib::AuditReport, Box<dyn std::error::Error>> { Ok(alias_lib::AuditReport::default()) }
 
-    fn install_autorun(_v: &Verbosity, _payload: &str) -> io::Result<()> { Ok(()) }
+    fn install_autorun(&self, _v: &Verbosity, _payload: &str) -> io::Result<()> { Ok(()) }
 
-    fn provider_type() -> ProviderType {
+    fn provider_type(&self) -> ProviderType {
         if cfg!(feature = "identity_wrapper") {
             ProviderType::Wrapper
         } else if cfg!(feature = "identity_hybrid") {
@@ -132,7 +138,7 @@ impl AliasProvider for MockProvider {
             ProviderType::NotLinked
         }
     }
-    fn get_version() -> &'static Versioning {
+    fn get_version(&self) -> &'static Versioning {
         static MOCK_VER: Versioning = Versioning {
             lib: "Mock",
             major: 0,